@@ -0,0 +1,34 @@
+// Hands the selection or page to the desktop app's native-messaging host.
+// Install the host with: le-chat native-host <firefox|chrome|chromium> <extension-id> --install
+const HOST = 'ai.mistral.lechat';
+const api = globalThis.browser ?? globalThis.chrome;
+
+const MENUS = [
+  { id: 'selection-launcher', title: 'Ask Le Chat about “%s”', contexts: ['selection'], target: 'launcher' },
+  { id: 'selection-new-chat', title: 'Send selection to a new Le Chat', contexts: ['selection'], target: 'new_chat' },
+  { id: 'page-launcher', title: 'Ask Le Chat about this page', contexts: ['page', 'link'], target: 'launcher' },
+];
+
+api.runtime.onInstalled.addListener(() => {
+  for (const { id, title, contexts } of MENUS) {
+    api.contextMenus.create({ id, title, contexts });
+  }
+});
+
+api.contextMenus.onClicked.addListener(async (info, tab) => {
+  const menu = MENUS.find(m => m.id === info.menuItemId);
+  if (!menu) return;
+  const message = {
+    action: 'send',
+    text: info.selectionText || '',
+    url: info.linkUrl || info.pageUrl || tab?.url || '',
+    title: info.linkUrl ? '' : tab?.title || '',
+    target: menu.target,
+  };
+  try {
+    const reply = await api.runtime.sendNativeMessage(HOST, message);
+    if (!reply?.ok) console.error('Le Chat refused the message:', reply?.error);
+  } catch (error) {
+    console.error('Le Chat desktop app is not reachable:', error);
+  }
+});
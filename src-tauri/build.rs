@@ -1,3 +1,49 @@
+// Every command in `generate_handler!`. Listing them makes the app's commands
+// subject to the capabilities in `capabilities/`, through the generated
+// `allow-<command>` permissions, instead of being open to every window.
+const COMMANDS: &[&str] = &[
+    "hide_launcher",
+    "show_launcher",
+    "toggle_launcher",
+    "set_launcher_expanded",
+    "show_main_window",
+    "submit_message",
+    "get_submissions",
+    "get_submission_queue",
+    "cancel_submission",
+    "clear_submission_queue",
+    "get_outbox",
+    "resend_outbox_item",
+    "discard_outbox_item",
+    "get_main_state",
+    "get_last_response",
+    "navigate_to_chat",
+    "navigate_to_offline",
+    "get_settings",
+    "get_control_socket_path",
+    "take_link_prompt",
+    "save_settings",
+    "has_api_key",
+    "save_api_key",
+    "delete_api_key",
+    "list_api_models",
+    "get_shortcut_failures",
+    "get_page_scripts",
+    "show_settings",
+    "switch_profile",
+    "report_dom_ready",
+    "report_heartbeat",
+    "report_inject_result",
+    "report_response_progress",
+    "report_response_complete",
+    "report_response_failed",
+    "report_conversation_snapshot",
+];
+
 fn main() {
-    tauri_build::build()
+    tauri_build::try_build(
+        tauri_build::Attributes::new()
+            .app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+    )
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,655 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::pipeline::{self, AppSession, SessionError};
+
+// `le-chat batch`: send each prompt of a JSONL file to a fresh conversation,
+// one at a time, and collect the answers into a Markdown or JSONL report.
+//
+// Prompts go through the same `ask-connect` session as `le-chat ask --wait`,
+// so the app types them into Le Chat and reads the answers back from the page.
+// Every finished prompt is appended to a progress journal; running the same
+// command again skips the prompts already answered and retries the rest.
+
+// Pause between prompts unless `--delay` says otherwise
+pub const DEFAULT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Jsonl,
+}
+
+impl ReportFormat {
+    // `.jsonl` reports are JSON lines; anything else is Markdown
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("jsonl") => ReportFormat::Jsonl,
+            _ => ReportFormat::Markdown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchOptions {
+    pub input: PathBuf,
+    pub out: PathBuf,
+    // Pause between one answer and the next prompt
+    pub delay: Duration,
+    // How long each answer may take
+    pub timeout: Duration,
+    // Ignore earlier progress and ask every prompt again
+    pub restart: bool,
+}
+
+// One line of the input file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPrompt {
+    pub id: String,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Answered,
+    Failed,
+}
+
+// The outcome of one prompt, as written to the journal and JSONL reports
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BatchRecord {
+    pub id: String,
+    pub prompt: String,
+    pub status: BatchStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // RFC 3339, local time
+    pub started_at: String,
+    pub duration_ms: u64,
+}
+
+// Parse the input: one prompt per line, either a JSON string or an object
+// with `prompt` and an optional `id` (the line number by default)
+pub fn parse_prompts(text: &str) -> Result<Vec<BatchPrompt>, String> {
+    let mut prompts: Vec<BatchPrompt> = Vec::new();
+    let mut ids = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", number, e))?;
+        let (id, prompt) = match &value {
+            serde_json::Value::String(prompt) => (number.to_string(), prompt.clone()),
+            serde_json::Value::Object(entry) => {
+                let prompt = entry
+                    .get("prompt")
+                    .and_then(|prompt| prompt.as_str())
+                    .ok_or_else(|| format!("line {}: missing \"prompt\"", number))?;
+                let id = match entry.get("id") {
+                    None | Some(serde_json::Value::Null) => number.to_string(),
+                    Some(serde_json::Value::String(id)) => id.clone(),
+                    Some(serde_json::Value::Number(id)) => id.to_string(),
+                    Some(_) => {
+                        return Err(format!(
+                            "line {}: \"id\" must be a string or number",
+                            number
+                        ))
+                    }
+                };
+                (id, prompt.to_string())
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected a prompt string or an object with \"prompt\"",
+                    number
+                ))
+            }
+        };
+        if prompt.trim().is_empty() {
+            return Err(format!("line {}: the prompt is empty", number));
+        }
+        if !ids.insert(id.clone()) {
+            return Err(format!("line {}: id \"{}\" is used twice", number, id));
+        }
+        prompts.push(BatchPrompt { id, prompt });
+    }
+    if prompts.is_empty() {
+        return Err("no prompts in the input".to_string());
+    }
+    Ok(prompts)
+}
+
+// Where progress is kept: the report itself when it is JSONL
+pub fn journal_path(out: &Path, format: ReportFormat) -> PathBuf {
+    match format {
+        ReportFormat::Jsonl => out.to_path_buf(),
+        ReportFormat::Markdown => {
+            let mut name = out.as_os_str().to_os_string();
+            name.push(".progress.jsonl");
+            PathBuf::from(name)
+        }
+    }
+}
+
+// The latest record for each id. Lines that can't be read are skipped, so a
+// line cut short by an interruption doesn't stop a resume.
+pub fn decode_journal(text: &str) -> HashMap<String, BatchRecord> {
+    let mut records = HashMap::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<BatchRecord>(line) {
+            Ok(record) => {
+                records.insert(record.id.clone(), record);
+            }
+            Err(e) => eprintln!("le-chat: skipping unreadable progress line: {}", e),
+        }
+    }
+    records
+}
+
+// "850ms", "12.3s" or "3m 07s"
+fn format_duration(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+// Quote every line so a prompt's own Markdown can't break the report
+fn blockquote(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// The Markdown report, with the finished prompts in input order
+pub fn render_markdown(source: &str, total: usize, records: &[&BatchRecord]) -> String {
+    let answered = records
+        .iter()
+        .filter(|record| record.status == BatchStatus::Answered)
+        .count();
+    let failed = records.len() - answered;
+    let time: u64 = records.iter().map(|record| record.duration_ms).sum();
+    let mut report = format!(
+        "# Batch results: {}\n\n{} of {} answered, {} failed, {} in total\n",
+        source,
+        answered,
+        total,
+        failed,
+        format_duration(time)
+    );
+    for record in records {
+        report.push_str(&format!(
+            "\n## {}\n\n{}\n\n",
+            record.id,
+            blockquote(&record.prompt)
+        ));
+        match (&record.answer, &record.error) {
+            (Some(answer), _) => report.push_str(&format!(
+                "*Answered in {} · started {}*\n\n{}\n",
+                format_duration(record.duration_ms),
+                record.started_at,
+                answer.trim_end()
+            )),
+            (None, error) => report.push_str(&format!(
+                "**Failed** after {} · started {}: {}\n",
+                format_duration(record.duration_ms),
+                record.started_at,
+                error.as_deref().unwrap_or("unknown error")
+            )),
+        }
+    }
+    report
+}
+
+// Answers prompts; the app in practice, a script in tests
+pub trait Asker {
+    fn ask(&mut self, prompt: &str, timeout: Duration) -> Result<String, String>;
+}
+
+// Asks the running app over an `ask-connect` session, reconnecting after a
+// timeout or a lost connection
+#[derive(Default)]
+pub struct AppAsker {
+    session: Option<AppSession>,
+}
+
+impl Asker for AppAsker {
+    fn ask(&mut self, prompt: &str, timeout: Duration) -> Result<String, String> {
+        let session = match self.session.as_mut() {
+            Some(session) => session,
+            None => self
+                .session
+                .insert(AppSession::open().map_err(|e| e.to_string())?),
+        };
+        let result = session.ask(prompt, true, timeout, |_| {});
+        if !matches!(&result, Ok(_) | Err(SessionError::Failed(_))) {
+            self.session = None;
+        }
+        result.map_err(|e| match e {
+            SessionError::Timeout => format!("no answer after {}s", timeout.as_secs()),
+            SessionError::Failed(message) => message,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub answered: usize,
+    pub failed: usize,
+    // Already answered in an earlier run
+    pub skipped: usize,
+}
+
+fn append_record(path: &Path, record: &BatchRecord) -> Result<(), String> {
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+// Rewrite the journal with one line per prompt, in input order
+fn write_journal(path: &Path, records: &[&BatchRecord]) -> Result<(), String> {
+    let mut text = String::new();
+    for record in records {
+        text.push_str(&serde_json::to_string(record).map_err(|e| e.to_string())?);
+        text.push('\n');
+    }
+    std::fs::write(path, text).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+// Send the prompts that still need an answer. `log` gets a line per prompt.
+pub fn run(
+    options: &BatchOptions,
+    asker: &mut impl Asker,
+    log: &mut impl Write,
+) -> Result<BatchSummary, String> {
+    let input = std::fs::read_to_string(&options.input)
+        .map_err(|e| format!("failed to read {}: {}", options.input.display(), e))?;
+    let prompts =
+        parse_prompts(&input).map_err(|e| format!("{}: {}", options.input.display(), e))?;
+    let format = ReportFormat::for_path(&options.out);
+    let journal = journal_path(&options.out, format);
+
+    let mut records = if options.restart {
+        HashMap::new()
+    } else {
+        match std::fs::read_to_string(&journal) {
+            Ok(text) => decode_journal(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("failed to read {}: {}", journal.display(), e)),
+        }
+    };
+    // Results for prompts that have since been edited don't count
+    records.retain(|id, record| {
+        prompts
+            .iter()
+            .any(|p| p.id == *id && p.prompt == record.prompt)
+    });
+    write_journal(&journal, &in_order(&prompts, &records))?;
+
+    let source = options
+        .input
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut summary = BatchSummary::default();
+    let mut asked_any = false;
+    for (index, prompt) in prompts.iter().enumerate() {
+        let position = format!("[{}/{}] {}", index + 1, prompts.len(), prompt.id);
+        if records
+            .get(&prompt.id)
+            .is_some_and(|record| record.status == BatchStatus::Answered)
+        {
+            summary.skipped += 1;
+            let _ = writeln!(log, "{}: already answered", position);
+            continue;
+        }
+        if asked_any && !options.delay.is_zero() {
+            std::thread::sleep(options.delay);
+        }
+        asked_any = true;
+
+        let started_at = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        let started = Instant::now();
+        let result = asker.ask(&prompt.prompt, options.timeout);
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (status, answer, error) = match result {
+            Ok(answer) => (BatchStatus::Answered, Some(answer), None),
+            Err(error) => (BatchStatus::Failed, None, Some(error)),
+        };
+        let _ = match &error {
+            None => writeln!(
+                log,
+                "{}: answered in {}",
+                position,
+                format_duration(duration_ms)
+            ),
+            Some(error) => writeln!(log, "{}: failed: {}", position, error),
+        };
+        match status {
+            BatchStatus::Answered => summary.answered += 1,
+            BatchStatus::Failed => summary.failed += 1,
+        }
+        let record = BatchRecord {
+            id: prompt.id.clone(),
+            prompt: prompt.prompt.clone(),
+            status,
+            answer,
+            error,
+            started_at,
+            duration_ms,
+        };
+        append_record(&journal, &record)?;
+        records.insert(record.id.clone(), record);
+
+        // Keep the report current, so an interrupted run still leaves one
+        if format == ReportFormat::Markdown {
+            let report = render_markdown(&source, prompts.len(), &in_order(&prompts, &records));
+            std::fs::write(&options.out, report)
+                .map_err(|e| format!("failed to write {}: {}", options.out.display(), e))?;
+        }
+    }
+
+    let finished = in_order(&prompts, &records);
+    write_journal(&journal, &finished)?;
+    if format == ReportFormat::Markdown {
+        std::fs::write(
+            &options.out,
+            render_markdown(&source, prompts.len(), &finished),
+        )
+        .map_err(|e| format!("failed to write {}: {}", options.out.display(), e))?;
+    }
+    Ok(summary)
+}
+
+fn in_order<'a>(
+    prompts: &[BatchPrompt],
+    records: &'a HashMap<String, BatchRecord>,
+) -> Vec<&'a BatchRecord> {
+    prompts
+        .iter()
+        .filter_map(|prompt| records.get(&prompt.id))
+        .collect()
+}
+
+// `le-chat batch`, run in the caller's process. Returns the exit code.
+pub fn run_client(options: BatchOptions) -> i32 {
+    match run(&options, &mut AppAsker::default(), &mut std::io::stderr()) {
+        Ok(summary) => {
+            eprintln!(
+                "le-chat: {} answered, {} failed, {} already answered; report in {}",
+                summary.answered,
+                summary.failed,
+                summary.skipped,
+                options.out.display()
+            );
+            if summary.failed > 0 {
+                pipeline::EXIT_FAILED
+            } else {
+                0
+            }
+        }
+        Err(e) => {
+            eprintln!("le-chat: {}", e);
+            pipeline::EXIT_FAILED
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answers by echoing, failing the prompts it's told to
+    struct Script {
+        fail: Vec<&'static str>,
+        asked: Vec<String>,
+    }
+
+    impl Asker for Script {
+        fn ask(&mut self, prompt: &str, _timeout: Duration) -> Result<String, String> {
+            self.asked.push(prompt.to_string());
+            if self.fail.contains(&prompt) {
+                return Err("Le Chat is offline".to_string());
+            }
+            Ok(format!("Answer to *{}*", prompt))
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("le-chat-batch-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn options(dir: &Path, out: &str) -> BatchOptions {
+        BatchOptions {
+            input: dir.join("prompts.jsonl"),
+            out: dir.join(out),
+            delay: Duration::ZERO,
+            timeout: Duration::from_secs(5),
+            restart: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_prompts() {
+        let prompts = parse_prompts(
+            "\"plain\"\n\n{\"id\": \"short\", \"prompt\": \"Be brief\"}\n{\"id\": 7, \"prompt\": \"x\", \"variant\": \"b\"}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            prompts,
+            [
+                BatchPrompt {
+                    id: "1".to_string(),
+                    prompt: "plain".to_string()
+                },
+                BatchPrompt {
+                    id: "short".to_string(),
+                    prompt: "Be brief".to_string()
+                },
+                BatchPrompt {
+                    id: "7".to_string(),
+                    prompt: "x".to_string()
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse_prompts("\"a\"\n{\"id\": \"1\", \"prompt\": \"b\"}"),
+            Err("line 2: id \"1\" is used twice".to_string())
+        );
+        assert!(parse_prompts("{\"text\": \"a\"}")
+            .unwrap_err()
+            .contains("missing \"prompt\""));
+        assert!(parse_prompts("not json")
+            .unwrap_err()
+            .starts_with("line 1:"));
+        assert!(parse_prompts("\"  \"").is_err());
+        assert!(parse_prompts("\n").is_err());
+    }
+
+    #[test]
+    fn test_report_paths_and_formats() {
+        assert_eq!(
+            ReportFormat::for_path(Path::new("out.JSONL")),
+            ReportFormat::Jsonl
+        );
+        assert_eq!(
+            ReportFormat::for_path(Path::new("results.md")),
+            ReportFormat::Markdown
+        );
+        assert_eq!(
+            journal_path(Path::new("results.md"), ReportFormat::Markdown),
+            PathBuf::from("results.md.progress.jsonl")
+        );
+        assert_eq!(
+            journal_path(Path::new("out.jsonl"), ReportFormat::Jsonl),
+            PathBuf::from("out.jsonl")
+        );
+        assert_eq!(format_duration(850), "850ms");
+        assert_eq!(format_duration(12_345), "12.3s");
+        assert_eq!(format_duration(187_000), "3m 07s");
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let answered = BatchRecord {
+            id: "short".to_string(),
+            prompt: "Be brief\n\n# not a heading".to_string(),
+            status: BatchStatus::Answered,
+            answer: Some("Sure.\n".to_string()),
+            error: None,
+            started_at: "2026-10-18T09:00:00+02:00".to_string(),
+            duration_ms: 2_500,
+        };
+        let failed = BatchRecord {
+            id: "long".to_string(),
+            prompt: "Go on".to_string(),
+            status: BatchStatus::Failed,
+            answer: None,
+            error: Some("no answer after 300s".to_string()),
+            started_at: "2026-10-18T09:00:05+02:00".to_string(),
+            duration_ms: 300_000,
+        };
+        let report = render_markdown("prompts.jsonl", 3, &[&answered, &failed]);
+        assert!(report.starts_with(
+            "# Batch results: prompts.jsonl\n\n1 of 3 answered, 1 failed, 5m 02s in total\n"
+        ));
+        assert!(report.contains(
+            "## short\n\n> Be brief\n>\n> # not a heading\n\n*Answered in 2.5s · started 2026-10-18T09:00:00+02:00*\n\nSure.\n"
+        ));
+        assert!(report.contains(
+            "**Failed** after 5m 00s · started 2026-10-18T09:00:05+02:00: no answer after 300s\n"
+        ));
+    }
+
+    #[test]
+    fn test_run_resumes_where_it_stopped() {
+        let dir = temp_dir("resume");
+        std::fs::write(
+            dir.join("prompts.jsonl"),
+            "\"one\"\n\"two\"\n{\"id\": \"three\", \"prompt\": \"three\"}\n",
+        )
+        .unwrap();
+        let options = options(&dir, "results.md");
+
+        let mut script = Script {
+            fail: vec!["two"],
+            asked: Vec::new(),
+        };
+        let summary = run(&options, &mut script, &mut Vec::new()).unwrap();
+        assert_eq!(
+            summary,
+            BatchSummary {
+                answered: 2,
+                failed: 1,
+                skipped: 0
+            }
+        );
+        assert_eq!(script.asked, ["one", "two", "three"]);
+        let report = std::fs::read_to_string(&options.out).unwrap();
+        assert!(report.contains("2 of 3 answered, 1 failed"), "{}", report);
+        assert!(report.contains("Le Chat is offline"));
+
+        // Only the failed prompt is asked again
+        let mut script = Script {
+            fail: vec![],
+            asked: Vec::new(),
+        };
+        let mut log = Vec::new();
+        let summary = run(&options, &mut script, &mut log).unwrap();
+        assert_eq!(
+            summary,
+            BatchSummary {
+                answered: 1,
+                failed: 0,
+                skipped: 2
+            }
+        );
+        assert_eq!(script.asked, ["two"]);
+        assert!(String::from_utf8(log)
+            .unwrap()
+            .contains("[1/3] 1: already answered"));
+        let report = std::fs::read_to_string(&options.out).unwrap();
+        assert!(report.contains("3 of 3 answered, 0 failed"), "{}", report);
+        let ids: Vec<_> = report
+            .lines()
+            .filter(|line| line.starts_with("## "))
+            .collect();
+        assert_eq!(ids, ["## 1", "## 2", "## three"]);
+
+        // An edited prompt counts as new; --restart asks everything
+        std::fs::write(dir.join("prompts.jsonl"), "\"one\"\n\"two, edited\"\n").unwrap();
+        let mut script = Script {
+            fail: vec![],
+            asked: Vec::new(),
+        };
+        run(&options, &mut script, &mut Vec::new()).unwrap();
+        assert_eq!(script.asked, ["two, edited"]);
+        let mut script = Script {
+            fail: vec![],
+            asked: Vec::new(),
+        };
+        run(
+            &BatchOptions {
+                restart: true,
+                ..options
+            },
+            &mut script,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(script.asked, ["one", "two, edited"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_jsonl_report_is_the_journal() {
+        let dir = temp_dir("jsonl");
+        std::fs::write(dir.join("prompts.jsonl"), "\"one\"\n\"two\"\n").unwrap();
+        let options = options(&dir, "results.jsonl");
+        // A line cut short when the last run was interrupted
+        std::fs::write(&options.out, "{\"id\": \"1\", \"prompt\": \"one\", \"status\": \"answered\", \"answer\": \"old\", \"started_at\": \"\", \"duration_ms\": 1}\n{\"id\": \"2\", \"pro").unwrap();
+
+        let mut script = Script {
+            fail: vec![],
+            asked: Vec::new(),
+        };
+        run(&options, &mut script, &mut Vec::new()).unwrap();
+        assert_eq!(script.asked, ["two"]);
+        let text = std::fs::read_to_string(&options.out).unwrap();
+        let records: Vec<BatchRecord> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].answer.as_deref(), Some("old"));
+        assert_eq!(records[1].answer.as_deref(), Some("Answer to *two*"));
+        assert!(!dir.join("results.jsonl.progress.jsonl").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
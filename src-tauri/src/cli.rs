@@ -0,0 +1,420 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::batch::{self, BatchOptions};
+use crate::deep_link::{self, DeepLink};
+use crate::native_host::{self, Browser};
+use crate::pipeline::{self, AskOptions};
+
+// Control verbs accepted on the command line. A second `le-chat` process
+// forwards its arguments to the running instance through the single-instance
+// plugin, so these can be bound in a window manager or used from scripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    ToggleLauncher,
+    NewChat,
+    Show,
+    // Open a conversation in the main window
+    Open(String),
+    Settings,
+    Quit,
+    // A lechat:// link handed over by the OS
+    Link(DeepLink),
+    // Internal: connect back to a waiting `le-chat ask` and answer its prompt
+    AskConnect { addr: SocketAddr, token: String },
+}
+
+pub const USAGE: &str = "\
+Usage: le-chat [COMMAND]
+
+Without a command, starts Le Chat or shows the running instance. A lechat://
+link as the only argument is handled like a click on it.
+
+Commands:
+  toggle-launcher   Show or hide the quick launcher
+  new-chat          Open the main window on a fresh conversation
+  show              Bring the main window to the front
+  open <url>        Open a conversation (https://chat.mistral.ai/...) in the main window
+  settings          Open the settings window
+  quit              Quit the running instance
+  ask [OPTIONS] [PROMPT...]
+                    Send a prompt, followed by anything piped to stdin, and
+                    show it in the main window
+      --wait            Print the answer as Markdown instead, as Le Chat writes it
+      --continue        Ask in the current conversation instead of a new one
+      --timeout <secs>  How long --wait waits for the answer (default 300)
+  batch <prompts.jsonl> --out <report.md|report.jsonl> [OPTIONS]
+                    Ask each prompt in a new chat, one at a time, and write the
+                    answers to a report. Run it again to resume.
+      --delay <secs>    Pause between prompts (default 5)
+      --timeout <secs>  How long each answer may take (default 300)
+      --restart         Ignore earlier progress and ask every prompt again
+  mcp               Serve the Model Context Protocol on stdin/stdout for local agents
+  native-host <firefox|chrome|chromium> <extension-id> [--install]
+                    Print the browser's native-messaging host manifest for an
+                    extension, or install it for the current user
+  help              Print this message";
+
+// What the command line asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invocation {
+    // Plain launch
+    Launch,
+    Command(CliCommand),
+    // Handled by the process that was started, which waits for the answer
+    Ask(AskOptions),
+    // Also run in the process that was started, asking one prompt at a time
+    Batch(BatchOptions),
+    // MCP server on stdio, forwarding to the running instance
+    Mcp,
+    // Started by a browser as its native-messaging host
+    NativeHost,
+    NativeHostManifest {
+        browser: Browser,
+        extension_id: String,
+        install: bool,
+    },
+    Help,
+}
+
+// Only conversations on Le Chat itself can be opened
+fn parse_conversation_url(raw: &str) -> Result<String, String> {
+    let url = tauri::Url::parse(raw).map_err(|e| format!("invalid URL '{}': {}", raw, e))?;
+    if url.scheme() != "https" || url.host_str() != Some("chat.mistral.ai") {
+        return Err(format!(
+            "'{}' is not a Le Chat URL (https://chat.mistral.ai/...)",
+            raw
+        ));
+    }
+    Ok(url.to_string())
+}
+
+fn parse_ask(args: &[&str]) -> Result<AskOptions, String> {
+    let mut options = AskOptions {
+        prompt: String::new(),
+        wait: false,
+        new_chat: true,
+        timeout: pipeline::DEFAULT_ANSWER_TIMEOUT,
+    };
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--wait" => options.wait = true,
+            "--continue" => options.new_chat = false,
+            "--timeout" => options.timeout = parse_seconds(arg, args.next(), false)?,
+            "--" => {
+                words.extend(args.by_ref());
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option '{}' for 'ask'", flag));
+            }
+            word => words.push(word),
+        }
+    }
+    options.prompt = words.join(" ");
+    Ok(options)
+}
+
+// A number of seconds following `flag`; zero only where `allow_zero`
+fn parse_seconds(flag: &str, value: Option<&&str>, allow_zero: bool) -> Result<Duration, String> {
+    value
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|&secs| allow_zero || secs > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("'{}' takes a number of seconds", flag))
+}
+
+fn parse_batch(args: &[&str]) -> Result<BatchOptions, String> {
+    let mut input = None;
+    let mut out = None;
+    let mut delay = batch::DEFAULT_DELAY;
+    let mut timeout = pipeline::DEFAULT_ANSWER_TIMEOUT;
+    let mut restart = false;
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--out" => {
+                let path = args.next().ok_or("'--out' takes a report path")?;
+                out = Some(PathBuf::from(path));
+            }
+            "--delay" => delay = parse_seconds(arg, args.next(), true)?,
+            "--timeout" => timeout = parse_seconds(arg, args.next(), false)?,
+            "--restart" => restart = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option '{}' for 'batch'", flag));
+            }
+            path if input.is_none() => input = Some(PathBuf::from(path)),
+            _ => return Err("'batch' takes one prompts file".to_string()),
+        }
+    }
+    Ok(BatchOptions {
+        input: input.ok_or("'batch' needs a prompts file (JSON lines)")?,
+        out: out.ok_or("'batch' needs '--out <report>'")?,
+        delay,
+        timeout,
+        restart,
+    })
+}
+
+// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> Result<Invocation, String> {
+    // Browsers pass their own arguments, not a verb
+    if native_host::is_host_launch(args) {
+        return Ok(Invocation::NativeHost);
+    }
+    // macOS adds a process serial number when launched from Finder
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| !arg.starts_with("-psn_"))
+        .collect();
+
+    let Some((&verb, rest)) = args.split_first() else {
+        return Ok(Invocation::Launch);
+    };
+    if deep_link::is_deep_link(verb) {
+        if !rest.is_empty() {
+            return Err("a lechat:// link must be the only argument".to_string());
+        }
+        return Ok(Invocation::Command(CliCommand::Link(deep_link::parse(
+            verb,
+        )?)));
+    }
+    let command =
+        match (verb, rest) {
+            ("help" | "--help" | "-h", []) => return Ok(Invocation::Help),
+            ("toggle-launcher", []) => CliCommand::ToggleLauncher,
+            ("new-chat", []) => CliCommand::NewChat,
+            ("show", []) => CliCommand::Show,
+            ("open", [url]) => CliCommand::Open(parse_conversation_url(url)?),
+            ("open", _) => return Err("'open' takes exactly one conversation URL".to_string()),
+            ("settings", []) => CliCommand::Settings,
+            ("quit", []) => CliCommand::Quit,
+            ("ask", rest) => return parse_ask(rest).map(Invocation::Ask),
+            ("batch", rest) => return parse_batch(rest).map(Invocation::Batch),
+            ("mcp", []) => return Ok(Invocation::Mcp),
+            ("native-host", [browser, extension_id, install @ ..])
+                if matches!(install, [] | ["--install"]) =>
+            {
+                return Ok(Invocation::NativeHostManifest {
+                    browser: Browser::parse(browser)?,
+                    extension_id: extension_id.to_string(),
+                    install: !install.is_empty(),
+                })
+            }
+            ("native-host", _) => return Err(
+                "usage: le-chat native-host <firefox|chrome|chromium> <extension-id> [--install]"
+                    .to_string(),
+            ),
+            ("ask-connect", [addr, token]) => CliCommand::AskConnect {
+                addr: pipeline::parse_reply_address(addr)?,
+                token: token.to_string(),
+            },
+            (
+                "help" | "--help" | "-h" | "toggle-launcher" | "new-chat" | "show" | "settings"
+                | "quit",
+                _,
+            ) => return Err(format!("'{}' doesn't take arguments", verb)),
+            _ => return Err(format!("unknown command '{}'", verb)),
+        };
+    Ok(Invocation::Command(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_arguments_is_a_plain_launch() {
+        assert_eq!(parse(&[]), Ok(Invocation::Launch));
+        assert_eq!(parse(&args(&["-psn_0_12345"])), Ok(Invocation::Launch));
+    }
+
+    #[test]
+    fn test_verbs() {
+        let cases = [
+            ("toggle-launcher", CliCommand::ToggleLauncher),
+            ("new-chat", CliCommand::NewChat),
+            ("show", CliCommand::Show),
+            ("settings", CliCommand::Settings),
+            ("quit", CliCommand::Quit),
+        ];
+        for (verb, command) in cases {
+            assert_eq!(parse(&args(&[verb])), Ok(Invocation::Command(command)));
+        }
+        assert_eq!(parse(&args(&["--help"])), Ok(Invocation::Help));
+    }
+
+    #[test]
+    fn test_open_requires_a_le_chat_url() {
+        assert_eq!(
+            parse(&args(&["open", "https://chat.mistral.ai/chat/abc-123"])),
+            Ok(Invocation::Command(CliCommand::Open(
+                "https://chat.mistral.ai/chat/abc-123".to_string()
+            )))
+        );
+        assert!(parse(&args(&["open"])).is_err());
+        assert!(parse(&args(&["open", "https://example.com/chat"])).is_err());
+        assert!(parse(&args(&["open", "http://chat.mistral.ai/chat"])).is_err());
+        assert!(parse(&args(&["open", "not a url"])).is_err());
+    }
+
+    #[test]
+    fn test_unknown_verbs_and_extra_arguments_are_errors() {
+        assert_eq!(
+            parse(&args(&["frobnicate"])),
+            Err("unknown command 'frobnicate'".to_string())
+        );
+        assert!(parse(&args(&["show", "now"])).is_err());
+        assert!(parse(&args(&["open", "https://chat.mistral.ai/chat", "x"])).is_err());
+        assert!(parse(&args(&["ask-connect", "127.0.0.1:4000"])).is_err());
+    }
+
+    #[test]
+    fn test_deep_links() {
+        assert_eq!(
+            parse(&args(&["lechat://settings"])),
+            Ok(Invocation::Command(CliCommand::Link(DeepLink::Settings)))
+        );
+        assert!(parse(&args(&["lechat://settings", "show"])).is_err());
+        assert!(parse(&args(&["lechat://launch-missiles"])).is_err());
+    }
+
+    #[test]
+    fn test_ask_options() {
+        let Ok(Invocation::Ask(options)) = parse(&args(&[
+            "ask",
+            "--wait",
+            "review",
+            "this",
+            "--timeout",
+            "60",
+            "--continue",
+        ])) else {
+            panic!("expected an ask invocation");
+        };
+        assert_eq!(
+            options,
+            AskOptions {
+                prompt: "review this".to_string(),
+                wait: true,
+                new_chat: false,
+                timeout: Duration::from_secs(60),
+            }
+        );
+
+        let Ok(Invocation::Ask(options)) = parse(&args(&["ask", "--", "--wait", "is a flag"]))
+        else {
+            panic!("expected an ask invocation");
+        };
+        assert_eq!(options.prompt, "--wait is a flag");
+        assert!(!options.wait);
+
+        // The prompt may come entirely from stdin
+        assert!(matches!(parse(&args(&["ask"])), Ok(Invocation::Ask(_))));
+        assert!(parse(&args(&["ask", "--timeout"])).is_err());
+        assert!(parse(&args(&["ask", "--timeout", "0", "x"])).is_err());
+        assert!(parse(&args(&["ask", "--quiet", "x"])).is_err());
+    }
+
+    #[test]
+    fn test_native_host_options() {
+        assert_eq!(
+            parse(&args(&[
+                "chrome-extension://abcdefghijklmnopabcdefghijklmnop/"
+            ])),
+            Ok(Invocation::NativeHost)
+        );
+        assert_eq!(
+            parse(&args(&[
+                "native-host",
+                "firefox",
+                "x@example.org",
+                "--install"
+            ])),
+            Ok(Invocation::NativeHostManifest {
+                browser: Browser::Firefox,
+                extension_id: "x@example.org".to_string(),
+                install: true,
+            })
+        );
+        assert!(parse(&args(&["native-host", "firefox"])).is_err());
+        assert!(parse(&args(&["native-host", "edge", "x@example.org"])).is_err());
+    }
+
+    #[test]
+    fn test_mcp_options() {
+        assert_eq!(parse(&args(&["mcp"])), Ok(Invocation::Mcp));
+        assert!(parse(&args(&["mcp", "--stdio"])).is_err());
+    }
+
+    #[test]
+    fn test_batch_options() {
+        assert_eq!(
+            parse(&args(&[
+                "batch",
+                "prompts.jsonl",
+                "--out",
+                "results.md",
+                "--delay",
+                "0",
+                "--restart"
+            ])),
+            Ok(Invocation::Batch(BatchOptions {
+                input: PathBuf::from("prompts.jsonl"),
+                out: PathBuf::from("results.md"),
+                delay: Duration::ZERO,
+                timeout: pipeline::DEFAULT_ANSWER_TIMEOUT,
+                restart: true,
+            }))
+        );
+        let Ok(Invocation::Batch(options)) = parse(&args(&[
+            "batch",
+            "--out",
+            "out.jsonl",
+            "--timeout",
+            "60",
+            "p.jsonl",
+        ])) else {
+            panic!("expected a batch invocation");
+        };
+        assert_eq!(options.delay, batch::DEFAULT_DELAY);
+        assert_eq!(options.timeout, Duration::from_secs(60));
+
+        assert!(parse(&args(&["batch", "p.jsonl"])).is_err());
+        assert!(parse(&args(&["batch", "--out", "r.md"])).is_err());
+        assert!(parse(&args(&["batch", "a.jsonl", "b.jsonl", "--out", "r.md"])).is_err());
+        assert!(parse(&args(&[
+            "batch",
+            "p.jsonl",
+            "--out",
+            "r.md",
+            "--timeout",
+            "0"
+        ]))
+        .is_err());
+        assert!(parse(&args(&[
+            "batch", "p.jsonl", "--out", "r.md", "--delay", "x"
+        ]))
+        .is_err());
+        assert!(parse(&args(&["batch", "p.jsonl", "--out", "r.md", "--fast"])).is_err());
+    }
+
+    #[test]
+    fn test_ask_connect_only_reaches_loopback() {
+        assert_eq!(
+            parse(&args(&["ask-connect", "127.0.0.1:4000", "abc"])),
+            Ok(Invocation::Command(CliCommand::AskConnect {
+                addr: "127.0.0.1:4000".parse().unwrap(),
+                token: "abc".to_string(),
+            }))
+        );
+        assert!(parse(&args(&["ask-connect", "192.168.1.2:4000", "abc"])).is_err());
+    }
+}
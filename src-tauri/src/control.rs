@@ -0,0 +1,540 @@
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, watch};
+
+// Local control socket: JSON-RPC 2.0, one message per line, over a Unix domain
+// socket (named pipe on Windows) that only the current user can open. Lets
+// editor plugins and launchers drive the app without spawning a process per call.
+
+// Events a client can subscribe to. Each arrives as an `event` notification:
+// {"jsonrpc":"2.0","method":"event","params":{"event":"response-complete","payload":{...}}}
+pub const STREAMED_EVENTS: &[&str] = &[
+    "inject-result",
+    "response-complete",
+    "submission-status",
+    "main-state-changed",
+    "last-response",
+    "settings-changed",
+];
+
+// How many events are buffered for a slow client before it misses some
+pub const EVENT_BUFFER: usize = 64;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// A method ran but failed, e.g. the main window is gone
+const APP_ERROR: i64 = -32000;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ControlEvent {
+    pub event: String,
+    pub payload: Value,
+}
+
+// Methods mirroring the Tauri commands of the same name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlMethod {
+    ShowLauncher,
+    ToggleLauncher,
+    SubmitMessage {
+        message: String,
+        new_chat: bool,
+        inline: Option<bool>,
+    },
+    NavigateToChat,
+    GetSettings,
+}
+
+#[derive(serde::Deserialize)]
+struct SubmitMessageParams {
+    message: String,
+    new_chat: bool,
+    #[serde(default)]
+    inline: Option<bool>,
+}
+
+#[derive(Default, serde::Deserialize)]
+struct SubscribeParams {
+    // All streamed events if left out
+    events: Option<Vec<String>>,
+}
+
+// Runs methods against the app
+pub trait ControlHandler: Send + Sync + 'static {
+    fn call(&self, method: ControlMethod) -> impl Future<Output = Result<Value, String>> + Send;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    // Absent for notifications, which get no response
+    pub id: Option<Value>,
+    pub method: String,
+    pub params: Value,
+}
+
+// Parse one line into a request. On failure, returns the id to answer with
+// (null if it couldn't be read) and the error.
+pub fn parse_request(line: &str) -> Result<Request, (Value, RpcError)> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| (Value::Null, RpcError::new(PARSE_ERROR, e.to_string())))?;
+    let Value::Object(mut object) = value else {
+        return Err((
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Expected a request object"),
+        ));
+    };
+    let id = object.remove("id");
+    let invalid = |message: &str| {
+        (
+            id.clone().unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, message),
+        )
+    };
+    if object.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(invalid("\"jsonrpc\" must be \"2.0\""));
+    }
+    if !matches!(
+        id,
+        None | Some(Value::Null | Value::Number(_) | Value::String(_))
+    ) {
+        return Err(invalid("\"id\" must be a number or a string"));
+    }
+    let Some(Value::String(method)) = object.remove("method") else {
+        return Err(invalid("\"method\" must be a string"));
+    };
+    let params = object.remove("params").unwrap_or(Value::Null);
+    if !matches!(params, Value::Null | Value::Object(_) | Value::Array(_)) {
+        return Err(invalid("\"params\" must be an object or an array"));
+    }
+    Ok(Request { id, method, params })
+}
+
+fn parse_params<T: serde::de::DeserializeOwned + Default>(params: Value) -> Result<T, RpcError> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+// Map a method name and its params onto an app method
+pub fn parse_method(method: &str, params: Value) -> Result<ControlMethod, RpcError> {
+    match method {
+        "show_launcher" => Ok(ControlMethod::ShowLauncher),
+        "toggle_launcher" => Ok(ControlMethod::ToggleLauncher),
+        "navigate_to_chat" => Ok(ControlMethod::NavigateToChat),
+        "get_settings" => Ok(ControlMethod::GetSettings),
+        "submit_message" => {
+            let params: SubmitMessageParams = serde_json::from_value(params)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+            Ok(ControlMethod::SubmitMessage {
+                message: params.message,
+                new_chat: params.new_chat,
+                inline: params.inline,
+            })
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+// The events named by `subscribe`/`unsubscribe` params
+fn event_names(params: Value) -> Result<Vec<String>, RpcError> {
+    let params: SubscribeParams = parse_params(params)?;
+    let Some(events) = params.events else {
+        return Ok(STREAMED_EVENTS.iter().map(|e| e.to_string()).collect());
+    };
+    if let Some(unknown) = events
+        .iter()
+        .find(|e| !STREAMED_EVENTS.contains(&e.as_str()))
+    {
+        return Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("Unknown event '{}'", unknown),
+        ));
+    }
+    Ok(events)
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+fn event_notification(event: &ControlEvent) -> Value {
+    serde_json::json!({ "jsonrpc": "2.0", "method": "event", "params": event })
+}
+
+async fn write_line(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &Value,
+) -> std::io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    writer.flush().await
+}
+
+// Answer one request, keeping track of this connection's subscriptions
+async fn dispatch<H: ControlHandler>(
+    handler: &H,
+    subscribed: &mut BTreeSet<String>,
+    request: Request,
+) -> Result<Value, RpcError> {
+    match request.method.as_str() {
+        "subscribe" => {
+            subscribed.extend(event_names(request.params)?);
+            Ok(serde_json::json!(subscribed))
+        }
+        "unsubscribe" => {
+            for event in event_names(request.params)? {
+                subscribed.remove(&event);
+            }
+            Ok(serde_json::json!(subscribed))
+        }
+        method => {
+            let method = parse_method(method, request.params)?;
+            handler
+                .call(method)
+                .await
+                .map_err(|e| RpcError::new(APP_ERROR, e))
+        }
+    }
+}
+
+// Serve one client until it disconnects or the socket is shut down
+pub async fn handle_connection<S, H>(
+    stream: S,
+    handler: Arc<H>,
+    mut events: broadcast::Receiver<ControlEvent>,
+    mut shutdown: watch::Receiver<bool>,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+    H: ControlHandler,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let mut subscribed = BTreeSet::new();
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reply = match parse_request(&line) {
+                    Ok(request) => {
+                        let id = request.id.clone();
+                        let result = dispatch(handler.as_ref(), &mut subscribed, request).await;
+                        id.map(|id| response(id, result))
+                    }
+                    Err((id, error)) => Some(response(id, Err(error))),
+                };
+                if let Some(reply) = reply {
+                    if write_line(&mut writer, &reply).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) if subscribed.contains(&event.event) => {
+                    if write_line(&mut writer, &event_notification(&event)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    eprintln!("Control socket client missed {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{bind, serve};
+
+#[cfg(unix)]
+mod unix {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use tokio::net::UnixListener;
+    use tokio::sync::{broadcast, watch};
+
+    use super::{handle_connection, ControlEvent, ControlHandler};
+
+    // Bind the socket at `path`. Its directory is created private to the user
+    // first, so the socket is never reachable by anyone else, and a socket left
+    // behind by a crash is replaced.
+    pub fn bind(path: &Path) -> Result<UnixListener, String> {
+        let dir = path.parent().ok_or("Invalid control socket path")?;
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| e.to_string())?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| e.to_string())?;
+
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(format!("{} is already in use", path.display()));
+            }
+            std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        let listener = UnixListener::bind(path).map_err(|e| e.to_string())?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+        Ok(listener)
+    }
+
+    // Accept clients until `shutdown` turns true
+    pub async fn serve<H: ControlHandler>(
+        listener: UnixListener,
+        handler: Arc<H>,
+        events: broadcast::Sender<ControlEvent>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(
+                            stream,
+                            handler.clone(),
+                            events.subscribe(),
+                            shutdown.clone(),
+                        ));
+                    }
+                    Err(e) => eprintln!("Control socket accept failed: {}", e),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+pub use windows::serve_pipe;
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+    use std::sync::Arc;
+
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+    use tokio::sync::{broadcast, watch};
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+
+    use super::{handle_connection, ControlEvent, ControlHandler};
+
+    // Full access for the pipe's owner, the user running the app, and nobody
+    // else. The default security would also let everyone, including anonymous
+    // logons, read from the pipe.
+    const OWNER_ONLY: &str = "D:P(A;;GA;;;OW)";
+
+    // A security descriptor parsed from SDDL, freed when dropped
+    struct SecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+    // SAFETY: the descriptor is never modified after it is created
+    unsafe impl Send for SecurityDescriptor {}
+    unsafe impl Sync for SecurityDescriptor {}
+
+    impl SecurityDescriptor {
+        fn parse(sddl: &str) -> Result<Self, String> {
+            let sddl: Vec<u16> = sddl.encode_utf16().chain(Some(0)).collect();
+            let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+            // SAFETY: `sddl` is NUL-terminated, and the buffer written to
+            // `descriptor` is freed by `Drop`
+            let ok = unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                    sddl.as_ptr(),
+                    SDDL_REVISION_1,
+                    &mut descriptor,
+                    std::ptr::null_mut(),
+                )
+            };
+            if ok == 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            Ok(Self(descriptor))
+        }
+
+        // Create an instance of the pipe `name` with this security
+        fn create(&self, options: &ServerOptions, name: &str) -> Result<NamedPipeServer, String> {
+            let mut attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: self.0,
+                bInheritHandle: 0,
+            };
+            // SAFETY: `attributes` and the descriptor it points at outlive the call
+            unsafe {
+                options.create_with_security_attributes_raw(
+                    name,
+                    &mut attributes as *mut SECURITY_ATTRIBUTES as *mut c_void,
+                )
+            }
+            .map_err(|e| e.to_string())
+        }
+    }
+
+    impl Drop for SecurityDescriptor {
+        fn drop(&mut self) {
+            // SAFETY: allocated by ConvertStringSecurityDescriptorToSecurityDescriptorW
+            unsafe { LocalFree(self.0) };
+        }
+    }
+
+    // Serve clients on the named pipe `name` until `shutdown` turns true. Only
+    // the user running the app may open the pipe, and remote clients are refused.
+    pub async fn serve_pipe<H: ControlHandler>(
+        name: String,
+        handler: Arc<H>,
+        events: broadcast::Sender<ControlEvent>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<(), String> {
+        let security = SecurityDescriptor::parse(OWNER_ONLY)?;
+        let mut server = security.create(
+            ServerOptions::new()
+                .first_pipe_instance(true)
+                .reject_remote_clients(true),
+            &name,
+        )?;
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => return Ok(()),
+                connected = server.connect() => {
+                    if let Err(e) = connected {
+                        eprintln!("Control pipe connect failed: {}", e);
+                        continue;
+                    }
+                    // Open the next instance before handing this one off
+                    let client = std::mem::replace(
+                        &mut server,
+                        security.create(ServerOptions::new().reject_remote_clients(true), &name)?,
+                    );
+                    tokio::spawn(handle_connection(
+                        client,
+                        handler.clone(),
+                        events.subscribe(),
+                        shutdown.clone(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(r#"{"jsonrpc":"2.0","id":7,"method":"get_settings"}"#).unwrap();
+        assert_eq!(request.id, Some(serde_json::json!(7)));
+        assert_eq!(request.method, "get_settings");
+        assert_eq!(request.params, Value::Null);
+
+        let notification =
+            parse_request(r#"{"jsonrpc":"2.0","method":"toggle_launcher"}"#).unwrap();
+        assert_eq!(notification.id, None);
+    }
+
+    #[test]
+    fn test_parse_request_errors() {
+        let (id, error) = parse_request("{not json").unwrap_err();
+        assert_eq!((id, error.code), (Value::Null, PARSE_ERROR));
+
+        let (id, error) = parse_request(r#"{"id":"a","method":"show_launcher"}"#).unwrap_err();
+        assert_eq!((id, error.code), (serde_json::json!("a"), INVALID_REQUEST));
+
+        for line in [
+            r#"[{"jsonrpc":"2.0","id":1,"method":"show_launcher"}]"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":3}"#,
+            r#"{"jsonrpc":"2.0","id":{},"method":"show_launcher"}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"show_launcher","params":"x"}"#,
+        ] {
+            assert_eq!(parse_request(line).unwrap_err().1.code, INVALID_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_parse_method() {
+        assert_eq!(
+            parse_method(
+                "submit_message",
+                serde_json::json!({ "message": "Hi", "new_chat": true })
+            ),
+            Ok(ControlMethod::SubmitMessage {
+                message: "Hi".to_string(),
+                new_chat: true,
+                inline: None,
+            })
+        );
+        assert_eq!(
+            parse_method("submit_message", serde_json::json!({ "message": "Hi" }))
+                .unwrap_err()
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            parse_method("save_settings", Value::Null).unwrap_err().code,
+            METHOD_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_event_names() {
+        assert_eq!(
+            event_names(Value::Null).unwrap().len(),
+            STREAMED_EVENTS.len()
+        );
+        assert_eq!(
+            event_names(serde_json::json!({ "events": ["response-complete"] })).unwrap(),
+            vec!["response-complete"]
+        );
+        assert_eq!(
+            event_names(serde_json::json!({ "events": ["page-dom-ready"] }))
+                .unwrap_err()
+                .code,
+            INVALID_PARAMS
+        );
+    }
+}
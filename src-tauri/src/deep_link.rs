@@ -0,0 +1,224 @@
+use std::sync::Mutex;
+
+use tauri::Url;
+
+// `lechat://` links, e.g. from a wiki or runbook. The OS passes them to the app
+// as a command-line argument (Windows, Linux) or an "open URL" event (macOS).
+// Links can come from any web page, so prompts are never sent without the user
+// confirming them in the launcher.
+
+pub const SCHEME: &str = "lechat";
+
+// Same limit as a message typed into the launcher
+pub const MAX_PROMPT_CHARS: usize = 5000;
+const MAX_CONVERSATION_ID_CHARS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    // lechat://ask?q=<prompt>[&new=1|0]
+    Ask {
+        prompt: String,
+        // None follows the "new chat by default" setting
+        new_chat: Option<bool>,
+    },
+    // lechat://open?c=<conversation-id>
+    Open {
+        conversation_id: String,
+    },
+    // lechat://settings
+    Settings,
+}
+
+// Where a prompt waiting in the launcher came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSource {
+    Link,
+    // The browser extension, through the native-messaging host
+    Browser,
+}
+
+// A prompt from a link, waiting in the launcher for the user to send it
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LinkPrompt {
+    pub prompt: String,
+    pub new_chat: Option<bool>,
+    pub source: PromptSource,
+}
+
+// Holds the latest link prompt until the launcher picks it up, since the
+// launcher page may still be loading when a link starts the app
+#[derive(Default)]
+pub struct PendingLinkPrompt(Mutex<Option<LinkPrompt>>);
+
+impl PendingLinkPrompt {
+    pub fn set(&self, prompt: LinkPrompt) {
+        *self.0.lock().unwrap() = Some(prompt);
+    }
+
+    pub fn take(&self) -> Option<LinkPrompt> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.get(..SCHEME.len() + 1)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("lechat:"))
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" => Ok(false),
+        _ => Err(format!("'new' must be 1 or 0, not '{}'", value)),
+    }
+}
+
+// Conversation ids are UUID-like; anything else could point elsewhere on the site
+fn parse_conversation_id(value: &str) -> Result<String, String> {
+    let valid = !value.is_empty()
+        && value.len() <= MAX_CONVERSATION_ID_CHARS
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("'{}' is not a conversation id", value));
+    }
+    Ok(value.to_string())
+}
+
+pub fn parse(raw: &str) -> Result<DeepLink, String> {
+    let url = Url::parse(raw.trim()).map_err(|e| format!("invalid link '{}': {}", raw, e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("'{}' is not a {}:// link", raw, SCHEME));
+    }
+    // `lechat://ask?...` has the action as host; accept `lechat:ask?...` and
+    // `lechat:///ask?...` too, since some launchers rewrite links
+    let action = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| url.path().trim_matches('/').to_string())
+        .to_ascii_lowercase();
+
+    let mut query = Vec::new();
+    for (key, value) in url.query_pairs() {
+        if query.iter().any(|(k, _): &(String, String)| *k == key) {
+            return Err(format!("'{}' is given more than once", key));
+        }
+        query.push((key.into_owned(), value.into_owned()));
+    }
+    let param = |name: &str| {
+        query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    match action.as_str() {
+        "ask" => {
+            let prompt = param("q").unwrap_or_default().trim();
+            if prompt.is_empty() {
+                return Err("ask links need a prompt: lechat://ask?q=...".to_string());
+            }
+            if prompt.chars().count() > MAX_PROMPT_CHARS {
+                return Err(format!(
+                    "the prompt is longer than {} characters",
+                    MAX_PROMPT_CHARS
+                ));
+            }
+            Ok(DeepLink::Ask {
+                prompt: prompt.to_string(),
+                new_chat: param("new").map(parse_flag).transpose()?,
+            })
+        }
+        "open" => {
+            let id = param("c").ok_or("open links need a conversation: lechat://open?c=<id>")?;
+            Ok(DeepLink::Open {
+                conversation_id: parse_conversation_id(id)?,
+            })
+        }
+        "settings" => Ok(DeepLink::Settings),
+        _ => Err(format!("unknown link action '{}'", action)),
+    }
+}
+
+pub fn conversation_url(conversation_id: &str) -> String {
+    format!("https://chat.mistral.ai/chat/{}", conversation_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_deep_link() {
+        assert!(is_deep_link("lechat://ask?q=hi"));
+        assert!(is_deep_link("LeChat:settings"));
+        assert!(!is_deep_link("lechat"));
+        assert!(!is_deep_link("open"));
+    }
+
+    #[test]
+    fn test_ask_links() {
+        assert_eq!(
+            parse("lechat://ask?q=Summarize%20this%20runbook&new=1"),
+            Ok(DeepLink::Ask {
+                prompt: "Summarize this runbook".to_string(),
+                new_chat: Some(true),
+            })
+        );
+        assert_eq!(
+            parse("lechat://ask?q=a+b+%26+c"),
+            Ok(DeepLink::Ask {
+                prompt: "a b & c".to_string(),
+                new_chat: None,
+            })
+        );
+        assert_eq!(
+            parse("lechat:ask?q=hi&new=0"),
+            Ok(DeepLink::Ask {
+                prompt: "hi".to_string(),
+                new_chat: Some(false),
+            })
+        );
+        // Unknown parameters are ignored so newer links still open
+        assert!(parse("lechat://ask?q=hi&utm_source=wiki").is_ok());
+    }
+
+    #[test]
+    fn test_ask_link_errors() {
+        assert!(parse("lechat://ask").is_err());
+        assert!(parse("lechat://ask?q=%20%20").is_err());
+        assert!(parse("lechat://ask?q=hi&new=maybe").is_err());
+        assert!(parse("lechat://ask?q=one&q=two").is_err());
+        let long = format!("lechat://ask?q={}", "a".repeat(MAX_PROMPT_CHARS + 1));
+        assert!(parse(&long).is_err());
+    }
+
+    #[test]
+    fn test_open_links() {
+        assert_eq!(
+            parse("lechat://open?c=0f8e7d6c-1234-4abc-9def-0123456789ab"),
+            Ok(DeepLink::Open {
+                conversation_id: "0f8e7d6c-1234-4abc-9def-0123456789ab".to_string()
+            })
+        );
+        assert_eq!(
+            conversation_url("abc-123"),
+            "https://chat.mistral.ai/chat/abc-123"
+        );
+        assert!(parse("lechat://open").is_err());
+        assert!(parse("lechat://open?c=../../settings").is_err());
+        assert!(parse("lechat://open?c=https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_other_links() {
+        assert_eq!(parse("lechat://settings"), Ok(DeepLink::Settings));
+        assert_eq!(parse("lechat:///settings/"), Ok(DeepLink::Settings));
+        assert!(parse("lechat://delete-everything").is_err());
+        assert!(parse("https://ask?q=hi").is_err());
+        assert!(parse("not a link").is_err());
+    }
+}
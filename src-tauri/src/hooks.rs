@@ -0,0 +1,478 @@
+use std::io::{Read, Write};
+use std::net::IpAddr;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use tauri::Url;
+
+// User-defined hooks: run a command or POST to a local URL when something
+// happens in the app, e.g. to log answers to a notes tool or drive a status
+// light. Each run gets the event as JSON:
+//   { "event": "response-complete", "timestamp": 1700000000, "data": { ... } }
+// on stdin for commands, as the request body for POSTs. Runs are detached from
+// the app's event handling, bounded by the hook's timeout, and logged.
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+pub const MAX_TIMEOUT_SECS: u64 = 300;
+// Longest stretch of a hook's output or response that gets logged
+const MAX_LOGGED_OUTPUT: usize = 2000;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// How long output is still collected after a command exits or is killed,
+// in case something it started keeps the pipes open
+const OUTPUT_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    // Le Chat finished a reply; data is the captured reply
+    ResponseComplete,
+    // A prompt couldn't be typed into Le Chat
+    InjectFailed,
+    // The main window lost or regained its connection to Le Chat
+    Offline,
+    Online,
+    // A prompt was submitted from the launcher, `le-chat ask` or an integration
+    Submission,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::ResponseComplete => "response-complete",
+            HookEvent::InjectFailed => "inject-failed",
+            HookEvent::Offline => "offline",
+            HookEvent::Online => "online",
+            HookEvent::Submission => "submission",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HookAction {
+    // Run through the shell (`sh -c`, `cmd /C`) with the event on stdin
+    Command { command: String },
+    // POST the event to an http://localhost URL
+    Post { url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Hook {
+    pub event: HookEvent,
+    #[serde(flatten)]
+    pub action: HookAction,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Hook {
+    fn target(&self) -> &str {
+        match &self.action {
+            HookAction::Command { command } => command,
+            HookAction::Post { url } => url,
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+// Only this machine may receive events; they can carry whole conversations
+fn check_local_url(raw: &str) -> Result<(), String> {
+    let url = Url::parse(raw).map_err(|e| format!("invalid URL '{}': {}", raw, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("'{}' is not an http:// URL", raw));
+    }
+    let local = url.host_str().is_some_and(|host| {
+        host.eq_ignore_ascii_case("localhost")
+            || host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    });
+    if !local {
+        return Err(format!(
+            "'{}' is not on this machine (use localhost or 127.0.0.1)",
+            raw
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate(hooks: &[Hook]) -> Result<(), String> {
+    for (i, hook) in hooks.iter().enumerate() {
+        let checked = match &hook.action {
+            HookAction::Command { command } if command.trim().is_empty() => {
+                Err("the command is empty".to_string())
+            }
+            HookAction::Command { .. } => Ok(()),
+            HookAction::Post { url } => check_local_url(url.trim()),
+        }
+        .and_then(|()| {
+            if (1..=MAX_TIMEOUT_SECS).contains(&hook.timeout_secs) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "the timeout must be 1 to {} seconds",
+                    MAX_TIMEOUT_SECS
+                ))
+            }
+        });
+        checked.map_err(|e| format!("Hook {} ({}): {}", i + 1, hook.event.name(), e))?;
+    }
+    Ok(())
+}
+
+pub fn payload(event: HookEvent, data: Value) -> Value {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    json!({ "event": event.name(), "timestamp": timestamp, "data": data })
+}
+
+// What a command printed, and how it ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    // None if it was killed or ended by a signal
+    pub code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut shell = Command::new("cmd");
+        shell
+            .arg("/C")
+            .arg(command)
+            .creation_flags(CREATE_NO_WINDOW);
+        shell
+    }
+    #[cfg(not(windows))]
+    {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+fn collect(mut pipe: impl Read + Send + 'static) -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        let _ = sender.send(String::from_utf8_lossy(&bytes).into_owned());
+    });
+    receiver
+}
+
+// Run a command with `input` on stdin, killing it once `timeout` has passed
+pub fn run_command(
+    command: &str,
+    input: &[u8],
+    timeout: Duration,
+) -> Result<CommandOutput, String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to start: {}", e))?;
+
+    // Fed from its own thread so a command that never reads stdin can't stall us
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_vec();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let stdout = child.stdout.take().map(collect);
+    let stderr = child.stderr.take().map(collect);
+
+    let deadline = Instant::now() + timeout;
+    let (status, timed_out) = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break (Some(status), false);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            break (child.wait().ok(), true);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let read = |output: Option<mpsc::Receiver<String>>| {
+        output
+            .and_then(|output| output.recv_timeout(OUTPUT_GRACE).ok())
+            .unwrap_or_default()
+    };
+    Ok(CommandOutput {
+        code: if timed_out {
+            None
+        } else {
+            status.and_then(|status| status.code())
+        },
+        timed_out,
+        stdout: read(stdout),
+        stderr: read(stderr),
+    })
+}
+
+// The status code and body of a POST. Redirects aren't followed, since they
+// could send the event off this machine, and count as a failure.
+pub async fn post(url: &str, body: String, timeout: Duration) -> Result<(u16, String), String> {
+    let response = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| e.to_string())?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "timed out".to_string()
+            } else {
+                e.to_string()
+            }
+        })?;
+    if response.status().is_redirection() {
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .unwrap_or("nowhere");
+        return Err(format!(
+            "{} redirect to {} not followed",
+            response.status().as_u16(),
+            location
+        ));
+    }
+    let status = response.status().as_u16();
+    let text = response.text().await.unwrap_or_default();
+    Ok((status, text))
+}
+
+fn excerpt(output: &str) -> String {
+    let output = output.trim();
+    if output.chars().count() <= MAX_LOGGED_OUTPUT {
+        return output.to_string();
+    }
+    let cut: String = output.chars().take(MAX_LOGGED_OUTPUT).collect();
+    format!("{}…", cut)
+}
+
+fn log_command(hook: &Hook, output: Result<CommandOutput, String>, elapsed: Duration) {
+    let prefix = format!("Hook {} `{}`", hook.event.name(), hook.target());
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("{}: {}", prefix, e);
+            return;
+        }
+    };
+    let outcome = match (output.timed_out, output.code) {
+        (true, _) => format!("killed after {}s", hook.timeout_secs),
+        (false, Some(code)) => format!("exited with {}", code),
+        (false, None) => "ended by a signal".to_string(),
+    };
+    eprintln!("{}: {} in {}ms", prefix, outcome, elapsed.as_millis());
+    for (name, text) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        if !text.trim().is_empty() {
+            eprintln!("{} {}: {}", prefix, name, excerpt(text));
+        }
+    }
+}
+
+// Start every enabled hook for `event` without waiting for any of them
+pub fn fire(hooks: &[Hook], event: HookEvent, data: Value) {
+    let matching: Vec<Hook> = hooks
+        .iter()
+        .filter(|hook| hook.enabled && hook.event == event)
+        .cloned()
+        .collect();
+    if matching.is_empty() {
+        return;
+    }
+    let body = payload(event, data).to_string();
+    for hook in matching {
+        let body = body.clone();
+        match hook.action.clone() {
+            HookAction::Command { command } => {
+                std::thread::spawn(move || {
+                    let started = Instant::now();
+                    let output = run_command(&command, body.as_bytes(), hook.timeout());
+                    log_command(&hook, output, started.elapsed());
+                });
+            }
+            HookAction::Post { url } => {
+                tauri::async_runtime::spawn(async move {
+                    let started = Instant::now();
+                    let prefix = format!("Hook {} POST {}", hook.event.name(), url);
+                    match post(&url, body, hook.timeout()).await {
+                        Ok((status, text)) => {
+                            eprintln!(
+                                "{}: {} in {}ms",
+                                prefix,
+                                status,
+                                started.elapsed().as_millis()
+                            );
+                            if !text.trim().is_empty() {
+                                eprintln!("{} response: {}", prefix, excerpt(&text));
+                            }
+                        }
+                        Err(e) => eprintln!("{}: {}", prefix, e),
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_hook(event: HookEvent, command: &str) -> Hook {
+        Hook {
+            event,
+            action: HookAction::Command {
+                command: command.to_string(),
+            },
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_hooks_are_stored_flat() {
+        let hook: Hook = serde_json::from_value(json!({
+            "event": "response-complete",
+            "kind": "post",
+            "url": "http://localhost:8080/answers",
+        }))
+        .unwrap();
+        assert_eq!(hook.event, HookEvent::ResponseComplete);
+        assert_eq!(
+            hook.action,
+            HookAction::Post {
+                url: "http://localhost:8080/answers".to_string()
+            }
+        );
+        assert_eq!(hook.timeout_secs, DEFAULT_TIMEOUT_SECS);
+        assert!(hook.enabled);
+
+        let stored = serde_json::to_value(command_hook(HookEvent::Offline, "light red")).unwrap();
+        assert_eq!(stored["kind"], "command");
+        assert_eq!(stored["event"], "offline");
+        assert_eq!(stored["command"], "light red");
+    }
+
+    #[test]
+    fn test_validate() {
+        let post = |url: &str| Hook {
+            event: HookEvent::Online,
+            action: HookAction::Post {
+                url: url.to_string(),
+            },
+            timeout_secs: 5,
+            enabled: true,
+        };
+        assert!(validate(&[
+            post("http://localhost:9000/hook"),
+            post("http://127.0.0.1/x"),
+            post("http://[::1]:8000/"),
+            command_hook(HookEvent::Submission, "notes add"),
+        ])
+        .is_ok());
+        assert!(validate(&[post("https://example.com/hook")]).is_err());
+        assert!(validate(&[post("http://localhost.example.com/")]).is_err());
+        assert!(validate(&[post("file:///tmp/x")]).is_err());
+        assert!(validate(&[command_hook(HookEvent::Submission, "  ")]).is_err());
+
+        let mut slow = command_hook(HookEvent::Offline, "true");
+        slow.timeout_secs = MAX_TIMEOUT_SECS + 1;
+        let error = validate(&[command_hook(HookEvent::Online, "true"), slow]).unwrap_err();
+        assert!(error.starts_with("Hook 2 (offline)"));
+    }
+
+    #[test]
+    fn test_payload() {
+        let payload = payload(HookEvent::InjectFailed, json!({ "submission_id": 3 }));
+        assert_eq!(payload["event"], "inject-failed");
+        assert_eq!(payload["data"]["submission_id"], 3);
+        assert!(payload["timestamp"].as_u64().unwrap() > 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commands_get_the_event_on_stdin() {
+        let output = run_command(
+            "cat; echo oops >&2; exit 3",
+            br#"{"event":"online"}"#,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(output.stdout, r#"{"event":"online"}"#);
+        assert_eq!(output.stderr, "oops\n");
+        assert_eq!(output.code, Some(3));
+        assert!(!output.timed_out);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commands_are_killed_after_the_timeout() {
+        let started = Instant::now();
+        let output =
+            run_command("echo started; sleep 10", b"", Duration::from_millis(200)).unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.code, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_posts_fail_on_redirects() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            let _ = stream.write_all(
+                b"HTTP/1.1 302 Found\r\nLocation: https://example.com/\r\nContent-Length: 0\r\n\r\n",
+            );
+        });
+        let err = post(&url, "{}".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "302 redirect to https://example.com/ not followed");
+    }
+
+    #[test]
+    fn test_excerpt() {
+        assert_eq!(excerpt("  ok\n"), "ok");
+        let long = "x".repeat(MAX_LOGGED_OUTPUT + 10);
+        assert_eq!(excerpt(&long).chars().count(), MAX_LOGGED_OUTPUT + 1);
+    }
+}
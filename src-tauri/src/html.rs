@@ -0,0 +1,495 @@
+// Minimal HTML parsing and sanitizing for markup captured from the chat page.
+// The input is a browser's `innerHTML` serialization, so it is well-formed apart
+// from void elements; this is not a general-purpose HTML5 parser.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Element {
+        tag: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+    // Decoded text
+    Text(String),
+}
+
+impl Node {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        match self {
+            Node::Element { attrs, .. } => attrs
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str()),
+            Node::Text(_) => None,
+        }
+    }
+
+    // Concatenated text of this node and its descendants
+    pub fn text(&self) -> String {
+        match self {
+            Node::Text(text) => text.clone(),
+            Node::Element { children, .. } => children.iter().map(Node::text).collect(),
+        }
+    }
+}
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+// Elements whose content is not markup
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+// Elements kept by `sanitize`; anything else is unwrapped to its children
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "del",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+// Elements dropped by `sanitize` together with their content
+const DROPPED_ELEMENTS: &[&str] = &[
+    "button", "form", "iframe", "input", "noscript", "object", "script", "select", "style", "svg",
+    "template", "textarea",
+];
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => {
+                    let code = if let Some(hex) = entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                    {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else {
+                        entity.strip_prefix('#').and_then(|dec| dec.parse().ok())
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parse_attrs(source: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (raw, remaining) = match after_eq.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after_eq[1..];
+                    let end = body.find(quote).unwrap_or(body.len());
+                    (&body[..end], body.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                    (&after_eq[..end], &after_eq[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining.trim_start();
+        }
+        if !name.is_empty() && name != "/" {
+            attrs.push((name, value));
+        }
+    }
+    attrs
+}
+
+// Byte offset of the '>' closing the tag at the start of `source`,
+// skipping any inside quoted attribute values
+fn tag_end(source: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in source.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+struct Open {
+    tag: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+fn close(stack: &mut Vec<Open>) {
+    let open = stack.pop().expect("root is never closed");
+    let node = Node::Element {
+        tag: open.tag,
+        attrs: open.attrs,
+        children: open.children,
+    };
+    stack.last_mut().unwrap().children.push(node);
+}
+
+// Parse an HTML fragment into a list of nodes. Comments and doctypes are skipped;
+// unmatched end tags are ignored and unclosed elements are closed at the end.
+pub fn parse(html: &str) -> Vec<Node> {
+    let mut stack = vec![Open {
+        tag: String::new(),
+        attrs: Vec::new(),
+        children: Vec::new(),
+    }];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(Node::Text(decode_entities(rest)));
+            break;
+        };
+        if start > 0 {
+            stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(Node::Text(decode_entities(&rest[..start])));
+        }
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .find("-->")
+                .map(|end| &comment[end + 3..])
+                .unwrap_or("");
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+            continue;
+        }
+
+        let Some(end) = tag_end(rest) else {
+            // A lone '<' in text
+            stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(Node::Text(decode_entities(rest)));
+            break;
+        };
+        let tag_source = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(name) = tag_source.strip_prefix('/') {
+            let name = name.trim().to_ascii_lowercase();
+            if let Some(depth) = stack.iter().rposition(|open| open.tag == name) {
+                if depth > 0 {
+                    while stack.len() > depth {
+                        close(&mut stack);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let name_end = tag_source
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag_source.len());
+        let tag = tag_source[..name_end].to_ascii_lowercase();
+        if tag.is_empty() || !tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(Node::Text(format!("<{}>", tag_source)));
+            continue;
+        }
+        let attrs = parse_attrs(tag_source[name_end..].trim_end_matches('/'));
+        let self_closing = tag_source.ends_with('/');
+
+        if VOID_ELEMENTS.contains(&tag.as_str()) || self_closing {
+            stack.last_mut().unwrap().children.push(Node::Element {
+                tag,
+                attrs,
+                children: Vec::new(),
+            });
+            continue;
+        }
+
+        if RAW_TEXT_ELEMENTS.contains(&tag.as_str()) {
+            let closing = format!("</{}", tag);
+            let lower = rest.to_ascii_lowercase();
+            let content_end = lower.find(&closing).unwrap_or(rest.len());
+            let text = decode_entities(&rest[..content_end]);
+            rest = &rest[content_end..];
+            rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+            stack.last_mut().unwrap().children.push(Node::Element {
+                tag,
+                attrs,
+                children: vec![Node::Text(text)],
+            });
+            continue;
+        }
+
+        stack.push(Open {
+            tag,
+            attrs,
+            children: Vec::new(),
+        });
+    }
+
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+    stack.pop().unwrap().children
+}
+
+fn is_safe_url(url: &str, allow_data_images: bool) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("https://")
+        || url.starts_with("http://")
+        || url.starts_with("mailto:")
+        || (allow_data_images && url.starts_with("data:image/"))
+}
+
+fn allowed_attrs(tag: &str, attrs: Vec<(String, String)>) -> Vec<(String, String)> {
+    attrs
+        .into_iter()
+        .filter(|(name, value)| match (tag, name.as_str()) {
+            ("a", "href") => is_safe_url(value, false),
+            ("img", "src") => is_safe_url(value, true),
+            ("img", "alt") | ("th", "align") | ("td", "align") => true,
+            // Keep the language of code blocks
+            ("code", "class") => value.split_whitespace().any(|c| c.starts_with("language-")),
+            ("ol", "start") => value.parse::<u32>().is_ok(),
+            _ => false,
+        })
+        .collect()
+}
+
+// Keep only presentational markup: scripts, styles, forms and UI controls are
+// removed with their content, other unknown elements are replaced by their
+// children, and only safe attributes (links, image sources, code languages) remain.
+pub fn sanitize(nodes: Vec<Node>) -> Vec<Node> {
+    let mut out = Vec::new();
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push(Node::Text(text)),
+            Node::Element {
+                tag,
+                attrs,
+                children,
+            } => {
+                if DROPPED_ELEMENTS.contains(&tag.as_str()) {
+                    continue;
+                }
+                let children = sanitize(children);
+                if ALLOWED_ELEMENTS.contains(&tag.as_str()) {
+                    let attrs = allowed_attrs(&tag, attrs);
+                    out.push(Node::Element {
+                        tag,
+                        attrs,
+                        children,
+                    });
+                } else {
+                    out.extend(children);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+pub fn serialize(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(&escape(text, false)),
+            Node::Element {
+                tag,
+                attrs,
+                children,
+            } => {
+                out.push('<');
+                out.push_str(tag);
+                for (name, value) in attrs {
+                    out.push_str(&format!(" {}=\"{}\"", name, escape(value, true)));
+                }
+                out.push('>');
+                if VOID_ELEMENTS.contains(&tag.as_str()) {
+                    continue;
+                }
+                out.push_str(&serialize(children));
+                out.push_str(&format!("</{}>", tag));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(html: &str) -> String {
+        serialize(&sanitize(parse(html)))
+    }
+
+    #[test]
+    fn test_parse_nested_elements_and_attributes() {
+        let nodes = parse(r#"<p class="x">Hello <a href='https://a.b/?q=1&amp;r=2'>link</a></p>"#);
+        assert_eq!(nodes.len(), 1);
+        let Node::Element { tag, children, .. } = &nodes[0] else {
+            panic!("expected element");
+        };
+        assert_eq!(tag, "p");
+        assert_eq!(children[1].attr("href"), Some("https://a.b/?q=1&r=2"));
+        assert_eq!(nodes[0].text(), "Hello link");
+    }
+
+    #[test]
+    fn test_entities_are_decoded() {
+        assert_eq!(
+            parse("a &lt;b&gt; &amp; &#39;c&#x27; &unknown; & d")[0].text(),
+            "a <b> & 'c' &unknown; & d"
+        );
+    }
+
+    #[test]
+    fn test_void_and_unclosed_elements() {
+        let nodes = parse("<p>one<br>two<img src=\"https://x/y.png\"><p>three");
+        assert_eq!(nodes.len(), 1, "unclosed <p> swallows the rest");
+        assert_eq!(nodes[0].text(), "onetwothree");
+        assert_eq!(
+            serialize(&nodes),
+            "<p>one<br>two<img src=\"https://x/y.png\"><p>three</p></p>"
+        );
+    }
+
+    #[test]
+    fn test_quoted_angle_brackets_in_attributes() {
+        let nodes = parse(r#"<img alt="a > b" src="https://x/y.png">after"#);
+        assert_eq!(nodes[0].attr("alt"), Some("a > b"));
+        assert_eq!(nodes[1].text(), "after");
+    }
+
+    #[test]
+    fn test_comments_and_stray_end_tags_are_ignored() {
+        assert_eq!(
+            serialize(&parse("<!-- x --><b>bold</i></b>")),
+            "<b>bold</b>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_drops_scripts_and_handlers() {
+        assert_eq!(
+            clean(r#"<p onclick="evil()">hi<script>alert("<p>")</script></p>"#),
+            "<p>hi</p>"
+        );
+        assert_eq!(
+            clean(r#"<a href="javascript:alert(1)">x</a><a href="https://mistral.ai">y</a>"#),
+            r#"<a>x</a><a href="https://mistral.ai">y</a>"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_unwraps_unknown_elements() {
+        assert_eq!(
+            clean(
+                r#"<div class="prose"><span>a</span><button>Copy</button><svg><path/></svg></div>"#
+            ),
+            "a"
+        );
+        assert_eq!(
+            clean(r#"<pre><code class="language-rust hljs">fn main() {}</code></pre>"#),
+            r#"<pre><code class="language-rust hljs">fn main() {}</code></pre>"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_escapes_text() {
+        assert_eq!(
+            clean("<p>1 &lt; 2 &amp;&amp; x</p>"),
+            "<p>1 &lt; 2 &amp;&amp; x</p>"
+        );
+    }
+}
@@ -1,10 +1,12 @@
-#[cfg(target_os = "macos")]
-use tauri::TitleBarStyle;
 use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Listener, Manager, RunEvent,
+    webview::{NewWindowFeatures, NewWindowResponse, PageLoadEvent},
+    AppHandle, Emitter, Listener, Manager, WebviewWindow, WebviewWindowBuilder,
 };
+#[cfg(target_os = "macos")]
+use tauri::{RunEvent, TitleBarStyle};
 // TODO: Migrate from `cocoa`/`objc` to `objc2`/`icrate` crates when Tauri ecosystem supports it.
 // The `cocoa` crate marks these APIs as deprecated in favor of the objc2 ecosystem.
 #[cfg(target_os = "macos")]
@@ -15,97 +17,72 @@ use cocoa::appkit::{NSColor, NSWindow};
 use cocoa::base::{id, nil, NO};
 #[cfg(target_os = "macos")]
 use objc::{msg_send, sel, sel_impl};
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-struct AppSettings {
-    new_chat_default: bool,
-    notifications_enabled: bool,
-}
+use chrono::TimeZone;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod batch;
+mod cli;
+pub mod control;
+mod deep_link;
+mod hooks;
+mod html;
+mod main_state;
+mod markdown;
+pub mod mcp;
+mod mistral_api;
+mod native_host;
+mod navigation;
+mod outbox;
+mod page_load;
+mod pipeline;
+mod profiles;
+mod response;
+mod schedule;
+mod scripts;
+mod settings;
+mod shortcuts;
+mod submissions;
+
+use cli::{CliCommand, Invocation};
+use control::{ControlEvent, ControlMethod};
+use deep_link::{DeepLink, LinkPrompt, PendingLinkPrompt, PromptSource};
+use hooks::HookEvent;
+use main_state::{dimmed_icon, Heartbeat, MainState, MainStateInfo, MainStateTracker, StateChange};
+use mistral_api::{ApiConversation, ApiSettings};
+use navigation::Destination;
+use outbox::OutboxItem;
+use page_load::{PageLoadTracker, NAVIGATION_TIMEOUT};
+use pipeline::{AskMessage, AskSubscribers, Snapshot};
+use profiles::{Profile, DEFAULT_PROFILE};
+use response::{
+    CapturedResponse, ConversationSnapshot, InlineAnswer, LastResponse, SnapshotRequests,
+};
+use schedule::{Decision, Schedule, ScheduledPrompt, ScheduledRun, ScheduledRuns};
+use settings::{AppSettings, LauncherBackend};
+use shortcuts::{ShortcutAction, ShortcutFailure, ShortcutStatus};
+use submissions::{
+    InjectResult, QueuedMessage, ResponseComplete, ResponseProgress, Submission, SubmissionQueue,
+    SubmissionStatus, SubmissionTracker, STREAM_START_GRACE,
+};
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        Self {
-            new_chat_default: true,
-            notifications_enabled: true,
-        }
-    }
-}
+const TRAY_ID: &str = "main";
 
-// JavaScript to inject custom styles that hide UI elements overlapping with title bar
-fn get_hide_titlebar_overlap_js() -> String {
-    r#"
-    (function() {
-        const STYLE_ID = 'le-chat-custom-styles';
-        
-        function injectStyles() {
-            // Avoid duplicate injection
-            if (document.getElementById(STYLE_ID)) return;
-            
-            const style = document.createElement('style');
-            style.id = STYLE_ID;
-            style.textContent = `
-                /* Hide the workspace menu button that overlaps title bar */
-                div[data-sidebar="header"] button[aria-haspopup="menu"] {
-                    display: none !important;
-                }
-                
-                /* Hide the flex-1 wrapper containing the workspace button */
-                div[data-sidebar="header"] .flex-1:has(button[aria-haspopup="menu"]) {
-                    display: none !important;
-                }
-                
-                /* Make the button container full width and push buttons to the right */
-                div[data-sidebar="header"] > div.flex {
-                    width: 100% !important;
-                    justify-content: flex-end !important;
-                }
-                
-                /* Add top padding to sidebar header to clear macOS traffic lights */
-                div[data-sidebar="header"] {
-                    padding-top: 2.5rem !important;
-                }
-            `;
-            document.head.appendChild(style);
-            
-            // Fallback for browsers without :has() support
-            document.querySelectorAll('div[data-sidebar="header"] button[aria-haspopup="menu"]').forEach(btn => {
-                btn.style.display = 'none';
-                // Also hide the flex-1 wrapper parent
-                const wrapper = btn.closest('.flex-1');
-                if (wrapper) {
-                    wrapper.style.display = 'none';
-                }
-            });
-            
-            // Push buttons to the right (fallback)
-            document.querySelectorAll('div[data-sidebar="header"] > div.flex').forEach(container => {
-                container.style.width = '100%';
-                container.style.justifyContent = 'flex-end';
-            });
-            
-            console.log('[Le Chat] Custom styles injected');
-        }
-        
-        // Retry until DOM is ready
-        if (document.readyState === 'loading') {
-            document.addEventListener('DOMContentLoaded', injectStyles);
-        } else {
-            injectStyles();
-        }
-        
-        // Re-inject on dynamic navigation (React SPA)
-        new MutationObserver(() => injectStyles()).observe(
-            document.documentElement, 
-            { childList: true, subtree: true }
-        );
-    })();
-    "#.to_string()
-}
+// How long the main window has to read out the open conversation
+const CONVERSATION_SNAPSHOT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// JavaScript expression that finds Le Chat's stop/cancel button, which is only
+// shown while a response is streaming
+const STOP_BUTTON_JS: &str = r#"(document.querySelector('button[aria-label*="stop" i]')
+            || document.querySelector('button[aria-label*="Stop" i]')
+            || document.querySelector('button[aria-label*="cancel" i]')
+            || document.querySelector('button[data-testid*="stop" i]'))"#;
 
 // JavaScript to inject message into Mistral's chat input with retry logic
-// Emits 'inject-result' Tauri event with { success: bool, error?: string }
-fn get_inject_message_js(message: &str) -> String {
+// Reports { id: number, success: bool, error?: string } through `report_inject_result`,
+// where `id` is the submission's correlation id.
+fn get_inject_message_js(message: &str, submission_id: u64) -> String {
     let escaped_message = message
         .replace('\\', "\\\\")
         .replace('`', "\\`")
@@ -117,16 +94,18 @@ fn get_inject_message_js(message: &str) -> String {
         r#"
         (function() {{
             const message = `{}`;
+            const submissionId = {};
             const maxRetries = 15;
             const retryDelay = 300;
             const totalTimeout = 8000;
             let retryCount = 0;
             let timedOut = false;
             
-            // Emit result back to Tauri so the launcher can show feedback
+            // Report the result back to Tauri so the launcher can show feedback
             function emitResult(success, error) {{
                 if (window.__TAURI__) {{
-                    window.__TAURI__.event.emit('inject-result', {{ success, error: error || null }});
+                    window.__TAURI__.core.invoke('report_inject_result', {{ result: {{ id: submissionId, success, error: error || null }} }})
+                        .catch(e => console.error('[Le Chat]', e));
                 }}
             }}
             
@@ -240,7 +219,7 @@ fn get_inject_message_js(message: &str) -> String {
             injectMessage();
         }})();
     "#,
-        escaped_message
+        escaped_message, submission_id
     )
 }
 
@@ -279,63 +258,295 @@ async fn toggle_launcher(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+// Launcher window sizes (logical pixels): the prompt bar alone, and with an inline answer
+const LAUNCHER_WIDTH: f64 = 660.0;
+const LAUNCHER_HEIGHT: f64 = 88.0;
+const LAUNCHER_EXPANDED_HEIGHT: f64 = 480.0;
+
+// Grow the launcher into an answer panel, or shrink it back to the prompt bar
+#[tauri::command]
+async fn set_launcher_expanded(app: AppHandle, expanded: bool) -> Result<(), String> {
+    if let Some(launcher) = app.get_webview_window("launcher") {
+        let height = if expanded {
+            LAUNCHER_EXPANDED_HEIGHT
+        } else {
+            LAUNCHER_HEIGHT
+        };
+        launcher
+            .set_size(tauri::LogicalSize::new(LAUNCHER_WIDTH, height))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
 const CHAT_URL: &str = "https://chat.mistral.ai/chat";
 
-// JavaScript to inject a MutationObserver that detects when the AI finishes responding.
-// It watches for the "stop generating" button to disappear, which signals completion.
-// Emits 'response-complete' Tauri event when the response finishes.
-fn get_response_watcher_js() -> String {
+// JavaScript to click Le Chat's "stop generating" button, if a response is streaming.
+// Uses the same selectors as the response watcher's streaming detection.
+fn get_stop_generating_js() -> String {
+    format!(
+        r#"
+    (function() {{
+        const stopBtn = {};
+        if (stopBtn) {{
+            console.log('[Le Chat] Stopping generation');
+            stopBtn.click();
+        }}
+    }})();
+    "#,
+        STOP_BUTTON_JS
+    )
+}
+
+// Initialization script for the main webview: a heartbeat that feeds the main window
+// state machine. Every 500ms it checks connectivity, whether a response is streaming
+// (same stop-button heuristic as the response watcher) and whether the user is signed
+// in, and reports through `report_heartbeat` when any of them changes, and at least every 5s.
+// Runs on the local offline page too, so coming back online is seen from either page.
+fn get_heartbeat_js() -> String {
+    format!(
+        r#"
+    (function() {{
+        if (window.__leChatHeartbeat) return;
+        window.__leChatHeartbeat = true;
+
+        const CHECK_INTERVAL = 500;
+        const MAX_SILENCE = 5000;
+        let last = null;
+        let lastSent = 0;
+
+        function signedIn() {{
+            if (!window.location.hostname.endsWith('mistral.ai')) return null;
+            if (/\/(login|signin|sign-in|signup|auth)\b/i.test(window.location.pathname)) return false;
+            if (document.querySelector('textarea')) return true;
+            const loginLink = document.querySelector('a[href*="login" i], a[href*="signin" i], button[data-testid*="login" i]');
+            return !loginLink;
+        }}
+
+        function report(force) {{
+            if (!window.__TAURI__) return;
+            const beat = {{
+                url: window.location.href,
+                online: navigator.onLine,
+                streaming: !!{},
+                signed_in: signedIn(),
+            }};
+            const key = JSON.stringify(beat);
+            const now = Date.now();
+            if (!force && key === last && now - lastSent < MAX_SILENCE) return;
+            last = key;
+            lastSent = now;
+            window.__TAURI__.core.invoke('report_heartbeat', {{ heartbeat: beat }}).catch(() => {{}});
+        }}
+
+        window.addEventListener('online', () => report(true));
+        window.addEventListener('offline', () => report(true));
+        if (document.readyState === 'loading') {{
+            document.addEventListener('DOMContentLoaded', () => report(true), {{ once: true }});
+        }} else {{
+            report(true);
+        }}
+        setInterval(() => report(false), CHECK_INTERVAL);
+    }})();
+    "#,
+        STOP_BUTTON_JS
+    )
+}
+
+// Initialization script for the private window: a purple frame around the page
+// with a "Private" tab at the top, so it can't be mistaken for the main window.
+// Attached to the document element, outside the page's own markup.
+fn get_private_window_js() -> String {
     r#"
     (function() {
-        if (window.__leChatResponseWatcher) return;
-        window.__leChatResponseWatcher = true;
+        const FRAME_ID = 'le-chat-private-frame';
+
+        function mark() {
+            if (document.getElementById(FRAME_ID)) return;
+            const frame = document.createElement('div');
+            frame.id = FRAME_ID;
+            frame.style.cssText = 'position:fixed;inset:0;border:3px solid #7c3aed;' +
+                'pointer-events:none;z-index:2147483647;';
+            const tab = document.createElement('div');
+            tab.textContent = 'Private';
+            tab.style.cssText = 'position:absolute;top:0;left:50%;transform:translateX(-50%);' +
+                'padding:1px 12px 3px;border-radius:0 0 6px 6px;background:#7c3aed;color:#fff;' +
+                'font:600 11px/1.4 -apple-system,BlinkMacSystemFont,"Segoe UI",sans-serif;';
+            frame.appendChild(tab);
+            document.documentElement.appendChild(frame);
+        }
+
+        if (document.readyState === 'loading') {
+            document.addEventListener('DOMContentLoaded', mark, { once: true });
+        } else {
+            mark();
+        }
+    })();
+    "#
+    .to_string()
+}
+
+// JavaScript expression that finds the last assistant message in the conversation
+const LAST_ASSISTANT_MESSAGE_JS: &str = r#"(function() {
+            const selectors = [
+                '[data-message-author-role="assistant"]',
+                '[data-testid*="assistant-message" i]',
+                '[data-role="assistant"]',
+                '[class*="assistant" i] .prose',
+                '.prose',
+                '[class*="markdown" i]',
+            ];
+            for (const selector of selectors) {
+                const matches = document.querySelectorAll(selector);
+                if (matches.length) return matches[matches.length - 1];
+            }
+            return null;
+        })()"#;
+
+// How long a response watcher waits for a finished reply
+const RESPONSE_WATCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+// JavaScript to inject a MutationObserver that follows the reply to a submission.
+// Each batch of DOM changes is examined at most every 100ms: the reply is the newest
+// assistant message that wasn't there when the watcher started, and the response
+// is complete once the "stop generating" button has appeared and gone again.
+// A reply that streams entirely between two checks never shows the button; it is
+// complete once it has stopped changing for a moment.
+// With `stream_progress`, reports { id, html, url } through `report_response_progress`
+// whenever the reply's markup changes. Reports { id, html: string | null, url, title }
+// through `report_response_complete` when the response finishes, where `html` is the
+// last assistant message's markup, or the id through `report_response_failed` if no
+// finished reply is seen within `RESPONSE_WATCH_TIMEOUT`.
+fn get_response_watcher_js(submission_id: u64, stream_progress: bool) -> String {
+    format!(
+        r#"
+    (function() {{
+        const submissionId = {};
+        const streamProgress = {};
+        // One watcher per submission, so overlapping prompts don't cancel each other
+        window.__leChatResponseWatchers = window.__leChatResponseWatchers || {{}};
+        if (window.__leChatResponseWatchers[submissionId]) return;
+        window.__leChatResponseWatchers[submissionId] = true;
         
-        const CHECK_INTERVAL = 500;
-        const INITIAL_DELAY = 2000;
+        const THROTTLE_MS = 100;
+        const SETTLE_MS = 3000;
+        const MAX_WATCH_MS = {};
         let wasStreaming = false;
-        let checkCount = 0;
-        const MAX_CHECKS = 600; // 5 minutes max watch time
+        let lastHtml = null;
+        let pending = null;
+        let settle = null;
         
-        function isStreaming() {
+        function isStreaming() {{
             // Check for stop/cancel button which appears during streaming
-            const stopBtn = document.querySelector('button[aria-label*="stop" i]')
-                || document.querySelector('button[aria-label*="Stop" i]')
-                || document.querySelector('button[aria-label*="cancel" i]')
-                || document.querySelector('button[data-testid*="stop" i]');
-            return !!stopBtn;
-        }
+            return !!{};
+        }}
         
-        // Wait for streaming to start before watching for completion
-        setTimeout(() => {
-            const intervalId = setInterval(() => {
-                checkCount++;
-                
-                if (checkCount > MAX_CHECKS) {
-                    clearInterval(intervalId);
-                    window.__leChatResponseWatcher = false;
-                    return;
-                }
-                
-                const streaming = isStreaming();
-                
-                if (streaming) {
-                    wasStreaming = true;
-                }
-                
-                // Streaming just stopped (was streaming, now it's not)
-                if (wasStreaming && !streaming) {
-                    clearInterval(intervalId);
-                    window.__leChatResponseWatcher = false;
-                    console.log('[Le Chat] Response complete');
-                    if (window.__TAURI__) {
-                        window.__TAURI__.event.emit('response-complete', {});
-                    }
-                }
-            }, CHECK_INTERVAL);
-        }, INITIAL_DELAY);
-    })();
-    "#
-    .to_string()
+        function lastAssistantMessage() {{
+            return {};
+        }}
+        
+        // Messages already on the page belong to earlier turns
+        const previousMessage = lastAssistantMessage();
+        function currentReply() {{
+            const message = lastAssistantMessage();
+            return message && message !== previousMessage ? message : null;
+        }}
+        
+        function stop() {{
+            observer.disconnect();
+            clearTimeout(pending);
+            clearTimeout(settle);
+            clearTimeout(giveUp);
+            delete window.__leChatResponseWatchers[submissionId];
+        }}
+        
+        function complete(reply) {{
+            stop();
+            console.log('[Le Chat] Response complete');
+            if (window.__TAURI__) {{
+                const message = reply || lastAssistantMessage();
+                window.__TAURI__.core.invoke('report_response_complete', {{ complete: {{ id: submissionId, html: message ? message.innerHTML : null, url: window.location.href, title: document.title }} }})
+                    .catch(e => console.error('[Le Chat]', e));
+            }}
+        }}
+        
+        function fail() {{
+            stop();
+            if (window.__TAURI__) {{
+                window.__TAURI__.core.invoke('report_response_failed', {{ id: submissionId }})
+                    .catch(e => console.error('[Le Chat]', e));
+            }}
+        }}
+        
+        function check() {{
+            pending = null;
+            const streaming = isStreaming();
+            if (streaming) {{
+                wasStreaming = true;
+            }}
+            
+            const reply = currentReply();
+            if (streamProgress && reply && reply.innerHTML !== lastHtml && window.__TAURI__) {{
+                lastHtml = reply.innerHTML;
+                window.__TAURI__.core.invoke('report_response_progress', {{ progress: {{ id: submissionId, html: lastHtml, url: window.location.href }} }})
+                    .catch(e => console.error('[Le Chat]', e));
+            }}
+            
+            // Streaming just stopped (was streaming, now it's not)
+            if (wasStreaming && !streaming) {{
+                complete(reply);
+                return;
+            }}
+            
+            // A new reply without the stop button ever seen: wait for it to settle
+            if (!wasStreaming && reply) {{
+                clearTimeout(settle);
+                settle = setTimeout(() => {{
+                    if (!wasStreaming && !isStreaming()) complete(currentReply());
+                }}, SETTLE_MS);
+            }}
+        }}
+        
+        function schedule() {{
+            if (pending === null) {{
+                pending = setTimeout(check, THROTTLE_MS);
+            }}
+        }}
+        
+        const observer = new MutationObserver(schedule);
+        observer.observe(document.body, {{ childList: true, subtree: true, characterData: true }});
+        const giveUp = setTimeout(fail, MAX_WATCH_MS);
+        schedule();
+    }})();
+    "#,
+        submission_id,
+        stream_progress,
+        RESPONSE_WATCH_TIMEOUT.as_millis(),
+        STOP_BUTTON_JS,
+        LAST_ASSISTANT_MESSAGE_JS
+    )
+}
+
+// JavaScript that reads the open conversation for `le-chat mcp`.
+// Reports { id, url, title, messages: [{ role, html }] } through `report_conversation_snapshot`.
+// Pages without role markers fall back to the last assistant message alone.
+fn get_conversation_snapshot_js(request_id: u64) -> String {
+    format!(
+        r#"
+    (function() {{
+        if (!window.__TAURI__) return;
+        let messages = Array.from(document.querySelectorAll('[data-message-author-role]'))
+            .map(el => ({{ role: el.getAttribute('data-message-author-role') === 'user' ? 'user' : 'assistant', html: el.innerHTML }}));
+        if (!messages.length) {{
+            const last = {};
+            if (last) messages = [{{ role: 'assistant', html: last.innerHTML }}];
+        }}
+        window.__TAURI__.core.invoke('report_conversation_snapshot', {{ snapshot: {{ id: {}, url: window.location.href, title: document.title, messages }} }})
+            .catch(e => console.error('[Le Chat]', e));
+    }})();
+    "#,
+        LAST_ASSISTANT_MESSAGE_JS, request_id
+    )
 }
 
 #[tauri::command]
@@ -354,9 +565,11 @@ async fn navigate_to_offline(app: AppHandle) -> Result<(), String> {
         let url = "tauri://localhost/index.html"
             .parse::<tauri::Url>()
             .map_err(|e| e.to_string())?;
+        let tracker = app.state::<PageLoadTracker>();
+        let since = tracker.current().id + 1;
         main_window.navigate(url).map_err(|e| e.to_string())?;
-        // Switch to offline state after navigation
-        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        // Switch to offline state once the offline page's DOM is ready
+        tracker.wait_until_ready(since, NAVIGATION_TIMEOUT).await?;
         let _ = main_window
             .eval("document.getElementById('main-container').className = 'container offline';");
     }
@@ -372,271 +585,2093 @@ async fn navigate_to_chat(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-#[tauri::command]
-async fn submit_message(app: AppHandle, message: String, new_chat: bool) -> Result<(), String> {
-    // Hide the launcher first
-    if let Some(launcher) = app.get_webview_window("launcher") {
-        launcher.hide().map_err(|e| e.to_string())?;
+// How long to wait for the injection script's `inject-result` once it has been
+// evaluated. Slightly longer than the script's own 8s timeout.
+const DELIVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// Record a submission status change and notify the launcher
+fn set_submission_status(
+    app: &AppHandle,
+    id: u64,
+    status: SubmissionStatus,
+    error: Option<String>,
+) -> Option<Submission> {
+    let submission = app.state::<SubmissionTracker>().update(id, status, error)?;
+    let _ = app.emit("submission-status", &submission);
+    if matches!(
+        status,
+        SubmissionStatus::Failed | SubmissionStatus::Cancelled | SubmissionStatus::Outboxed
+    ) {
+        let message = match (status, &submission.error) {
+            (SubmissionStatus::Outboxed, _) => {
+                "Le Chat is offline; the prompt was kept in the outbox"
+            }
+            (SubmissionStatus::Cancelled, _) => "The prompt was cancelled",
+            (_, Some(error)) => error.as_str(),
+            (_, None) => "The prompt couldn't be sent",
+        };
+        app.state::<AskSubscribers>().notify(
+            id,
+            AskMessage::Error {
+                message: message.to_string(),
+            },
+        );
     }
+    Some(submission)
+}
 
-    // Show and focus main window
-    if let Some(main_window) = app.get_webview_window("main") {
-        main_window.show().map_err(|e| e.to_string())?;
-        main_window.set_focus().map_err(|e| e.to_string())?;
+// Show an inline answer in the launcher and pass it on to a waiting `le-chat ask`
+fn publish_answer(app: &AppHandle, answer: &InlineAnswer) {
+    let _ = app.emit_to("launcher", "inline-answer", answer);
+    let markdown = answer.markdown.clone();
+    let message = if answer.done {
+        AskMessage::Done { markdown }
+    } else {
+        AskMessage::Progress { markdown }
+    };
+    app.state::<AskSubscribers>()
+        .notify(answer.submission_id, message);
+}
 
-        if new_chat {
-            // Navigate to the base chat URL to start a fresh conversation.
-            // The injected JS retry logic will wait for the new page's textarea.
-            let url = CHAT_URL.parse::<tauri::Url>().map_err(|e| e.to_string())?;
-            main_window.navigate(url).map_err(|e| e.to_string())?;
-            // Give the navigation a moment to start before injecting JS
-            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-        } else {
-            // Small delay to let the window become visible
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+// A finished response: mark the submission answered, keep the reply, run the
+// hooks, and answer with a notification, in the launcher or to a scheduled run.
+// A notification is only sent if the main window is not focused (user switched away).
+fn on_response_complete(app: &AppHandle, complete: ResponseComplete) {
+    let mut is_inline = false;
+    if let Some(id) = complete.id {
+        let queue = app.state::<SubmissionQueue>();
+        queue.clear_expected_response(id);
+        queue.stop_following(id);
+        is_inline = set_submission_status(app, id, SubmissionStatus::Answered, None)
+            .is_some_and(|submission| submission.inline);
+    }
+
+    // Keep the reply so it can be copied, archived or excerpted
+    let response = complete
+        .html
+        .as_deref()
+        .map(|html| CapturedResponse::capture(complete.id, html, &complete.url, &complete.title));
+    if let Some(response) = &response {
+        app.state::<LastResponse>().set(response.clone());
+        let _ = app.emit("last-response", response);
+    }
+    run_hooks(
+        app,
+        HookEvent::ResponseComplete,
+        serde_json::json!({
+            "id": complete.id,
+            "url": complete.url,
+            "title": complete.title,
+            "markdown": response.as_ref().map(|r| r.markdown.as_str()),
+        }),
+    );
+
+    // Scheduled prompts answer with a notification from their hidden window
+    if let Some(run) = complete
+        .id
+        .and_then(|id| app.state::<ScheduledRuns>().finish(id))
+    {
+        close_window(app, &run.window);
+        let body = response
+            .map(|r| r.excerpt())
+            .filter(|excerpt| !excerpt.is_empty())
+            .unwrap_or_else(|| "The answer couldn't be read from the page".to_string());
+        notify_scheduled(app, &run.name, &body);
+        return;
+    }
+
+    // Inline answers are already on screen in the launcher
+    if is_inline {
+        match (complete.id, &response) {
+            (Some(id), Some(response)) => {
+                publish_answer(app, &InlineAnswer::complete(id, response));
+            }
+            (Some(id), None) => app.state::<AskSubscribers>().notify(
+                id,
+                AskMessage::Error {
+                    message: "The answer couldn't be read from the page".to_string(),
+                },
+            ),
+            _ => {}
         }
+        return;
+    }
+
+    // Check if notifications are enabled in settings
+    let notifications_enabled = settings::load(app).notifications_enabled;
 
-        // Inject the message — JS will emit 'inject-result' event with success/failure.
-        // The injected JS has robust retry logic (up to 15 attempts / 8s timeout)
-        // to wait for the textarea to become available after navigation.
-        let js = get_inject_message_js(&message);
-        main_window.eval(&js).map_err(|e| e.to_string())?;
+    if !notifications_enabled {
+        return;
+    }
 
-        // Inject response watcher to detect when the AI finishes responding.
-        // This will emit 'response-complete' event for notification handling.
-        let watcher_js = get_response_watcher_js();
-        main_window.eval(&watcher_js).map_err(|e| e.to_string())?;
+    let is_focused = app
+        .get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false);
+
+    if !is_focused {
+        use tauri_plugin_notification::NotificationExt;
+        let _ = app
+            .notification()
+            .builder()
+            .title("Le Chat")
+            .body(
+                response
+                    .map(|r| r.excerpt())
+                    .filter(|excerpt| !excerpt.is_empty())
+                    .unwrap_or_else(|| "Response ready".to_string()),
+            )
+            .show();
+
+        // Show main window (don't auto-focus — let user click notification)
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.show();
+        }
     }
+}
 
+// The scripts injected into the chat windows report back through these
+// commands rather than events, which any script on the page could send. Each
+// one only takes what the app is waiting for from the window that calls it.
+fn check_reporter(window: &WebviewWindow, expected: &str) -> Result<(), String> {
+    if window.label() != expected {
+        return Err(format!("The {} window can't report this", window.label()));
+    }
     Ok(())
 }
 
 #[tauri::command]
-async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
-    use tauri_plugin_store::StoreExt;
-    let store = app.store("settings.json").map_err(|e| e.to_string())?;
-    let settings = match store.get("app_settings") {
-        Some(value) => serde_json::from_value(value).unwrap_or_default(),
-        None => AppSettings::default(),
+async fn report_dom_ready(window: WebviewWindow, url: String) -> Result<(), String> {
+    check_reporter(&window, "main")?;
+    window.state::<PageLoadTracker>().dom_ready(&url);
+    Ok(())
+}
+
+// Heartbeats from the main window feed its state machine
+#[tauri::command]
+async fn report_heartbeat(window: WebviewWindow, heartbeat: Heartbeat) -> Result<(), String> {
+    check_reporter(&window, "main")?;
+    let change = window.state::<MainStateTracker>().heartbeat(heartbeat);
+    on_main_state_change(window.app_handle(), change);
+    Ok(())
+}
+
+// Route injection results to the submission that is waiting for them
+#[tauri::command]
+async fn report_inject_result(window: WebviewWindow, result: InjectResult) -> Result<(), String> {
+    let app = window.app_handle();
+    let id = result.id.ok_or("The result has no submission id")?;
+    let outcome = if result.success {
+        Ok(())
+    } else {
+        Err(result
+            .error
+            .clone()
+            .unwrap_or_else(|| "Message injection failed".to_string()))
     };
-    Ok(settings)
+    if !app
+        .state::<SubmissionTracker>()
+        .report_delivery(id, window.label(), outcome.clone())
+    {
+        return Err(format!("No message {} is being sent from this window", id));
+    }
+    if let Err(error) = &outcome {
+        run_hooks(
+            app,
+            HookEvent::InjectFailed,
+            serde_json::json!({ "id": id, "error": error }),
+        );
+    }
+    let _ = app.emit("inject-result", &result);
+    Ok(())
 }
 
+// Stream inline answers to the launcher as the reply grows
 #[tauri::command]
-async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
-    use tauri_plugin_store::StoreExt;
-    let store = app.store("settings.json").map_err(|e| e.to_string())?;
-    let value = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
-    store.set("app_settings", value);
-    store.save().map_err(|e| e.to_string())?;
+async fn report_response_progress(
+    window: WebviewWindow,
+    progress: ResponseProgress,
+) -> Result<(), String> {
+    let app = window.app_handle();
+    let is_inline = app
+        .state::<SubmissionTracker>()
+        .get(progress.id)
+        .is_some_and(|submission| submission.inline);
+    if !is_inline
+        || !app
+            .state::<SubmissionTracker>()
+            .is_watching(progress.id, window.label())
+    {
+        return Err(format!(
+            "No inline answer {} is expected from this window",
+            progress.id
+        ));
+    }
+    let answer = InlineAnswer::partial(progress.id, &progress.html, &progress.url);
+    publish_answer(app, &answer);
+    Ok(())
+}
 
-    // Emit settings-changed event so other windows can react
-    let _ = app.emit("settings-changed", &settings);
+#[tauri::command]
+async fn report_response_complete(
+    window: WebviewWindow,
+    complete: ResponseComplete,
+) -> Result<(), String> {
+    let app = window.app_handle();
+    let id = complete.id.ok_or("The response has no submission id")?;
+    if !app
+        .state::<SubmissionTracker>()
+        .finish_response(id, window.label())
+    {
+        return Err(format!("No response {} is expected from this window", id));
+    }
+    let _ = app.emit("response-complete", &complete);
+    on_response_complete(app, complete);
     Ok(())
 }
 
+// A response watcher gave up waiting for the reply
 #[tauri::command]
-async fn show_settings(app: AppHandle) -> Result<(), String> {
-    if let Some(settings) = app.get_webview_window("settings") {
-        settings.show().map_err(|e| e.to_string())?;
-        settings.set_focus().map_err(|e| e.to_string())?;
+async fn report_response_failed(window: WebviewWindow, id: u64) -> Result<(), String> {
+    let app = window.app_handle();
+    if !app
+        .state::<SubmissionTracker>()
+        .finish_response(id, window.label())
+    {
+        return Err(format!("No response {} is expected from this window", id));
+    }
+    let error = format!(
+        "No finished answer within {} minutes",
+        RESPONSE_WATCH_TIMEOUT.as_secs() / 60
+    );
+    eprintln!("Response to submission {} failed: {}", id, error);
+    fail_response(app, id, &error);
+    // A scheduled run's hidden window has nothing left to wait for
+    if let Some(run) = app.state::<ScheduledRuns>().finish(id) {
+        close_window(app, &run.window);
+        notify_scheduled(app, &run.name, &error);
     }
     Ok(())
 }
 
-fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let show_item = MenuItem::with_id(app, "show", "Show Le Chat", true, None::<&str>)?;
-    let launcher_item = MenuItem::with_id(app, "launcher", "Quick Ask...", true, None::<&str>)?;
-    let separator1 = PredefinedMenuItem::separator(app)?;
-    let settings_item = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
-    let separator2 = PredefinedMenuItem::separator(app)?;
-    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+// Conversations read out for `le-chat mcp`
+#[tauri::command]
+async fn report_conversation_snapshot(
+    window: WebviewWindow,
+    snapshot: ConversationSnapshot,
+) -> Result<(), String> {
+    check_reporter(&window, "main")?;
+    window.state::<SnapshotRequests>().resolve(snapshot);
+    Ok(())
+}
 
-    let menu = Menu::with_items(
-        app,
-        &[
-            &show_item,
-            &launcher_item,
-            &separator1,
-            &settings_item,
-            &separator2,
-            &quit_item,
-        ],
-    )?;
+// Resolves once the page has reported whether the message was sent
+async fn deliver_submission(app: &AppHandle, next: &QueuedMessage) -> Result<(), String> {
+    let main_window = app
+        .get_webview_window("main")
+        .ok_or("Main window is not available")?;
+    // Inline answers are read from the hidden main window
+    if !next.inline {
+        main_window.show().map_err(|e| e.to_string())?;
+        main_window.set_focus().map_err(|e| e.to_string())?;
+    }
 
-    let _tray = TrayIconBuilder::new()
-        .icon(app.default_window_icon().unwrap().clone())
-        .menu(&menu)
-        .show_menu_on_left_click(false)
-        .on_menu_event(|app, event| match event.id.as_ref() {
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
-            "launcher" => {
-                if let Some(launcher) = app.get_webview_window("launcher") {
-                    let _ = launcher.center();
-                    let _ = launcher.show();
-                    let _ = launcher.set_focus();
-                }
-            }
-            "settings" => {
-                if let Some(settings) = app.get_webview_window("settings") {
-                    let _ = settings.show();
-                    let _ = settings.set_focus();
-                }
-            }
-            "quit" => {
-                app.exit(0);
-            }
-            _ => {}
-        })
-        .on_tray_icon_event(|tray, event| {
-            if let TrayIconEvent::Click {
-                button: MouseButton::Left,
-                button_state: MouseButtonState::Up,
-                ..
-            } = event
-            {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
-        })
-        .build(app)?;
+    let tracker = app.state::<PageLoadTracker>();
+    if next.new_chat {
+        // Navigate to the base chat URL to start a fresh conversation,
+        // then wait for that navigation (not the current page) to load.
+        // The injected JS retry logic will wait for the new page's textarea.
+        let url = CHAT_URL.parse::<tauri::Url>().map_err(|e| e.to_string())?;
+        let since = tracker.current().id + 1;
+        main_window.navigate(url).map_err(|e| e.to_string())?;
+        tracker.wait_until_ready(since, NAVIGATION_TIMEOUT).await?;
+    } else {
+        // Make sure the current page is not still loading
+        let since = tracker.current().id;
+        tracker.wait_until_ready(since, NAVIGATION_TIMEOUT).await?;
+    }
 
-    Ok(())
+    // Inject the message — JS will report success/failure through `report_inject_result`.
+    // The injected JS has robust retry logic (up to 15 attempts / 8s timeout)
+    // to wait for the textarea to become available after navigation.
+    let delivery = app
+        .state::<SubmissionTracker>()
+        .expect_delivery(next.id, "main");
+    let js = get_inject_message_js(&next.message, next.id);
+    main_window.eval(&js).map_err(|e| e.to_string())?;
+    set_submission_status(app, next.id, SubmissionStatus::Injected, None);
+
+    // Inject response watcher to detect when the AI finishes responding.
+    // It reports the finished response for notification handling, and streams
+    // progress for inline answers.
+    app.state::<SubmissionTracker>()
+        .watch_response(next.id, "main");
+    app.state::<SubmissionQueue>().follow_response(next.id);
+    let watcher_js = get_response_watcher_js(next.id, next.inline);
+    main_window.eval(&watcher_js).map_err(|e| e.to_string())?;
+
+    wait_for_delivery(delivery).await
 }
 
-fn setup_global_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    // Platform-specific shortcut: Alt+Space on Windows, Option+Space on macOS
-    #[cfg(target_os = "macos")]
-    let shortcut = Shortcut::new(Some(Modifiers::ALT), Code::Space);
-
-    #[cfg(target_os = "windows")]
-    let shortcut = Shortcut::new(Some(Modifiers::ALT), Code::Space);
-
-    #[cfg(target_os = "linux")]
-    let shortcut = Shortcut::new(Some(Modifiers::ALT), Code::Space);
-
-    let app_handle = app.clone();
-
-    app.global_shortcut()
-        .on_shortcut(shortcut, move |_app, _shortcut, event| {
-            if event.state == ShortcutState::Pressed {
-                if let Some(launcher) = app_handle.get_webview_window("launcher") {
-                    let is_visible = launcher.is_visible().unwrap_or(false);
-                    if is_visible {
-                        let _ = launcher.hide();
-                    } else {
-                        let _ = launcher.center();
-                        let _ = launcher.show();
-                        let _ = launcher.set_focus();
+// Wait for the injection script to report whether the message was sent
+async fn wait_for_delivery(
+    delivery: tokio::sync::oneshot::Receiver<Result<(), String>>,
+) -> Result<(), String> {
+    match tokio::time::timeout(DELIVERY_TIMEOUT, delivery).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("Delivery tracking was interrupted".to_string()),
+        Err(_) => Err(format!(
+            "No response from the chat page after {}s",
+            DELIVERY_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+// Give up on the reply to a sent submission, which won't be reported
+fn fail_response(app: &AppHandle, id: u64, error: &str) {
+    let queue = app.state::<SubmissionQueue>();
+    queue.clear_expected_response(id);
+    queue.stop_following(id);
+    set_submission_status(app, id, SubmissionStatus::Failed, Some(error.to_string()));
+}
+
+// The main window started loading a page, which ends the response watchers
+// in the one before it
+fn abandon_main_responses(app: &AppHandle) {
+    for id in app.state::<SubmissionTracker>().abandon_responses("main") {
+        fail_response(app, id, "Le Chat's page changed before the answer finished");
+    }
+}
+
+// Send queued submissions to the main window one at a time, for the lifetime of the app
+async fn run_submission_queue(app: AppHandle) {
+    let queue = app.state::<SubmissionQueue>();
+    loop {
+        let next = queue.next().await;
+        emit_submission_queue(&app);
+
+        match deliver_submission(&app, &next).await {
+            Ok(()) => {
+                // Hold the following message until this response has started
+                // (and then finished), or until it's clear none is coming
+                queue.expect_response(next.id);
+                set_submission_status(&app, next.id, SubmissionStatus::Sent, None);
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(STREAM_START_GRACE).await;
+                    app.state::<SubmissionQueue>()
+                        .clear_expected_response(next.id);
+                });
+            }
+            Err(error) => {
+                // Make sure a late inject-result or reply for this id is dropped
+                let tracker = app.state::<SubmissionTracker>();
+                tracker.resolve_delivery(next.id, Err(error.clone()));
+                tracker.stop_watching(next.id);
+                queue.stop_following(next.id);
+                eprintln!("Failed to send submission {}: {}", next.id, error);
+
+                // Keep prompts that failed for lack of a connection, to resend later
+                if is_offline(&app) {
+                    match outbox::add(&app, &next.message, next.new_chat, Some(error.clone())) {
+                        Ok(_) => {
+                            set_submission_status(
+                                &app,
+                                next.id,
+                                SubmissionStatus::Outboxed,
+                                Some(error),
+                            );
+                            emit_outbox(&app);
+                            continue;
+                        }
+                        Err(e) => eprintln!("Failed to save submission to outbox: {}", e),
                     }
                 }
+                set_submission_status(&app, next.id, SubmissionStatus::Failed, Some(error));
             }
-        })?;
+        }
+    }
+}
 
-    Ok(())
+// How often the scheduler looks for scheduled prompts that are due
+const SCHEDULE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// How long a scheduled prompt's hidden window waits for the answer to finish
+const SCHEDULED_ANSWER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+// Check scheduled prompts for the lifetime of the app
+async fn run_scheduler(app: AppHandle) {
+    loop {
+        check_scheduled_prompts(&app);
+        tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+    }
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
+// Start the scheduled prompts that are due and record when each one last ran,
+// so the schedule carries over restarts. Prompts seen for the first time
+// start counting from now.
+fn check_scheduled_prompts(app: &AppHandle) {
+    let settings = settings::load(app);
+    let now = chrono::Local::now();
+    let stored = schedule::load_last_runs(app);
+    let mut last_runs = stored.clone();
+    // Disabled and removed prompts start afresh if they come back
+    last_runs.retain(|name, _| {
+        settings
+            .scheduled_prompts
+            .iter()
+            .any(|prompt| prompt.enabled && prompt.name == *name)
+    });
+
+    for prompt in settings.scheduled_prompts.iter().filter(|p| p.enabled) {
+        // Schedules are checked when settings are saved
+        let Ok(parsed) = Schedule::parse(&prompt.schedule) else {
+            continue;
+        };
+        let since = last_runs
+            .get(&prompt.name)
+            .and_then(|&time| chrono::Local.timestamp_opt(time, 0).single());
+        if let Some(since) = since {
+            match parsed.decide(&since, &now, settings.missed_runs) {
+                Decision::Wait => continue,
+                Decision::Run => start_scheduled_run(app, prompt.clone()),
+                Decision::Skip => eprintln!(
+                    "Skipping missed run of scheduled prompt \"{}\"",
+                    prompt.name
+                ),
+            }
+        }
+        last_runs.insert(prompt.name.clone(), now.timestamp());
+    }
+
+    if last_runs != stored {
+        if let Err(e) = schedule::save_last_runs(app, &last_runs) {
+            eprintln!("Failed to save scheduled prompt runs: {}", e);
+        }
+    }
+}
+
+fn start_scheduled_run(app: &AppHandle, prompt: ScheduledPrompt) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_scheduled_prompt(&app, &prompt).await {
+            eprintln!("Scheduled prompt \"{}\" failed: {}", prompt.name, e);
+            notify_scheduled(&app, &prompt.name, &e);
+        }
+    });
+}
+
+// Send a scheduled prompt in a new chat in its own hidden window, leaving the
+// main window alone. The 'response-complete' listener finishes the run.
+async fn run_scheduled_prompt(app: &AppHandle, prompt: &ScheduledPrompt) -> Result<(), String> {
+    let home = app.path().home_dir().ok();
+    let message = schedule::expand_files(&prompt.prompt, home.as_deref())?;
+    let submission = app
+        .state::<SubmissionTracker>()
+        .create(&message, true, false);
+    let _ = app.emit("submission-status", &submission);
+    let id = submission.id;
+    let window = format!("scheduled-{}", id);
+    app.state::<ScheduledRuns>().start(
+        id,
+        ScheduledRun {
+            name: prompt.name.clone(),
+            window: window.clone(),
+        },
+    );
+    run_hooks(
+        app,
+        HookEvent::Submission,
+        serde_json::json!({
+            "message": &message,
+            "new_chat": true,
+            "id": id,
+            "inline": false,
+            "scheduled": &prompt.name,
+        }),
+    );
+
+    if let Err(error) = deliver_scheduled_prompt(app, &window, &message, id).await {
+        app.state::<SubmissionTracker>()
+            .resolve_delivery(id, Err(error.clone()));
+        app.state::<ScheduledRuns>().finish(id);
+        close_window(app, &window);
+        set_submission_status(app, id, SubmissionStatus::Failed, Some(error.clone()));
+        return Err(error);
+    }
+    set_submission_status(app, id, SubmissionStatus::Sent, None);
+
+    // Don't keep the window around for an answer that never finishes
+    let app = app.clone();
+    let name = prompt.name.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SCHEDULED_ANSWER_TIMEOUT).await;
+        if let Some(run) = app.state::<ScheduledRuns>().finish(id) {
+            let error = format!(
+                "No answer within {} minutes",
+                SCHEDULED_ANSWER_TIMEOUT.as_secs() / 60
+            );
+            eprintln!("Scheduled prompt \"{}\" failed: {}", name, error);
+            close_window(&app, &run.window);
+            set_submission_status(&app, id, SubmissionStatus::Failed, Some(error.clone()));
+            notify_scheduled(&app, &run.name, &error);
+        }
+    });
+    Ok(())
+}
+
+// Open Le Chat in a hidden window of the open profile and type the message in,
+// as the submission queue does in the main window
+async fn deliver_scheduled_prompt(
+    app: &AppHandle,
+    label: &str,
+    message: &str,
+    id: u64,
+) -> Result<(), String> {
+    let url = CHAT_URL.parse::<tauri::Url>().map_err(|e| e.to_string())?;
+    let (loaded, on_loaded) = tokio::sync::oneshot::channel();
+    let loaded = Mutex::new(Some(loaded));
+    let builder = WebviewWindowBuilder::new(app, label, tauri::WebviewUrl::External(url))
+        .title("Le Chat")
+        .visible(false)
+        .on_page_load(move |_, payload| {
+            if matches!(payload.event(), PageLoadEvent::Finished) {
+                if let Some(loaded) = loaded.lock().unwrap().take() {
+                    let _ = loaded.send(());
+                }
+            }
+        });
+    // Signed in to the same account as the main window
+    let profile = settings::load(app).active_profile;
+    let window = with_profile_data(builder, app, &profile)
+        .and_then(|builder| builder.build())
+        .map_err(|e| e.to_string())?;
+    match tokio::time::timeout(NAVIGATION_TIMEOUT, on_loaded).await {
+        Ok(Ok(())) => {}
+        _ => return Err("Le Chat didn't load in time".to_string()),
+    }
+
+    let tracker = app.state::<SubmissionTracker>();
+    let delivery = tracker.expect_delivery(id, label);
+    window
+        .eval(get_inject_message_js(message, id))
+        .map_err(|e| e.to_string())?;
+    set_submission_status(app, id, SubmissionStatus::Injected, None);
+    tracker.watch_response(id, label);
+    window
+        .eval(get_response_watcher_js(id, false))
+        .map_err(|e| e.to_string())?;
+    wait_for_delivery(delivery).await
+}
+
+fn close_window(app: &AppHandle, label: &str) {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.destroy();
+    }
+}
+
+// Answer or error of a scheduled prompt, titled with its name
+fn notify_scheduled(app: &AppHandle, name: &str, body: &str) {
+    if !settings::load(app).notifications_enabled {
+        return;
+    }
+    use tauri_plugin_notification::NotificationExt;
+    let _ = app.notification().builder().title(name).body(body).show();
+}
+
+// Submissions still waiting in the queue, oldest first
+fn queued_submissions(app: &AppHandle) -> Vec<Submission> {
+    let tracker = app.state::<SubmissionTracker>();
+    app.state::<SubmissionQueue>()
+        .pending_ids()
+        .into_iter()
+        .filter_map(|id| tracker.get(id))
+        .collect()
+}
+
+// Broadcast the queue to the launcher and mirror it in the tray menu
+fn emit_submission_queue(app: &AppHandle) {
+    let _ = app.emit("submission-queue", &queued_submissions(app));
+    if let Err(e) = update_tray_menu(app) {
+        eprintln!("Failed to update tray menu: {}", e);
+    }
+}
+
+// Whether Le Chat is unreachable, e.g. the main window shows the local offline page
+fn is_offline(app: &AppHandle) -> bool {
+    app.state::<MainStateTracker>().current().state == MainState::Offline
+}
+
+// Publish a main window state change and keep everything that depends on it in sync
+fn on_main_state_change(app: &AppHandle, change: Option<StateChange>) {
+    app.state::<SubmissionQueue>()
+        .set_streaming(app.state::<MainStateTracker>().is_streaming());
+
+    let Some(change) = change else {
+        return;
+    };
+    let _ = app.emit("main-state-changed", &change.info);
+    update_tray_status(app, change.info.state);
+
+    if change.info.state == MainState::Offline && change.previous != MainState::Offline {
+        run_hooks(app, HookEvent::Offline, serde_json::json!(change.info));
+    } else if change.reconnected {
+        run_hooks(app, HookEvent::Online, serde_json::json!(change.info));
+    }
+
+    if change.reconnected {
+        match outbox::take_all(app) {
+            Ok(items) => resend_outbox_items(app, items),
+            Err(e) => eprintln!("Failed to read outbox: {}", e),
+        }
+    }
+}
+
+#[tauri::command]
+async fn get_last_response(
+    last: tauri::State<'_, LastResponse>,
+) -> Result<Option<CapturedResponse>, String> {
+    Ok(last.get())
+}
+
+#[tauri::command]
+async fn get_main_state(
+    tracker: tauri::State<'_, MainStateTracker>,
+) -> Result<MainStateInfo, String> {
+    Ok(tracker.current())
+}
+
+fn emit_outbox(app: &AppHandle) {
+    let _ = app.emit("outbox-changed", &outbox::load(app));
+    if let Err(e) = update_tray_menu(app) {
+        eprintln!("Failed to update tray menu: {}", e);
+    }
+}
+
+// Create a submission and put it in the queue
+fn enqueue_submission(
+    app: &AppHandle,
+    message: String,
+    new_chat: bool,
+    inline: bool,
+) -> Submission {
+    let submission = app
+        .state::<SubmissionTracker>()
+        .create(&message, new_chat, inline);
+    let _ = app.emit("submission-status", &submission);
+    app.state::<SubmissionQueue>().push(QueuedMessage {
+        id: submission.id,
+        message,
+        new_chat,
+        inline,
+    });
+    emit_submission_queue(app);
+    submission
+}
+
+// Move outbox items back into the submission queue
+fn resend_outbox_items(app: &AppHandle, items: Vec<OutboxItem>) {
+    if items.is_empty() {
+        return;
+    }
+    for item in items {
+        // The launcher that asked may be long gone, so resent replies go to the main window
+        enqueue_submission(app, item.message, item.new_chat, false);
+    }
+    emit_outbox(app);
+}
+
+fn resend_outbox_item_by_id(app: &AppHandle, id: u64) -> Result<(), String> {
+    let item = outbox::take(app, id)?.ok_or("This message is no longer in the outbox")?;
+    resend_outbox_items(app, vec![item]);
+    Ok(())
+}
+
+fn discard_outbox_item_by_id(app: &AppHandle, id: u64) -> Result<(), String> {
+    outbox::take(app, id)?.ok_or("This message is no longer in the outbox")?;
+    emit_outbox(app);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_outbox(app: AppHandle) -> Result<Vec<OutboxItem>, String> {
+    Ok(outbox::load(&app))
+}
+
+#[tauri::command]
+async fn resend_outbox_item(app: AppHandle, id: u64) -> Result<(), String> {
+    resend_outbox_item_by_id(&app, id)
+}
+
+#[tauri::command]
+async fn discard_outbox_item(app: AppHandle, id: u64) -> Result<(), String> {
+    discard_outbox_item_by_id(&app, id)
+}
+
+// How often a streaming API reply is re-rendered for the launcher
+const API_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+// Send a launcher prompt to the chat completions API and stream the reply to
+// the launcher as 'inline-answer' events
+async fn run_api_submission(
+    app: AppHandle,
+    id: u64,
+    message: String,
+    new_chat: bool,
+    api: ApiSettings,
+    key: String,
+) {
+    let conversation = app.state::<ApiConversation>();
+    let _turn = conversation.take_turn().await;
+    let messages = conversation.begin(&message, new_chat);
+    set_submission_status(&app, id, SubmissionStatus::Sent, None);
+
+    let mut last_progress: Option<std::time::Instant> = None;
+    let result = mistral_api::stream_chat(&api, &key, &messages, |reply| {
+        if last_progress.is_some_and(|at| at.elapsed() < API_PROGRESS_INTERVAL) {
+            return;
+        }
+        last_progress = Some(std::time::Instant::now());
+        publish_answer(&app, &InlineAnswer::from_markdown(id, reply));
+    })
+    .await;
+
+    match result {
+        Ok(reply) => {
+            conversation.finish(&reply);
+            let response = CapturedResponse::from_markdown(Some(id), &reply, &api.model);
+            app.state::<LastResponse>().set(response.clone());
+            let _ = app.emit("last-response", &response);
+            set_submission_status(&app, id, SubmissionStatus::Answered, None);
+            publish_answer(&app, &InlineAnswer::complete(id, &response));
+            run_hooks(
+                &app,
+                HookEvent::ResponseComplete,
+                serde_json::json!({
+                    "id": id,
+                    "url": response.url,
+                    "title": response.title,
+                    "markdown": response.markdown,
+                }),
+            );
+        }
+        Err(error) => {
+            conversation.abandon();
+            eprintln!("API request for submission {} failed: {}", id, error);
+            set_submission_status(&app, id, SubmissionStatus::Failed, Some(error));
+        }
+    }
+}
+
+// Queue a launcher prompt for the main window and return it right away.
+// Messages are sent in order; one that continues the current chat waits until
+// Le Chat has finished the response in progress. Progress is broadcast as
+// 'submission-status' events keyed by submission id.
+// With `inline` (default: the `inline_answers` setting) the main window stays
+// hidden and the reply is streamed to the launcher as 'inline-answer' events.
+// In the "API" launcher backend the prompt skips the main window entirely and
+// is always answered inline. With `profile` the main window switches to that
+// profile first, so the prompt lands in its account.
+#[tauri::command]
+async fn submit_message(
+    app: AppHandle,
+    message: String,
+    new_chat: bool,
+    inline: Option<bool>,
+    profile: Option<String>,
+) -> Result<Submission, String> {
+    if let Some(profile) = profile {
+        open_profile(&app, &profile, false).await?;
+    }
+    let submission = submit(&app, SubmissionSource::Launcher, message, new_chat, inline)?;
+
+    // Hide the launcher, unless the answer is shown in it
+    if !submission.inline {
+        if let Some(launcher) = app.get_webview_window("launcher") {
+            launcher.hide().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(submission)
+}
+
+// Where a prompt comes from, which decides whether the launcher backend applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubmissionSource {
+    // The launcher and control socket clients
+    Launcher,
+    // `le-chat ask`, `le-chat mcp`, `le-chat batch` and the browser extension
+    Pipeline,
+}
+
+// Only launcher prompts follow the launcher backend. Pipeline prompts are always
+// typed into the signed-in Le Chat session, so their answers, and batch reports,
+// come from the user's own account and model.
+fn uses_api(settings: &AppSettings, source: SubmissionSource) -> bool {
+    source == SubmissionSource::Launcher && settings.launcher_backend == LauncherBackend::Api
+}
+
+// Route a prompt to the backend for its source, see `submit_message`
+fn submit(
+    app: &AppHandle,
+    source: SubmissionSource,
+    message: String,
+    new_chat: bool,
+    inline: Option<bool>,
+) -> Result<Submission, String> {
+    let settings = settings::load(app);
+    if !uses_api(&settings, source) {
+        let inline = inline.unwrap_or(settings.inline_answers);
+        return Ok(submit_to_webview(app, message, new_chat, inline));
+    }
+    let key = mistral_api::api_key()?.ok_or("No API key set — add one in Settings")?;
+    let submission = app
+        .state::<SubmissionTracker>()
+        .create(&message, new_chat, true);
+    let _ = app.emit("submission-status", &submission);
+    fire_submission_hooks(&settings, &submission, &message);
+    tauri::async_runtime::spawn(run_api_submission(
+        app.clone(),
+        submission.id,
+        message,
+        new_chat,
+        settings.api,
+        key,
+    ));
+    Ok(submission)
+}
+
+// Queue a prompt for the main window, whatever the launcher backend
+fn submit_to_webview(app: &AppHandle, message: String, new_chat: bool, inline: bool) -> Submission {
+    let hook_message = message.clone();
+    let submission = enqueue_submission(app, message, new_chat, inline);
+    fire_submission_hooks(&settings::load(app), &submission, &hook_message);
+    submission
+}
+
+fn fire_submission_hooks(settings: &AppSettings, submission: &Submission, message: &str) {
+    hooks::fire(
+        &settings.hooks,
+        HookEvent::Submission,
+        serde_json::json!({
+            "message": message,
+            "new_chat": submission.new_chat,
+            "id": submission.id,
+            "inline": submission.inline,
+        }),
+    );
+}
+
+// Tell the user's hooks about an app event
+fn run_hooks(app: &AppHandle, event: HookEvent, data: serde_json::Value) {
+    hooks::fire(&settings::load(app).hooks, event, data);
+}
+
+// Cancel a submission that hasn't been sent yet
+fn cancel_queued_submission(app: &AppHandle, id: u64) -> bool {
+    if !app.state::<SubmissionQueue>().cancel(id) {
+        return false;
+    }
+    set_submission_status(app, id, SubmissionStatus::Cancelled, None);
+    emit_submission_queue(app);
+    true
+}
+
+#[tauri::command]
+async fn cancel_submission(app: AppHandle, id: u64) -> Result<(), String> {
+    if cancel_queued_submission(&app, id) {
+        Ok(())
+    } else {
+        Err("This message is no longer queued".to_string())
+    }
+}
+
+#[tauri::command]
+async fn clear_submission_queue(app: AppHandle) -> Result<(), String> {
+    for id in app.state::<SubmissionQueue>().pending_ids() {
+        cancel_queued_submission(&app, id);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_submission_queue(app: AppHandle) -> Result<Vec<Submission>, String> {
+    Ok(queued_submissions(&app))
+}
+
+#[tauri::command]
+async fn get_submissions(
+    tracker: tauri::State<'_, SubmissionTracker>,
+) -> Result<Vec<Submission>, String> {
+    Ok(tracker.recent())
+}
+
+#[tauri::command]
+async fn has_api_key() -> Result<bool, String> {
+    Ok(mistral_api::api_key()?.is_some())
+}
+
+#[tauri::command]
+async fn save_api_key(key: String) -> Result<(), String> {
+    mistral_api::set_api_key(&key)
+}
+
+#[tauri::command]
+async fn delete_api_key() -> Result<(), String> {
+    mistral_api::delete_api_key()
+}
+
+// Models offered at `api.base_url`, which may not be saved yet
+#[tauri::command]
+async fn list_api_models(api: ApiSettings) -> Result<Vec<String>, String> {
+    let key = mistral_api::api_key()?.ok_or("No API key set")?;
+    mistral_api::list_models(&api, &key).await
+}
+
+#[tauri::command]
+async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
+    Ok(settings::load(&app))
+}
+
+// Where the control socket listens, shown in settings
+#[tauri::command]
+async fn get_control_socket_path(app: AppHandle) -> Result<String, String> {
+    control_socket_path(&app).map(|path| path.display().to_string())
+}
+
+#[tauri::command]
+async fn save_settings(
+    app: AppHandle,
+    settings: AppSettings,
+) -> Result<Vec<ShortcutFailure>, String> {
+    hooks::validate(&settings.hooks)?;
+    schedule::validate(&settings.scheduled_prompts)?;
+    navigation::validate(&settings.navigation)?;
+    profiles::validate(&settings.profiles, &settings::load(&app).active_profile)?;
+    settings::save(&app, &settings)?;
+    apply_control_socket(&app, settings.control_socket);
+    if let Err(e) = update_tray_menu(&app) {
+        eprintln!("Failed to update tray menu: {}", e);
+    }
+
+    // Re-register global shortcuts so new bindings take effect immediately
+    let failures = shortcuts::register_shortcuts(&app, &settings.shortcuts, handle_shortcut_action);
+    let _ = app.emit("shortcut-status", &failures);
+
+    // Emit settings-changed event so other windows can react
+    let _ = app.emit("settings-changed", &settings);
+    Ok(failures)
+}
+
+// Open the main window in another profile, from the launcher
+#[tauri::command]
+async fn switch_profile(app: AppHandle, profile: String) -> Result<(), String> {
+    open_profile(&app, &profile, false).await
+}
+
+#[tauri::command]
+async fn get_shortcut_failures(
+    status: tauri::State<'_, ShortcutStatus>,
+) -> Result<Vec<ShortcutFailure>, String> {
+    Ok(status.0.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn get_page_scripts(app: AppHandle) -> Result<Vec<scripts::PageScriptInfo>, String> {
+    Ok(scripts::available_scripts(
+        &settings::load(&app).page_scripts,
+    ))
+}
+
+#[tauri::command]
+async fn show_settings(app: AppHandle) -> Result<(), String> {
+    if let Some(settings) = app.get_webview_window("settings") {
+        settings.show().map_err(|e| e.to_string())?;
+        settings.set_focus().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// The main window's entry in tauri.conf.json, which isn't created at startup
+fn main_window_config(app: &AppHandle) -> tauri::Result<tauri::utils::config::WindowConfig> {
+    app.config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == "main")
+        .cloned()
+        .ok_or(tauri::Error::WindowNotFound)
+}
+
+// The main window is built here rather than in tauri.conf.json so it can
+// carry initialization scripts that run in every document it loads, and keep
+// its webview data with a profile's.
+fn create_main_window(app: &AppHandle, profile: &str) -> tauri::Result<WebviewWindow> {
+    let mut config = main_window_config(app)?;
+    let settings = settings::load(app);
+    if profile != DEFAULT_PROFILE {
+        config.title = format!(
+            "{} — {}",
+            config.title,
+            profiles::display_name(&settings.profiles, profile)
+        );
+    }
+    build_chat_window(app, config, Some(profile))
+}
+
+// Label of the private window
+const PRIVATE_WINDOW: &str = "private";
+
+// Open the private window, or bring it forward if it is already open
+fn open_private_window(app: &AppHandle) -> tauri::Result<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(PRIVATE_WINDOW) {
+        window.show()?;
+        window.set_focus()?;
+        return Ok(window);
+    }
+    let mut config = main_window_config(app)?;
+    config.label = PRIVATE_WINDOW.to_string();
+    config.title = format!("{} — Private", config.title);
+    config.visible = true;
+    config.focus = true;
+    build_chat_window(app, config, None)
+}
+
+// Build a Le Chat window, the main one or the private one: link handling,
+// the macOS title bar and what closing it does. The main window runs on the
+// given profile's webview data and reports back to the app through its
+// initialization scripts. Without a profile the window is private: its data
+// store is in memory only, it is marked as private, and it has no access to
+// the app, so nothing it shows ends up in notifications, hooks or history.
+fn build_chat_window(
+    app: &AppHandle,
+    mut config: tauri::utils::config::WindowConfig,
+    profile: Option<&str>,
+) -> tauri::Result<WebviewWindow> {
+    config.incognito = profile.is_none();
+    let label = config.label.clone();
+    let navigation_app = app.clone();
+    let new_window_app = app.clone();
+    let opener = label.clone();
+    let builder = WebviewWindowBuilder::from_config(app, &config)?
+        .on_navigation(move |url| allow_navigation(&navigation_app, url))
+        .on_new_window(move |url, features| {
+            open_new_window(&new_window_app, &opener, url, features)
+        });
+    let window = match profile {
+        Some(profile) => with_profile_data(
+            builder
+                .initialization_script(page_load::get_dom_ready_js())
+                .initialization_script(get_heartbeat_js()),
+            app,
+            profile,
+        )?
+        .build()?,
+        None => builder
+            .initialization_script(get_private_window_js())
+            .build()?,
+    };
+
+    // macOS: Use overlay title bar style with hidden title. The CSS that hides
+    // UI elements overlapping with traffic lights is a registered page script.
+    #[cfg(target_os = "macos")]
+    let _ = window.set_title_bar_style(TitleBarStyle::Overlay);
+
+    // Hide instead of quit on close. The private window really closes, which
+    // throws its session away.
+    if profile.is_some() {
+        let app_handle = app.clone();
+        window.on_window_event(move |event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Prevent close, hide instead
+                api.prevent_close();
+                if let Some(window) = app_handle.get_webview_window(&label) {
+                    let _ = window.hide();
+                }
+            }
+        });
+    }
+    Ok(window)
+}
+
+// Keep a window's cookies, storage and caches with a profile's
+fn with_profile_data<'a>(
+    builder: WebviewWindowBuilder<'a, tauri::Wry, AppHandle>,
+    app: &AppHandle,
+    profile: &str,
+) -> tauri::Result<WebviewWindowBuilder<'a, tauri::Wry, AppHandle>> {
+    Ok(
+        match profiles::data_directory(&app.path().app_local_data_dir()?, profile) {
+            Some(dir) => builder
+                .data_directory(dir)
+                .data_store_identifier(profiles::data_store_identifier(profile)),
+            None => builder,
+        },
+    )
+}
+
+// How long the old main window gets to go away when switching profiles
+const PROFILE_SWITCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Switching profiles rebuilds the main window, which would drop prompts on
+// their way into it and answers still being read from it
+fn check_profile_switch(app: &AppHandle) -> Result<(), String> {
+    let unsent = app.state::<SubmissionTracker>().unsent();
+    if unsent > 0 {
+        return Err(format!(
+            "{} prompt{} still to be sent; wait for {} or cancel {} before switching profiles",
+            unsent,
+            if unsent == 1 { " is" } else { "s are" },
+            if unsent == 1 { "it" } else { "them" },
+            if unsent == 1 { "it" } else { "them" },
+        ));
+    }
+    if app.state::<SubmissionQueue>().is_busy() {
+        return Err("Le Chat is still answering; switch profiles once it has finished".to_string());
+    }
+    Ok(())
+}
+
+// Destroy the main window and wait until it is gone
+async fn close_main_window(app: &AppHandle) -> Result<(), String> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    window.destroy().map_err(|e| e.to_string())?;
+    let deadline = tokio::time::Instant::now() + PROFILE_SWITCH_TIMEOUT;
+    while app.get_webview_window("main").is_some() {
+        if tokio::time::Instant::now() >= deadline {
+            return Err("The main window didn't close in time".to_string());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    Ok(())
+}
+
+// Rebuild the main window on `profile` after a failed switch
+fn restore_main_window(app: &AppHandle, profile: &str, visible: bool) {
+    match create_main_window(app, profile) {
+        Ok(window) if visible => {
+            let _ = window.show();
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to reopen the main window: {}", e),
+    }
+}
+
+// Reopen the main window in another profile. One profile is open at a time:
+// the main window is rebuilt on the profile's own webview data. It is shown
+// if `show` is set or it was visible before. Refused while prompts are on
+// their way or being answered; the profile is only saved as open once its
+// window has been built.
+async fn open_profile(app: &AppHandle, id: &str, show: bool) -> Result<(), String> {
+    let settings = settings::load(app);
+    let current = settings.active_profile;
+    let main_window = app.get_webview_window("main");
+    if current != id || main_window.is_none() {
+        if !profiles::exists(&settings.profiles, id) {
+            return Err(format!("No profile \"{}\"", id));
+        }
+        if main_window.is_some() {
+            check_profile_switch(app)?;
+        }
+        let was_visible = main_window
+            .as_ref()
+            .is_some_and(|window| window.is_visible().unwrap_or(false));
+        close_main_window(app).await?;
+        let window = match create_main_window(app, id) {
+            Ok(window) => window,
+            Err(e) => {
+                restore_main_window(app, &current, was_visible);
+                return Err(format!("Couldn't open profile \"{}\": {}", id, e));
+            }
+        };
+        if let Err(e) = settings::set_active_profile(app, id) {
+            // Don't leave a window open on a profile the settings don't know about
+            close_main_window(app).await?;
+            restore_main_window(app, &current, was_visible);
+            return Err(e);
+        }
+        if was_visible {
+            window.show().map_err(|e| e.to_string())?;
+        }
+        let settings = settings::load(app);
+        let _ = app.emit("profile-changed", &settings.active_profile);
+        let _ = app.emit("settings-changed", &settings);
+        if let Err(e) = update_tray_menu(app) {
+            eprintln!("Failed to update tray menu: {}", e);
+        }
+    }
+    if show {
+        if let Some(window) = app.get_webview_window("main") {
+            window.show().map_err(|e| e.to_string())?;
+            window.set_focus().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// Let the main window load Mistral and sign-in pages; hand other links to the browser
+fn allow_navigation(app: &AppHandle, url: &tauri::Url) -> bool {
+    let settings = settings::load(app).navigation;
+    match navigation::classify(url, &settings) {
+        Destination::Window | Destination::Popup => true,
+        Destination::Browser => {
+            open_in_browser(app, url);
+            false
+        }
+        Destination::Blocked => {
+            eprintln!("Blocked navigation to {}", url);
+            false
+        }
+    }
+}
+
+// `target=_blank` links and `window.open` from a chat window, the main or the
+// private one. Sign-in pages open in a popup; other allowed pages replace the
+// current one rather than opening another chat window.
+fn open_new_window(
+    app: &AppHandle,
+    opener: &str,
+    url: tauri::Url,
+    features: NewWindowFeatures,
+) -> NewWindowResponse<tauri::Wry> {
+    let settings = settings::load(app).navigation;
+    match navigation::classify_new_window(&url, &settings) {
+        Destination::Popup => match open_sign_in_popup(app, opener, features) {
+            Ok(window) => return NewWindowResponse::Create { window },
+            Err(e) => eprintln!("Failed to open sign-in window for {}: {}", url, e),
+        },
+        Destination::Window => {
+            if let Some(window) = app.get_webview_window(opener) {
+                if let Err(e) = window.navigate(url) {
+                    eprintln!("Failed to open link in the {} window: {}", opener, e);
+                }
+            }
+        }
+        Destination::Browser => open_in_browser(app, &url),
+        Destination::Blocked => eprintln!("Blocked new window for {}", url),
+    }
+    NewWindowResponse::Deny
+}
+
+// Sign-in popups are labeled "sign-in-<n>"
+static NEXT_SIGN_IN_WINDOW: AtomicU64 = AtomicU64::new(1);
+
+// A child of the window that opened it and shares its session, so the identity
+// provider's cookies land where Le Chat can use them and the page that opened
+// the popup can talk to it. The webview loads the requested page itself.
+fn open_sign_in_popup(
+    app: &AppHandle,
+    opener: &str,
+    features: NewWindowFeatures,
+) -> tauri::Result<WebviewWindow> {
+    let label = format!(
+        "sign-in-{}",
+        NEXT_SIGN_IN_WINDOW.fetch_add(1, Ordering::Relaxed)
+    );
+    let blank = "about:blank"
+        .parse::<tauri::Url>()
+        .map_err(tauri::Error::InvalidUrl)?;
+    let navigation_app = app.clone();
+    let new_window_app = app.clone();
+    let popup_label = label.clone();
+    let popup_opener = opener.to_string();
+    let new_window_opener = opener.to_string();
+    let mut builder = WebviewWindowBuilder::new(app, &label, tauri::WebviewUrl::External(blank))
+        .title("Sign in to Le Chat")
+        .inner_size(500.0, 650.0)
+        .window_features(features)
+        .on_navigation(move |url| {
+            allow_sign_in_navigation(&navigation_app, &popup_opener, &popup_label, url)
+        })
+        .on_new_window(move |url, features| {
+            open_new_window(&new_window_app, &new_window_opener, url, features)
+        })
+        .on_document_title_changed(|window, title| {
+            let _ = window.set_title(&title);
+        });
+    if let Some(opener_window) = app.get_webview_window(opener) {
+        builder = builder.parent(&opener_window)?;
+    }
+    builder.build()
+}
+
+// Sign-in popups follow the main window's policy until they redirect back to
+// Le Chat. Then the popup closes and the window that opened it loads that page
+// instead, finishing the sign-in with the session they share.
+fn allow_sign_in_navigation(app: &AppHandle, opener: &str, label: &str, url: &tauri::Url) -> bool {
+    if !navigation::returns_to_chat(url) {
+        return allow_navigation(app, url);
+    }
+    let app = app.clone();
+    let opener = opener.to_string();
+    let label = label.to_string();
+    let url = url.clone();
+    // Not from within the popup's own navigation handler
+    tauri::async_runtime::spawn(async move {
+        close_window(&app, &label);
+        if let Some(window) = app.get_webview_window(&opener) {
+            if let Err(e) = window.navigate(url) {
+                eprintln!("Failed to return to Le Chat after signing in: {}", e);
+            }
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    });
+    false
+}
+
+fn open_in_browser(app: &AppHandle, url: &tauri::Url) {
+    use tauri_plugin_opener::OpenerExt;
+    if let Err(e) = app.opener().open_url(url.as_str(), None::<&str>) {
+        eprintln!("Failed to open {} in the browser: {}", url, e);
+    }
+}
+
+// Tray menu, with submenus to cancel queued submissions and to resend or
+// discard prompts saved in the outbox
+fn build_tray_menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let queued = queued_submissions(app);
+    let outbox_items = outbox::load(app);
+
+    let show_item = MenuItem::with_id(app, "show", "Show Le Chat", true, None::<&str>)?;
+    let launcher_item = MenuItem::with_id(app, "launcher", "Quick Ask...", true, None::<&str>)?;
+    let private_item = MenuItem::with_id(app, "private", "Private Window", true, None::<&str>)?;
+    let queue_menu = Submenu::with_id(
+        app,
+        "queue",
+        format!("Queued Messages ({})", queued.len()),
+        !queued.is_empty(),
+    )?;
+    for submission in &queued {
+        let item = MenuItem::with_id(
+            app,
+            format!("cancel-submission:{}", submission.id),
+            format!("Cancel \"{}\"", submission.preview),
+            true,
+            None::<&str>,
+        )?;
+        queue_menu.append(&item)?;
+    }
+    if !queued.is_empty() {
+        queue_menu.append(&PredefinedMenuItem::separator(app)?)?;
+        queue_menu.append(&MenuItem::with_id(
+            app,
+            "cancel-all-submissions",
+            "Cancel All",
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    let outbox_menu = Submenu::with_id(
+        app,
+        "outbox",
+        format!("Outbox ({})", outbox_items.len()),
+        !outbox_items.is_empty(),
+    )?;
+    for item in &outbox_items {
+        let item_menu = Submenu::with_items(
+            app,
+            submissions::preview(&item.message),
+            true,
+            &[
+                &MenuItem::with_id(
+                    app,
+                    format!("outbox-resend:{}", item.id),
+                    "Resend",
+                    true,
+                    None::<&str>,
+                )?,
+                &MenuItem::with_id(
+                    app,
+                    format!("outbox-discard:{}", item.id),
+                    "Discard",
+                    true,
+                    None::<&str>,
+                )?,
+            ],
+        )?;
+        outbox_menu.append(&item_menu)?;
+    }
+    if !outbox_items.is_empty() {
+        outbox_menu.append(&PredefinedMenuItem::separator(app)?)?;
+        outbox_menu.append(&MenuItem::with_id(
+            app,
+            "outbox-resend-all",
+            "Resend All",
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    let settings = settings::load(app);
+    let profiles_menu =
+        Submenu::with_id(app, "profiles", "Profile", !settings.profiles.is_empty())?;
+    let default_profile = Profile {
+        id: DEFAULT_PROFILE.to_string(),
+        name: profiles::display_name(&[], DEFAULT_PROFILE).to_string(),
+        settings: Default::default(),
+    };
+    for profile in std::iter::once(&default_profile).chain(&settings.profiles) {
+        profiles_menu.append(&CheckMenuItem::with_id(
+            app,
+            format!("profile:{}", profile.id),
+            &profile.name,
+            true,
+            profile.id == settings.active_profile,
+            None::<&str>,
+        )?)?;
+    }
+    let separator1 = PredefinedMenuItem::separator(app)?;
+    let settings_item = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
+    let separator2 = PredefinedMenuItem::separator(app)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &show_item,
+            &launcher_item,
+            &private_item,
+            &queue_menu,
+            &outbox_menu,
+            &profiles_menu,
+            &separator1,
+            &settings_item,
+            &separator2,
+            &quit_item,
+        ],
+    )
+}
+
+// Reflect the main window state in the tray: dimmed icon while Le Chat is
+// unavailable, and the state in the tooltip
+fn update_tray_status(app: &AppHandle, state: MainState) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let _ = tray.set_tooltip(Some(format!("Le Chat — {}", state.label())));
+    if let Some(icon) = app.default_window_icon() {
+        let icon = if state.is_unavailable() {
+            Image::new_owned(dimmed_icon(icon.rgba()), icon.width(), icon.height())
+        } else {
+            icon.clone().to_owned()
+        };
+        if let Err(e) = tray.set_icon(Some(icon)) {
+            eprintln!("Failed to update tray icon: {}", e);
+        }
+    }
+}
+
+fn update_tray_menu(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(build_tray_menu(app)?))?;
+    }
+    Ok(())
+}
+
+fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let menu = build_tray_menu(app)?;
+
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(app.default_window_icon().unwrap().clone())
+        .tooltip(format!(
+            "Le Chat — {}",
+            app.state::<MainStateTracker>().current().state.label()
+        ))
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "show" => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            "launcher" => {
+                if let Some(launcher) = app.get_webview_window("launcher") {
+                    let _ = launcher.center();
+                    let _ = launcher.show();
+                    let _ = launcher.set_focus();
+                }
+            }
+            "private" => {
+                if let Err(e) = open_private_window(app) {
+                    eprintln!("Failed to open private window: {}", e);
+                }
+            }
+            "settings" => {
+                if let Some(settings) = app.get_webview_window("settings") {
+                    let _ = settings.show();
+                    let _ = settings.set_focus();
+                }
+            }
+            "quit" => {
+                app.exit(0);
+            }
+            "cancel-all-submissions" => {
+                for id in app.state::<SubmissionQueue>().pending_ids() {
+                    cancel_queued_submission(app, id);
+                }
+            }
+            "outbox-resend-all" => match outbox::take_all(app) {
+                Ok(items) => resend_outbox_items(app, items),
+                Err(e) => eprintln!("Failed to read outbox: {}", e),
+            },
+            id => {
+                let parse_id = |prefix: &str| id.strip_prefix(prefix)?.parse::<u64>().ok();
+                if let Some(submission_id) = parse_id("cancel-submission:") {
+                    cancel_queued_submission(app, submission_id);
+                } else if let Some(item_id) = parse_id("outbox-resend:") {
+                    if let Err(e) = resend_outbox_item_by_id(app, item_id) {
+                        eprintln!("Failed to resend outbox item: {}", e);
+                    }
+                } else if let Some(item_id) = parse_id("outbox-discard:") {
+                    if let Err(e) = discard_outbox_item_by_id(app, item_id) {
+                        eprintln!("Failed to discard outbox item: {}", e);
+                    }
+                } else if let Some(profile) = id.strip_prefix("profile:") {
+                    let app = app.clone();
+                    let profile = profile.to_string();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = open_profile(&app, &profile, true).await {
+                            eprintln!("Failed to switch profile: {}", e);
+                            // The tray has nowhere else to say why
+                            use tauri_plugin_notification::NotificationExt;
+                            let _ = app
+                                .notification()
+                                .builder()
+                                .title("Couldn't switch profiles")
+                                .body(e)
+                                .show();
+                        }
+                        // Put the check mark back on the open profile
+                        if let Err(e) = update_tray_menu(&app) {
+                            eprintln!("Failed to update tray menu: {}", e);
+                        }
+                    });
+                }
+            }
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                let app = tray.app_handle();
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
+
+// Dispatch a global shortcut press to its action
+fn handle_shortcut_action(app: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::ToggleLauncher => {
+            if let Some(launcher) = app.get_webview_window("launcher") {
+                let is_visible = launcher.is_visible().unwrap_or(false);
+                if is_visible {
+                    let _ = launcher.hide();
+                } else {
+                    let _ = launcher.center();
+                    let _ = launcher.show();
+                    let _ = launcher.set_focus();
+                }
+            }
+        }
+        ShortcutAction::ShowMainWindow => {
+            if let Some(main_window) = app.get_webview_window("main") {
+                let _ = main_window.show();
+                let _ = main_window.set_focus();
+            }
+        }
+        ShortcutAction::NewChat => {
+            if let Some(main_window) = app.get_webview_window("main") {
+                let _ = main_window.show();
+                let _ = main_window.set_focus();
+                if let Ok(url) = CHAT_URL.parse::<tauri::Url>() {
+                    let _ = main_window.navigate(url);
+                }
+            }
+        }
+        ShortcutAction::StopGenerating => {
+            if let Some(main_window) = app.get_webview_window("main") {
+                let _ = main_window.eval(get_stop_generating_js());
+            }
+        }
+    }
+}
+
+// Answers to a submission for `le-chat ask`, including one that already
+// finished before anyone was listening
+fn subscribe_to_answer(app: &AppHandle, id: u64) -> std::sync::mpsc::Receiver<AskMessage> {
+    let subscribers = app.state::<AskSubscribers>();
+    let answers = subscribers.subscribe(id);
+    let Some(submission) = app.state::<SubmissionTracker>().get(id) else {
+        return answers;
+    };
+    match submission.status {
+        SubmissionStatus::Answered => {
+            let message = match app.state::<LastResponse>().get() {
+                Some(response) if response.submission_id == Some(id) => AskMessage::Done {
+                    markdown: response.markdown,
+                },
+                _ => AskMessage::Error {
+                    message: "The answer is no longer available".to_string(),
+                },
+            };
+            subscribers.notify(id, message);
+        }
+        SubmissionStatus::Failed | SubmissionStatus::Cancelled | SubmissionStatus::Outboxed => {
+            subscribers.notify(
+                id,
+                AskMessage::Error {
+                    message: submission
+                        .error
+                        .unwrap_or_else(|| "The prompt couldn't be sent".to_string()),
+                },
+            );
+        }
+        _ => {}
+    }
+    answers
+}
+
+// Read the conversation open in the main window
+fn read_conversation(app: &AppHandle) -> Result<Snapshot, String> {
+    if is_offline(app) {
+        return Err("Le Chat is offline".to_string());
+    }
+    let main_window = app
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    let requests = app.state::<SnapshotRequests>();
+    let (id, snapshot) = requests.expect();
+    if let Err(e) = main_window.eval(get_conversation_snapshot_js(id)) {
+        requests.cancel(id);
+        return Err(e.to_string());
+    }
+    let Ok(snapshot) = snapshot.recv_timeout(CONVERSATION_SNAPSHOT_TIMEOUT) else {
+        requests.cancel(id);
+        return Err("The conversation couldn't be read from the page".to_string());
+    };
+    if snapshot.messages.is_empty() {
+        return Err("No conversation is open".to_string());
+    }
+    Ok(Snapshot {
+        markdown: snapshot.to_markdown(),
+        url: snapshot.url,
+        title: snapshot.title,
+    })
+}
+
+// Answers `le-chat ask`, `le-chat mcp` and native-messaging sessions from the app
+struct AskBridge(AppHandle);
+
+impl pipeline::AskHandler for AskBridge {
+    fn submit(
+        &self,
+        prompt: String,
+        new_chat: bool,
+        show: bool,
+    ) -> Result<(u64, std::sync::mpsc::Receiver<AskMessage>), String> {
+        // Always typed into the signed-in Le Chat session, even when launcher
+        // prompts go to the API. Otherwise answered in the background, like an
+        // inline launcher prompt.
+        let submission = submit(
+            &self.0,
+            SubmissionSource::Pipeline,
+            prompt,
+            new_chat,
+            Some(!show),
+        )?;
+        if show {
+            if let Some(window) = self.0.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        Ok((submission.id, subscribe_to_answer(&self.0, submission.id)))
+    }
+
+    fn last_response(&self) -> Result<Snapshot, String> {
+        let response = self
+            .0
+            .state::<LastResponse>()
+            .get()
+            .ok_or("Le Chat hasn't answered anything yet")?;
+        Ok(Snapshot {
+            markdown: response.markdown,
+            url: response.url,
+            title: response.title,
+        })
+    }
+
+    fn conversation(&self) -> Result<Snapshot, String> {
+        read_conversation(&self.0)
+    }
+
+    fn prefill(&self, prompt: String, new_chat: Option<bool>) -> Result<(), String> {
+        // Like a lechat://ask link: nothing is sent until the user confirms
+        prefill_launcher(
+            &self.0,
+            LinkPrompt {
+                prompt,
+                new_chat,
+                source: PromptSource::Browser,
+            },
+        );
+        Ok(())
+    }
+}
+
+// Runs control socket methods through the same code as the Tauri commands
+struct AppControl(AppHandle);
+
+impl control::ControlHandler for AppControl {
+    async fn call(&self, method: ControlMethod) -> Result<serde_json::Value, String> {
+        let app = self.0.clone();
+        let result = match method {
+            ControlMethod::ShowLauncher => show_launcher(app).await,
+            ControlMethod::ToggleLauncher => toggle_launcher(app).await,
+            ControlMethod::NavigateToChat => navigate_to_chat(app).await,
+            ControlMethod::SubmitMessage {
+                message,
+                new_chat,
+                inline,
+            } => {
+                let submission = submit_message(app, message, new_chat, inline, None).await?;
+                return serde_json::to_value(submission).map_err(|e| e.to_string());
+            }
+            ControlMethod::GetSettings => {
+                let settings = get_settings(app).await?;
+                return serde_json::to_value(settings).map_err(|e| e.to_string());
+            }
+        };
+        result.map(|()| serde_json::Value::Null)
+    }
+}
+
+// Events for control socket subscribers, and the running socket's shutdown switch
+struct ControlSocket {
+    events: tokio::sync::broadcast::Sender<ControlEvent>,
+    shutdown: Mutex<Option<tokio::sync::watch::Sender<bool>>>,
+}
+
+impl Default for ControlSocket {
+    fn default() -> Self {
+        Self {
+            events: tokio::sync::broadcast::channel(control::EVENT_BUFFER).0,
+            shutdown: Mutex::new(None),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn control_socket_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(data_dir.join("control").join("le-chat.sock"))
+}
+
+// Named pipes live in their own namespace, so the user name keeps them per user
+#[cfg(windows)]
+fn control_socket_path(_app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    Ok(format!(r"\\.\pipe\le-chat-control-{}", user).into())
+}
+
+// Start or stop the control socket to match the setting
+fn apply_control_socket(app: &AppHandle, enabled: bool) {
+    let state = app.state::<ControlSocket>();
+    let mut running = state.shutdown.lock().unwrap();
+    if !enabled {
+        if let Some(shutdown) = running.take() {
+            let _ = shutdown.send(true);
+        }
+        return;
+    }
+    if running.is_some() {
+        return;
+    }
+    let path = match control_socket_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Failed to locate control socket: {}", e);
+            return;
+        }
+    };
+    let (shutdown, shutdown_rx) = tokio::sync::watch::channel(false);
+    let handler = Arc::new(AppControl(app.clone()));
+    let events = state.events.clone();
+    tauri::async_runtime::spawn(async move {
+        #[cfg(unix)]
+        match control::bind(&path) {
+            Ok(listener) => control::serve(listener, handler, events, shutdown_rx).await,
+            Err(e) => eprintln!("Failed to start control socket: {}", e),
+        }
+        #[cfg(windows)]
+        if let Err(e) =
+            control::serve_pipe(path.display().to_string(), handler, events, shutdown_rx).await
+        {
+            eprintln!("Failed to start control pipe: {}", e);
+        }
+    });
+    *running = Some(shutdown);
+}
+
+// Show a prompt in the launcher, where it waits for the user to send it
+fn prefill_launcher(app: &AppHandle, prompt: LinkPrompt) {
+    app.state::<PendingLinkPrompt>().set(prompt);
+    if let Some(launcher) = app.get_webview_window("launcher") {
+        let _ = launcher.center();
+        let _ = launcher.show();
+        let _ = launcher.set_focus();
+        let _ = launcher.emit("link-prompt", ());
+    }
+}
+
+// Prompts from links are only put in the launcher; the user sends them
+fn handle_deep_link(app: &AppHandle, link: DeepLink) {
+    match link {
+        DeepLink::Ask { prompt, new_chat } => prefill_launcher(
+            app,
+            LinkPrompt {
+                prompt,
+                new_chat,
+                source: PromptSource::Link,
+            },
+        ),
+        DeepLink::Open { conversation_id } => handle_cli_command(
+            app,
+            CliCommand::Open(deep_link::conversation_url(&conversation_id)),
+        ),
+        DeepLink::Settings => handle_cli_command(app, CliCommand::Settings),
+    }
+}
+
+// The prompt from the latest lechat://ask link, once
+#[tauri::command]
+async fn take_link_prompt(app: AppHandle) -> Result<Option<LinkPrompt>, String> {
+    Ok(app.state::<PendingLinkPrompt>().take())
+}
+
+fn handle_cli_command(app: &AppHandle, command: CliCommand) {
+    match command {
+        CliCommand::ToggleLauncher => {
+            if let Some(launcher) = app.get_webview_window("launcher") {
+                if launcher.is_visible().unwrap_or(false) {
+                    let _ = launcher.hide();
+                } else {
+                    let _ = launcher.center();
+                    let _ = launcher.show();
+                    let _ = launcher.set_focus();
+                    let _ = launcher.emit("launcher-shown", ());
+                }
+            }
+        }
+        CliCommand::NewChat => handle_shortcut_action(app, ShortcutAction::NewChat),
+        CliCommand::Show => handle_shortcut_action(app, ShortcutAction::ShowMainWindow),
+        CliCommand::Open(url) => {
+            if let Some(main_window) = app.get_webview_window("main") {
+                let _ = main_window.show();
+                let _ = main_window.set_focus();
+                if let Ok(url) = url.parse::<tauri::Url>() {
+                    let _ = main_window.navigate(url);
+                }
+            }
+        }
+        CliCommand::Settings => {
+            if let Some(settings) = app.get_webview_window("settings") {
+                let _ = settings.show();
+                let _ = settings.set_focus();
+            }
+        }
+        CliCommand::Quit => app.exit(0),
+        CliCommand::Link(link) => handle_deep_link(app, link),
+        CliCommand::AskConnect { addr, token } => {
+            let app = app.clone();
+            // Blocks until the answer is relayed, so it gets its own thread
+            std::thread::spawn(move || {
+                if let Err(e) = pipeline::serve(addr, &token, &AskBridge(app)) {
+                    eprintln!("Failed to answer le-chat ask: {}", e);
+                }
+            });
+        }
+    }
+}
+
+fn setup_global_shortcuts(app: &AppHandle) {
+    let settings = settings::load(app);
+    let failures = shortcuts::register_shortcuts(app, &settings.shortcuts, handle_shortcut_action);
+
+    // If the launcher ended up with no working shortcut, open settings so the
+    // user can pick another binding. Other failures are shown when settings opens.
+    let launcher_unreachable = failures
+        .iter()
+        .any(|f| f.action == ShortcutAction::ToggleLauncher && f.fallback.is_none());
+    if launcher_unreachable {
+        if let Some(settings_window) = app.get_webview_window("settings") {
+            let _ = settings_window.show();
+            let _ = settings_window.set_focus();
+        }
+    }
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Reject bad arguments here, before a second instance hands them to the
+    // running one and exits successfully
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let startup_command = match cli::parse(&args) {
+        Ok(Invocation::Launch) => None,
+        Ok(Invocation::Command(command)) => Some(command),
+        Ok(Invocation::Ask(options)) => std::process::exit(pipeline::run_client(options)),
+        Ok(Invocation::Batch(options)) => std::process::exit(batch::run_client(options)),
+        Ok(Invocation::Mcp) => std::process::exit(mcp::run_stdio()),
+        Ok(Invocation::NativeHost) => std::process::exit(native_host::run_stdio()),
+        Ok(Invocation::NativeHostManifest {
+            browser,
+            extension_id,
+            install,
+        }) => std::process::exit(native_host::run_manifest(browser, &extension_id, install)),
+        Ok(Invocation::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("le-chat: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::default().build())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            if let Some(main_window) = app.get_webview_window("main") {
-                let _ = main_window.show();
-                let _ = main_window.set_focus();
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // `args` are the second instance's, already validated by it
+            match cli::parse(args.get(1..).unwrap_or_default()) {
+                Ok(Invocation::Command(command)) => handle_cli_command(app, command),
+                Ok(_) => handle_shortcut_action(app, ShortcutAction::ShowMainWindow),
+                Err(e) => eprintln!("Ignoring arguments from second instance: {}", e),
             }
         }))
         .invoke_handler(tauri::generate_handler![
             hide_launcher,
             show_launcher,
             toggle_launcher,
+            set_launcher_expanded,
             show_main_window,
             submit_message,
+            get_submissions,
+            get_submission_queue,
+            cancel_submission,
+            clear_submission_queue,
+            get_outbox,
+            resend_outbox_item,
+            discard_outbox_item,
+            get_main_state,
+            get_last_response,
             navigate_to_chat,
             navigate_to_offline,
             get_settings,
+            get_control_socket_path,
+            take_link_prompt,
             save_settings,
+            has_api_key,
+            save_api_key,
+            delete_api_key,
+            list_api_models,
+            get_shortcut_failures,
+            get_page_scripts,
             show_settings,
+            switch_profile,
+            report_dom_ready,
+            report_heartbeat,
+            report_inject_result,
+            report_response_progress,
+            report_response_complete,
+            report_response_failed,
+            report_conversation_snapshot,
         ])
-        .setup(|app| {
+        .manage(ShortcutStatus::default())
+        .manage(PageLoadTracker::default())
+        .manage(SubmissionTracker::default())
+        .manage(SubmissionQueue::default())
+        .manage(MainStateTracker::default())
+        .manage(LastResponse::default())
+        .manage(ApiConversation::default())
+        .manage(AskSubscribers::default())
+        .manage(ControlSocket::default())
+        .manage(PendingLinkPrompt::default())
+        .manage(SnapshotRequests::default())
+        .manage(ScheduledRuns::default())
+        .on_page_load(|webview, payload| {
+            let overrides = settings::load(webview.app_handle()).page_scripts;
+            // The private window gets the page scripts that aren't tied to the main
+            // window, but isn't tracked
+            if webview.label() == PRIVATE_WINDOW {
+                scripts::on_page_load(webview, payload.url(), payload.event(), &overrides);
+                return;
+            }
+            if webview.label() != "main" {
+                return;
+            }
+            let tracker = webview.state::<PageLoadTracker>();
+            match payload.event() {
+                PageLoadEvent::Started => {
+                    tracker.started(payload.url().as_str());
+                    abandon_main_responses(webview.app_handle());
+                }
+                PageLoadEvent::Finished => tracker.finished(payload.url().as_str()),
+            }
+            let main_state = webview.state::<MainStateTracker>();
+            let change = match payload.event() {
+                PageLoadEvent::Started => main_state.page_started(payload.url().as_str()),
+                PageLoadEvent::Finished => main_state.page_finished(payload.url().as_str()),
+            };
+            on_main_state_change(webview.app_handle(), change);
+            scripts::on_page_load(webview, payload.url(), payload.event(), &overrides);
+        })
+        .setup(move |app| {
+            create_main_window(app.handle(), &settings::load(app.handle()).active_profile)?;
+
             // Setup system tray
             if let Err(e) = setup_tray(app.handle()) {
                 eprintln!("Failed to setup tray: {}", e);
             }
 
-            // Setup global shortcut
-            if let Err(e) = setup_global_shortcut(app.handle()) {
-                eprintln!("Failed to setup global shortcut: {}", e);
-            }
+            // Register global shortcuts from settings
+            setup_global_shortcuts(app.handle());
 
-            // Handle main window close - hide instead of quit
-            if let Some(main_window) = app.get_webview_window("main") {
+            // Pass events on to control socket subscribers
+            for &name in control::STREAMED_EVENTS {
                 let app_handle = app.handle().clone();
-                main_window.on_window_event(move |event| {
-                    if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                        // Prevent close, hide instead
-                        api.prevent_close();
-                        if let Some(window) = app_handle.get_webview_window("main") {
-                            let _ = window.hide();
-                        }
-                    }
+                app.listen(name, move |event| {
+                    let payload =
+                        serde_json::from_str(event.payload()).unwrap_or(serde_json::Value::Null);
+                    let _ = app_handle
+                        .state::<ControlSocket>()
+                        .events
+                        .send(ControlEvent {
+                            event: name.to_string(),
+                            payload,
+                        });
                 });
             }
+            apply_control_socket(app.handle(), settings::load(app.handle()).control_socket);
 
-            // Listen for response-complete events and send a notification
-            // if the main window is not focused (user switched away).
-            {
-                let app_handle = app.handle().clone();
-                app.listen("response-complete", move |_event| {
-                    // Check if notifications are enabled in settings
-                    let notifications_enabled = {
-                        use tauri_plugin_store::StoreExt;
-                        app_handle
-                            .store("settings.json")
-                            .ok()
-                            .and_then(|store| store.get("app_settings"))
-                            .and_then(|v| serde_json::from_value::<AppSettings>(v).ok())
-                            .map(|s| s.notifications_enabled)
-                            .unwrap_or(true)
-                    };
-
-                    if !notifications_enabled {
-                        return;
-                    }
-
-                    let is_focused = app_handle
-                        .get_webview_window("main")
-                        .and_then(|w| w.is_focused().ok())
-                        .unwrap_or(false);
-
-                    if !is_focused {
-                        use tauri_plugin_notification::NotificationExt;
-                        let _ = app_handle
-                            .notification()
-                            .builder()
-                            .title("Le Chat")
-                            .body("Response ready")
-                            .show();
-
-                        // Show main window (don't auto-focus — let user click notification)
-                        if let Some(window) = app_handle.get_webview_window("main") {
-                            let _ = window.show();
-                        }
-                    }
-                });
-            }
+            tauri::async_runtime::spawn(run_submission_queue(app.handle().clone()));
+            tauri::async_runtime::spawn(run_scheduler(app.handle().clone()));
 
             // Handle launcher losing focus - hide it
             if let Some(launcher) = app.get_webview_window("launcher") {
@@ -677,107 +2712,9 @@ pub fn run() {
                 }
             }
 
-            // Inject PWA-aware connectivity monitoring into the main window.
-            // Respects Le Chat's service worker for offline caching — only falls back
-            // to the local offline page when no service worker is registered (first launch).
-            if let Some(main_window) = app.get_webview_window("main") {
-                let connectivity_js = r#"
-                    (function() {
-                        const CHAT_URL = 'https://chat.mistral.ai/chat';
-
-                        // Check if Le Chat's service worker is registered
-                        async function checkServiceWorker() {
-                            if (!('serviceWorker' in navigator)) {
-                                console.log('[Le Chat] Service workers not supported in this webview');
-                                return { supported: false, registered: false };
-                            }
-                            try {
-                                const registrations = await navigator.serviceWorker.getRegistrations();
-                                const hasSW = registrations.length > 0;
-                                console.log('[Le Chat] Service worker supported: true, registered:', hasSW,
-                                    hasSW ? '(PWA active)' : '(no PWA cache yet)');
-                                for (const reg of registrations) {
-                                    console.log('[Le Chat]   SW scope:', reg.scope, 'state:',
-                                        reg.active ? 'active' : reg.installing ? 'installing' : reg.waiting ? 'waiting' : 'unknown');
-                                }
-                                return { supported: true, registered: hasSW };
-                            } catch (e) {
-                                console.log('[Le Chat] Service worker check failed:', e.message);
-                                return { supported: true, registered: false };
-                            }
-                        }
-
-                        // Monitor online/offline events
-                        window.addEventListener('offline', () => {
-                            console.log('[Le Chat] Browser went offline');
-                        });
-                        window.addEventListener('online', () => {
-                            console.log('[Le Chat] Browser came online — reloading');
-                            if (window.location.href.includes('chat.mistral.ai')) {
-                                window.location.reload();
-                            } else {
-                                window.location.href = CHAT_URL;
-                            }
-                        });
-
-                        // Check if page loaded successfully after a delay.
-                        // If the PWA service worker is registered, let it handle offline.
-                        // Only fall back to the local offline page on first launch with no cache.
-                        setTimeout(async () => {
-                            const isErrorPage = !navigator.onLine
-                                || document.title.toLowerCase().includes('error')
-                                || document.title.toLowerCase().includes('not found')
-                                || document.title === ''
-                                || (document.body && document.body.innerText.length < 50
-                                    && !document.querySelector('[data-sidebar]'));
-
-                            if (!isErrorPage || window.location.href.includes('tauri')) {
-                                // Page loaded fine or we're on a local page — just log SW status
-                                await checkServiceWorker();
-                                return;
-                            }
-
-                            // Page failed to load — check if PWA service worker can handle it
-                            const sw = await checkServiceWorker();
-                            if (sw.registered) {
-                                // Reload guard: prevent infinite reload loop via sessionStorage flag
-                                const reloadKey = '__le_chat_sw_reload';
-                                if (sessionStorage.getItem(reloadKey)) {
-                                    console.log('[Le Chat] Already tried SW reload — falling back to offline page');
-                                    sessionStorage.removeItem(reloadKey);
-                                    if (window.__TAURI__) {
-                                        window.__TAURI__.core.invoke('navigate_to_offline').catch(() => {});
-                                    }
-                                } else {
-                                    sessionStorage.setItem(reloadKey, '1');
-                                    console.log('[Le Chat] Page failed but PWA service worker is active — reloading to use cache');
-                                    window.location.reload();
-                                }
-                            } else {
-                                // No service worker (first launch or SW not installed) — local offline page
-                                console.log('[Le Chat] Page failed and no service worker — showing offline page');
-                                if (window.__TAURI__) {
-                                    window.__TAURI__.core.invoke('navigate_to_offline').catch(() => {});
-                                }
-                            }
-                        }, 5000);
-                    })();
-                "#;
-                let _ = main_window.eval(connectivity_js);
-            }
-
-            // Platform-specific window configuration and style injection
-            if let Some(main_window) = app.get_webview_window("main") {
-                #[cfg(target_os = "macos")]
-                {
-                    // macOS: Use overlay title bar style with hidden title
-                    let _ = main_window.set_title_bar_style(TitleBarStyle::Overlay);
-                    // Inject CSS to hide UI elements overlapping with traffic lights
-                    let js = get_hide_titlebar_overlap_js();
-                    let _ = main_window.eval(&js);
-                }
-
-
+            // A verb given to the first instance runs once the app is set up
+            if let Some(command) = startup_command {
+                handle_cli_command(app.handle(), command);
             }
 
             Ok(())
@@ -793,6 +2730,17 @@ pub fn run() {
                     let _ = window.set_focus();
                 }
             }
+
+            // macOS hands lechat:// links over as an event instead of arguments
+            #[cfg(target_os = "macos")]
+            if let RunEvent::Opened { urls } = &_event {
+                for url in urls {
+                    match deep_link::parse(url.as_str()) {
+                        Ok(link) => handle_deep_link(_app, link),
+                        Err(e) => eprintln!("Ignoring link: {}", e),
+                    }
+                }
+            }
         });
 }
 
@@ -800,44 +2748,9 @@ pub fn run() {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_app_settings_default() {
-        let settings = AppSettings::default();
-        assert!(settings.new_chat_default, "new_chat_default should be true");
-        assert!(
-            settings.notifications_enabled,
-            "notifications_enabled should be true"
-        );
-    }
-
-    #[test]
-    fn test_app_settings_serialization_roundtrip() {
-        let settings = AppSettings {
-            new_chat_default: false,
-            notifications_enabled: true,
-        };
-        let json = serde_json::to_value(&settings).unwrap();
-        let deserialized: AppSettings = serde_json::from_value(json).unwrap();
-        assert_eq!(deserialized.new_chat_default, false);
-        assert_eq!(deserialized.notifications_enabled, true);
-    }
-
-    #[test]
-    fn test_app_settings_deserialize_missing_fields_uses_defaults() {
-        // Simulate a stored JSON that's missing a field (e.g., after adding a new setting)
-        let json = serde_json::json!({ "new_chat_default": false });
-        let result: Result<AppSettings, _> = serde_json::from_value(json);
-        // Strict deserialization will fail — this is expected since we don't use
-        // #[serde(default)]. This test documents the current behavior.
-        assert!(
-            result.is_err(),
-            "Missing fields should fail without #[serde(default)]"
-        );
-    }
-
     #[test]
     fn test_inject_message_js_simple() {
-        let js = get_inject_message_js("Hello world");
+        let js = get_inject_message_js("Hello world", 1);
         assert!(js.contains("Hello world"));
         assert!(js.contains("emitResult"));
         assert!(js.contains("findTextarea"));
@@ -845,39 +2758,42 @@ mod tests {
 
     #[test]
     fn test_inject_message_js_escapes_backticks() {
-        let js = get_inject_message_js("code `inline` here");
+        let js = get_inject_message_js("code `inline` here", 1);
         assert!(js.contains(r"\`inline\`"));
         assert!(!js.contains("code `inline` here"));
     }
 
     #[test]
     fn test_inject_message_js_escapes_backslashes() {
-        let js = get_inject_message_js(r"path\to\file");
+        let js = get_inject_message_js(r"path\to\file", 1);
         assert!(js.contains(r"path\\to\\file"));
     }
 
     #[test]
     fn test_inject_message_js_escapes_dollar_signs() {
-        let js = get_inject_message_js("cost is $100");
+        let js = get_inject_message_js("cost is $100", 1);
         assert!(js.contains(r"cost is \$100"));
     }
 
     #[test]
     fn test_inject_message_js_escapes_newlines() {
-        let js = get_inject_message_js("line1\nline2\rline3");
+        let js = get_inject_message_js("line1\nline2\rline3", 1);
         assert!(js.contains(r"line1\nline2\rline3"));
     }
 
     #[test]
     fn test_inject_message_js_handles_empty_string() {
-        let js = get_inject_message_js("");
+        let js = get_inject_message_js("", 1);
         assert!(js.contains("const message = ``"));
     }
 
     #[test]
     fn test_inject_message_js_complex_input() {
         // A realistic complex message with multiple special chars
-        let js = get_inject_message_js("Explain `async/await` in JS.\nThe cost is $50\\per unit.");
+        let js = get_inject_message_js(
+            "Explain `async/await` in JS.\nThe cost is $50\\per unit.",
+            1,
+        );
         assert!(js.contains(r"\`async/await\`"));
         assert!(js.contains(r"\$50"));
         assert!(js.contains(r"\\per"));
@@ -886,20 +2802,83 @@ mod tests {
 
     #[test]
     fn test_response_watcher_js_is_valid() {
-        let js = get_response_watcher_js();
+        let js = get_response_watcher_js(1, false);
         assert!(!js.is_empty());
-        assert!(js.contains("__leChatResponseWatcher"));
-        assert!(js.contains("response-complete"));
+        assert!(js.contains("__leChatResponseWatchers"));
+        assert!(js.contains("report_response_complete"));
+        assert!(js.contains("report_response_failed"));
+        assert!(js.contains("const MAX_WATCH_MS = 300000;"));
         assert!(js.contains("isStreaming"));
+        assert!(js.contains("innerHTML"));
+        assert!(js.contains(LAST_ASSISTANT_MESSAGE_JS));
+        assert!(js.contains("new MutationObserver"));
+        assert!(!js.contains("setInterval"));
     }
 
     #[test]
-    fn test_hide_titlebar_overlap_js_is_valid() {
-        let js = get_hide_titlebar_overlap_js();
-        assert!(!js.is_empty());
-        assert!(js.contains("le-chat-custom-styles"));
-        assert!(js.contains("data-sidebar"));
-        assert!(js.contains("MutationObserver"));
+    fn test_pipeline_prompts_ignore_the_launcher_backend() {
+        let api = AppSettings {
+            launcher_backend: LauncherBackend::Api,
+            ..Default::default()
+        };
+        assert!(uses_api(&api, SubmissionSource::Launcher));
+        assert!(!uses_api(&api, SubmissionSource::Pipeline));
+        let webview = AppSettings::default();
+        assert!(!uses_api(&webview, SubmissionSource::Launcher));
+        assert!(!uses_api(&webview, SubmissionSource::Pipeline));
+    }
+
+    #[test]
+    fn test_private_window_js_is_valid() {
+        let js = get_private_window_js();
+        assert!(js.contains("le-chat-private-frame"));
+        assert!(js.contains("pointer-events:none"));
+        assert!(!js.contains("__TAURI__"));
+    }
+
+    #[test]
+    fn test_conversation_snapshot_js_is_valid() {
+        let js = get_conversation_snapshot_js(7);
+        assert!(js.contains("report_conversation_snapshot"));
+        assert!(js.contains("id: 7"));
+        assert!(js.contains("[data-message-author-role]"));
+        assert!(js.contains(LAST_ASSISTANT_MESSAGE_JS));
+    }
+
+    #[test]
+    fn test_response_watcher_streams_progress_only_when_asked() {
+        assert!(get_response_watcher_js(1, true).contains("const streamProgress = true;"));
+        assert!(get_response_watcher_js(1, false).contains("const streamProgress = false;"));
+        assert!(get_response_watcher_js(1, true).contains("report_response_progress"));
+    }
+
+    #[test]
+    fn test_streaming_detection_shares_stop_button_heuristic() {
+        let heartbeat = get_heartbeat_js();
+        assert!(heartbeat.contains("report_heartbeat"));
+        assert!(heartbeat.contains("signed_in"));
+        assert!(heartbeat.contains(STOP_BUTTON_JS));
+        assert!(get_response_watcher_js(1, false).contains(STOP_BUTTON_JS));
+        assert!(get_stop_generating_js().contains(STOP_BUTTON_JS));
+    }
+
+    #[test]
+    fn test_scripts_carry_submission_id() {
+        let inject = get_inject_message_js("Hello", 42);
+        assert!(inject.contains("const submissionId = 42;"));
+        assert!(inject.contains("id: submissionId"));
+
+        let watcher = get_response_watcher_js(42, true);
+        assert!(watcher.contains("const submissionId = 42;"));
+        assert!(watcher.contains("{ id: submissionId, html: lastHtml"));
+        assert!(watcher.contains("{ id: submissionId, html: message"));
+    }
+
+    #[test]
+    fn test_stop_generating_js_is_valid() {
+        let js = get_stop_generating_js();
+        assert!(js.contains("stopBtn.click()"));
+        assert!(js.contains("aria-label*=\"stop\" i"));
     }
 
     #[test]
@@ -0,0 +1,321 @@
+use std::sync::Mutex;
+
+// What the main window is doing, derived from page-load events and the
+// heartbeat reports of its initialization script
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MainState {
+    #[default]
+    Loading,
+    Ready,
+    Streaming,
+    Offline,
+    SignedOut,
+}
+
+impl MainState {
+    pub fn label(self) -> &'static str {
+        match self {
+            MainState::Loading => "Loading",
+            MainState::Ready => "Ready",
+            MainState::Streaming => "Responding",
+            MainState::Offline => "Offline",
+            MainState::SignedOut => "Signed out",
+        }
+    }
+
+    // Whether the tray icon should be shown dimmed
+    pub fn is_unavailable(self) -> bool {
+        matches!(self, MainState::Offline | MainState::SignedOut)
+    }
+}
+
+// Payload of the `main-heartbeat` event emitted by the main window
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct Heartbeat {
+    pub url: String,
+    pub online: bool,
+    pub streaming: bool,
+    // `None` when the page isn't a Le Chat page (e.g. the local offline page)
+    pub signed_in: Option<bool>,
+}
+
+// Assumed until the page reports otherwise
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            online: true,
+            streaming: false,
+            signed_in: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct MainStateInfo {
+    pub state: MainState,
+    pub url: String,
+}
+
+// A state transition, returned so callers can react to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange {
+    pub previous: MainState,
+    pub info: MainStateInfo,
+    // Became usable again after having been offline
+    pub reconnected: bool,
+}
+
+#[derive(Default)]
+struct Inner {
+    loading: bool,
+    url: String,
+    heartbeat: Heartbeat,
+    state: MainState,
+    // Went offline since the window was last usable
+    was_offline: bool,
+}
+
+// Whether `url` is a page of Le Chat itself, rather than the local offline page
+// or a browser error page
+fn is_chat_page(url: &str) -> bool {
+    tauri::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host == "chat.mistral.ai"))
+        .unwrap_or(false)
+}
+
+fn derive(loading: bool, url: &str, heartbeat: &Heartbeat) -> MainState {
+    if loading {
+        MainState::Loading
+    } else if !heartbeat.online || !is_chat_page(url) {
+        MainState::Offline
+    } else if heartbeat.signed_in == Some(false) {
+        MainState::SignedOut
+    } else if heartbeat.streaming {
+        MainState::Streaming
+    } else {
+        MainState::Ready
+    }
+}
+
+// The single authoritative state of the main window
+#[derive(Default)]
+pub struct MainStateTracker {
+    inner: Mutex<Inner>,
+}
+
+impl MainStateTracker {
+    pub fn current(&self) -> MainStateInfo {
+        let inner = self.inner.lock().unwrap();
+        MainStateInfo {
+            state: inner.state,
+            url: inner.url.clone(),
+        }
+    }
+
+    // Whether the page currently shows Le Chat's stop button
+    pub fn is_streaming(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        !inner.loading && inner.heartbeat.streaming
+    }
+
+    pub fn page_started(&self, url: &str) -> Option<StateChange> {
+        self.apply(|inner| {
+            inner.loading = true;
+            inner.url = url.to_string();
+            // The new document reports its own state once it runs
+            inner.heartbeat = Heartbeat {
+                online: inner.heartbeat.online,
+                ..Heartbeat::default()
+            };
+        })
+    }
+
+    pub fn page_finished(&self, url: &str) -> Option<StateChange> {
+        self.apply(|inner| {
+            inner.loading = false;
+            inner.url = url.to_string();
+        })
+    }
+
+    // While a page is loading, only heartbeats for the URL being loaded are kept;
+    // `page_finished` decides when loading is over.
+    pub fn heartbeat(&self, heartbeat: Heartbeat) -> Option<StateChange> {
+        self.apply(|inner| {
+            if inner.loading {
+                if heartbeat.url == inner.url {
+                    inner.heartbeat = heartbeat;
+                }
+                return;
+            }
+            // Le Chat is a single-page app, so the URL changes without page loads
+            inner.url = heartbeat.url.clone();
+            inner.heartbeat = heartbeat;
+        })
+    }
+
+    fn apply(&self, update: impl FnOnce(&mut Inner)) -> Option<StateChange> {
+        let mut inner = self.inner.lock().unwrap();
+        update(&mut inner);
+
+        let previous = inner.state;
+        let state = derive(inner.loading, &inner.url, &inner.heartbeat);
+        inner.state = state;
+        if state == previous {
+            return None;
+        }
+
+        let mut reconnected = false;
+        match state {
+            MainState::Offline => inner.was_offline = true,
+            MainState::Ready | MainState::Streaming => {
+                reconnected = inner.was_offline;
+                inner.was_offline = false;
+            }
+            MainState::Loading | MainState::SignedOut => {}
+        }
+        Some(StateChange {
+            previous,
+            info: MainStateInfo {
+                state,
+                url: inner.url.clone(),
+            },
+            reconnected,
+        })
+    }
+}
+
+// Grayscale, half-transparent copy of an RGBA icon for unavailable states
+pub fn dimmed_icon(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|pixel| {
+            let [r, g, b, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            let gray = ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8;
+            [gray, gray, gray, a / 2]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAT_URL: &str = "https://chat.mistral.ai/chat";
+    const OFFLINE_URL: &str = "tauri://localhost/index.html";
+
+    fn beat(url: &str) -> Heartbeat {
+        Heartbeat {
+            url: url.to_string(),
+            online: true,
+            streaming: false,
+            signed_in: Some(true),
+        }
+    }
+
+    #[test]
+    fn test_load_then_ready() {
+        let tracker = MainStateTracker::default();
+        assert_eq!(tracker.current().state, MainState::Loading);
+        tracker.page_started(CHAT_URL);
+        assert!(tracker.page_finished(CHAT_URL).is_some());
+        assert_eq!(tracker.current().state, MainState::Ready);
+        assert!(tracker.heartbeat(beat(CHAT_URL)).is_none(), "no change");
+    }
+
+    #[test]
+    fn test_streaming_and_signed_out() {
+        let tracker = MainStateTracker::default();
+        tracker.page_finished(CHAT_URL);
+        let change = tracker
+            .heartbeat(Heartbeat {
+                streaming: true,
+                ..beat(CHAT_URL)
+            })
+            .unwrap();
+        assert_eq!(change.info.state, MainState::Streaming);
+        assert!(tracker.is_streaming());
+
+        tracker.heartbeat(Heartbeat {
+            signed_in: Some(false),
+            ..beat(CHAT_URL)
+        });
+        assert_eq!(tracker.current().state, MainState::SignedOut);
+    }
+
+    #[test]
+    fn test_offline_page_and_reconnect() {
+        let tracker = MainStateTracker::default();
+        tracker.page_started(OFFLINE_URL);
+        tracker.page_finished(OFFLINE_URL);
+        assert_eq!(tracker.current().state, MainState::Offline);
+
+        tracker.page_started(CHAT_URL);
+        assert_eq!(tracker.current().state, MainState::Loading);
+        let change = tracker.page_finished(CHAT_URL).unwrap();
+        assert_eq!(change.info.state, MainState::Ready);
+        assert!(change.reconnected);
+
+        // Only the first usable state after going offline counts
+        tracker.heartbeat(Heartbeat {
+            streaming: true,
+            ..beat(CHAT_URL)
+        });
+        let change = tracker.heartbeat(beat(CHAT_URL)).unwrap();
+        assert!(!change.reconnected);
+    }
+
+    #[test]
+    fn test_browser_offline_event() {
+        let tracker = MainStateTracker::default();
+        tracker.page_finished(CHAT_URL);
+        let change = tracker
+            .heartbeat(Heartbeat {
+                online: false,
+                ..beat(CHAT_URL)
+            })
+            .unwrap();
+        assert_eq!(change.previous, MainState::Ready);
+        assert_eq!(change.info.state, MainState::Offline);
+        assert!(tracker.heartbeat(beat(CHAT_URL)).unwrap().reconnected);
+    }
+
+    #[test]
+    fn test_heartbeats_from_previous_page_are_ignored_while_loading() {
+        let tracker = MainStateTracker::default();
+        tracker.page_started(CHAT_URL);
+        tracker.heartbeat(Heartbeat {
+            streaming: true,
+            ..beat("https://chat.mistral.ai/chat/abc")
+        });
+        assert!(!tracker.is_streaming());
+        tracker.heartbeat(Heartbeat {
+            signed_in: Some(false),
+            ..beat(CHAT_URL)
+        });
+        assert_eq!(tracker.current().state, MainState::Loading);
+        tracker.page_finished(CHAT_URL);
+        assert_eq!(tracker.current().state, MainState::SignedOut);
+    }
+
+    #[test]
+    fn test_new_page_clears_streaming() {
+        let tracker = MainStateTracker::default();
+        tracker.page_finished(CHAT_URL);
+        tracker.heartbeat(Heartbeat {
+            streaming: true,
+            ..beat(CHAT_URL)
+        });
+        tracker.page_started(CHAT_URL);
+        assert!(!tracker.is_streaming());
+    }
+
+    #[test]
+    fn test_dimmed_icon() {
+        let dimmed = dimmed_icon(&[255, 0, 0, 255, 10, 20, 30, 0]);
+        assert_eq!(dimmed.len(), 8);
+        assert_eq!(&dimmed[..4], &[76, 76, 76, 127]);
+        assert_eq!(dimmed[7], 0);
+    }
+}
@@ -0,0 +1,840 @@
+use crate::html::Node;
+
+// Convert sanitized HTML nodes (see `html::sanitize`) to GitHub-flavored Markdown,
+// keeping headings, emphasis, links, code blocks, lists, block quotes and tables.
+pub fn from_html(nodes: &[Node]) -> String {
+    let mut blocks = Vec::new();
+    render_blocks(nodes, &mut blocks);
+    blocks.join("\n\n")
+}
+
+fn is_block(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Element { tag, .. } if matches!(
+            tag.as_str(),
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "ul" | "ol" | "pre"
+                | "blockquote" | "table" | "hr"
+        )
+    )
+}
+
+fn render_blocks(nodes: &[Node], blocks: &mut Vec<String>) {
+    let mut inline: Vec<&Node> = Vec::new();
+    for node in nodes {
+        if is_block(node) {
+            flush_inline(&mut inline, blocks);
+            render_block(node, blocks);
+        } else {
+            inline.push(node);
+        }
+    }
+    flush_inline(&mut inline, blocks);
+}
+
+fn flush_inline(inline: &mut Vec<&Node>, blocks: &mut Vec<String>) {
+    let text = render_inline_nodes(inline.iter().copied());
+    let text = text.trim();
+    if !text.is_empty() {
+        blocks.push(text.to_string());
+    }
+    inline.clear();
+}
+
+fn render_block(node: &Node, blocks: &mut Vec<String>) {
+    let Node::Element { tag, children, .. } = node else {
+        return;
+    };
+    match tag.as_str() {
+        "p" => {
+            // Paragraphs sometimes wrap block content
+            if children.iter().any(is_block) {
+                render_blocks(children, blocks);
+            } else {
+                let text = render_inline_nodes(children.iter());
+                let text = text.trim();
+                if !text.is_empty() {
+                    blocks.push(text.to_string());
+                }
+            }
+        }
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level: usize = tag[1..].parse().unwrap_or(1);
+            let text = render_inline_nodes(children.iter());
+            blocks.push(format!("{} {}", "#".repeat(level), text.trim()));
+        }
+        "hr" => blocks.push("---".to_string()),
+        "pre" => blocks.push(render_code_block(node)),
+        "ul" | "ol" => blocks.push(render_list(node)),
+        "blockquote" => {
+            let mut inner = Vec::new();
+            render_blocks(children, &mut inner);
+            let quoted = inner
+                .join("\n\n")
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_string()
+                    } else {
+                        format!("> {}", line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            blocks.push(quoted);
+        }
+        "table" => {
+            if let Some(table) = render_table(node) {
+                blocks.push(table);
+            }
+        }
+        _ => render_blocks(children, blocks),
+    }
+}
+
+fn render_code_block(pre: &Node) -> String {
+    let Node::Element { children, .. } = pre else {
+        return String::new();
+    };
+    // `<pre><code class="language-x">` is the usual shape; fall back to the <pre> itself
+    let code = children
+        .iter()
+        .find(|child| matches!(child, Node::Element { tag, .. } if tag == "code"))
+        .unwrap_or(pre);
+    let language = code
+        .attr("class")
+        .and_then(|class| {
+            class
+                .split_whitespace()
+                .find_map(|c| c.strip_prefix("language-"))
+        })
+        .unwrap_or("");
+    let text = code.text();
+    let text = text.trim_end_matches('\n');
+
+    // The fence must be longer than any backtick run inside the code
+    let longest_run = longest_backtick_run(text);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language, text, fence)
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn render_list(list: &Node) -> String {
+    let Node::Element { tag, children, .. } = list else {
+        return String::new();
+    };
+    let ordered = tag == "ol";
+    let start: usize = list.attr("start").and_then(|s| s.parse().ok()).unwrap_or(1);
+
+    let mut lines = Vec::new();
+    let items = children
+        .iter()
+        .filter(|child| matches!(child, Node::Element { tag, .. } if tag == "li"));
+    for (index, item) in items.enumerate() {
+        let marker = if ordered {
+            format!("{}. ", start + index)
+        } else {
+            "- ".to_string()
+        };
+        let Node::Element { children, .. } = item else {
+            continue;
+        };
+        let mut blocks = Vec::new();
+        render_blocks(children, &mut blocks);
+        let content = blocks.join("\n");
+        let indent = " ".repeat(marker.len());
+        for (i, line) in content.lines().enumerate() {
+            if i == 0 {
+                lines.push(format!("{}{}", marker, line));
+            } else if line.is_empty() {
+                lines.push(String::new());
+            } else {
+                lines.push(format!("{}{}", indent, line));
+            }
+        }
+        if content.is_empty() {
+            lines.push(marker.trim_end().to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+fn collect_rows<'a>(node: &'a Node, rows: &mut Vec<&'a Node>) {
+    if let Node::Element { tag, children, .. } = node {
+        if tag == "tr" {
+            rows.push(node);
+            return;
+        }
+        for child in children {
+            collect_rows(child, rows);
+        }
+    }
+}
+
+fn render_table(table: &Node) -> Option<String> {
+    let mut rows = Vec::new();
+    collect_rows(table, &mut rows);
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| match row {
+            Node::Element { children, .. } => children
+                .iter()
+                .filter(
+                    |cell| matches!(cell, Node::Element { tag, .. } if tag == "th" || tag == "td"),
+                )
+                .map(|cell| {
+                    let Node::Element { children, .. } = cell else {
+                        return String::new();
+                    };
+                    render_inline_nodes(children.iter())
+                        .trim()
+                        .replace('|', "\\|")
+                        .replace('\n', " ")
+                })
+                .collect(),
+            Node::Text(_) => Vec::new(),
+        })
+        .collect();
+    let columns = cells.iter().map(Vec::len).max().filter(|&n| n > 0)?;
+
+    let format_row = |row: &Vec<String>| {
+        let mut padded = row.clone();
+        padded.resize(columns, String::new());
+        format!("| {} |", padded.join(" | "))
+    };
+    let mut lines = vec![format_row(&cells[0])];
+    lines.push(format!("|{}", " --- |".repeat(columns)));
+    lines.extend(cells[1..].iter().map(format_row));
+    Some(lines.join("\n"))
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '*' | '_' | '`' | '[' | ']') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+// Render inline content, collapsing whitespace the way a browser would
+fn render_inline_nodes<'a>(nodes: impl Iterator<Item = &'a Node>) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        render_inline(node, &mut out);
+    }
+    out
+}
+
+fn push_collapsed(text: &str, out: &mut String) {
+    for (i, word) in text.split(|c: char| c.is_ascii_whitespace()).enumerate() {
+        if i > 0 && !out.ends_with([' ', '\n']) && !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(&escape_text(word));
+    }
+}
+
+fn wrap(children: &[Node], marker: &str, out: &mut String) {
+    let inner = render_inline_nodes(children.iter());
+    let trimmed = inner.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+    if inner.starts_with(' ') && !out.ends_with(' ') {
+        out.push(' ');
+    }
+    out.push_str(&format!("{}{}{}", marker, trimmed, marker));
+    if inner.ends_with(' ') {
+        out.push(' ');
+    }
+}
+
+fn render_inline(node: &Node, out: &mut String) {
+    let (tag, children) = match node {
+        Node::Text(text) => {
+            push_collapsed(text, out);
+            return;
+        }
+        Node::Element { tag, children, .. } => (tag.as_str(), children),
+    };
+    match tag {
+        "strong" | "b" => wrap(children, "**", out),
+        "em" | "i" => wrap(children, "*", out),
+        "del" | "s" => wrap(children, "~~", out),
+        "code" => {
+            let text = node.text();
+            let fence = "`".repeat(longest_backtick_run(&text) + 1);
+            let pad = if text.starts_with('`') || text.ends_with('`') {
+                " "
+            } else {
+                ""
+            };
+            out.push_str(&format!("{}{}{}{}{}", fence, pad, text, pad, fence));
+        }
+        "a" => {
+            let text = render_inline_nodes(children.iter());
+            match node.attr("href") {
+                Some(href) => out.push_str(&format!("[{}]({})", text.trim(), href)),
+                None => out.push_str(&text),
+            }
+        }
+        "img" => {
+            if let Some(src) = node.attr("src") {
+                out.push_str(&format!(
+                    "![{}]({})",
+                    escape_text(node.attr("alt").unwrap_or("")),
+                    src
+                ));
+            }
+        }
+        "br" => out.push_str("  \n"),
+        _ if is_block(node) => {
+            // Block content inside inline context (e.g. a list inside a link): keep the text
+            let mut blocks = Vec::new();
+            render_block(node, &mut blocks);
+            out.push_str(&blocks.join("\n"));
+        }
+        _ => {
+            for child in children {
+                render_inline(child, out);
+            }
+        }
+    }
+}
+
+// Render GitHub-flavored Markdown, as returned by the chat completions API, to HTML.
+// Covers the same subset `from_html` produces; the result should still go through
+// `html::sanitize` since links and images come straight from the model.
+pub fn to_html(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut out = String::new();
+    render_md_blocks(&lines, &mut out);
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn fence_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    ["```", "~~~"]
+        .into_iter()
+        .find(|marker| trimmed.starts_with(marker))
+}
+
+fn heading_level(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some((level, rest.trim()))
+    } else {
+        None
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|marker| compact.chars().all(|c| c.to_string() == *marker))
+}
+
+// `(ordered, start, content)` for a list item line
+fn list_item(line: &str) -> Option<(bool, usize, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return Some((false, 1, rest));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        if let Some(rest) = line[digits..].strip_prefix(". ") {
+            return Some((true, line[..digits].parse().unwrap_or(1), rest));
+        }
+    }
+    None
+}
+
+fn is_table_separator(line: &str) -> bool {
+    let line = line.trim();
+    line.contains('-')
+        && line.starts_with('|')
+        && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(ch),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn starts_block(line: &str) -> bool {
+    fence_marker(line).is_some()
+        || heading_level(line).is_some()
+        || is_rule(line)
+        || line.starts_with('>')
+        || list_item(line).is_some()
+}
+
+fn render_md_blocks(lines: &[&str], out: &mut String) {
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(marker) = fence_marker(line) {
+            let language = line.trim_start()[marker.len()..].trim();
+            let mut code = Vec::new();
+            i += 1;
+            // An unterminated fence (e.g. a reply still streaming) runs to the end
+            while i < lines.len() && !lines[i].trim_start().starts_with(marker) {
+                code.push(lines[i]);
+                i += 1;
+            }
+            i += 1;
+            let class = if language.is_empty() {
+                String::new()
+            } else {
+                format!(" class=\"language-{}\"", escape_html(language))
+            };
+            let mut text = code.join("\n");
+            if !code.is_empty() {
+                text.push('\n');
+            }
+            out.push_str(&format!(
+                "<pre><code{}>{}</code></pre>",
+                class,
+                escape_html(&text)
+            ));
+        } else if let Some((level, text)) = heading_level(line) {
+            out.push_str(&format!(
+                "<h{}>{}</h{}>",
+                level,
+                render_md_inline(text),
+                level
+            ));
+            i += 1;
+        } else if is_rule(line) {
+            out.push_str("<hr>");
+            i += 1;
+        } else if line.starts_with('>') {
+            let mut quoted = Vec::new();
+            while i < lines.len() && lines[i].starts_with('>') {
+                let inner = &lines[i][1..];
+                quoted.push(inner.strip_prefix(' ').unwrap_or(inner));
+                i += 1;
+            }
+            out.push_str("<blockquote>");
+            render_md_blocks(&quoted, out);
+            out.push_str("</blockquote>");
+        } else if let Some((ordered, start, _)) = list_item(line) {
+            i = render_md_list(lines, i, ordered, start, out);
+        } else if line.trim_start().starts_with('|')
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| is_table_separator(next))
+        {
+            let header = table_cells(line);
+            out.push_str("<table><thead><tr>");
+            for cell in &header {
+                out.push_str(&format!("<th>{}</th>", render_md_inline(cell)));
+            }
+            out.push_str("</tr></thead><tbody>");
+            i += 2;
+            while i < lines.len() && lines[i].trim_start().starts_with('|') {
+                out.push_str("<tr>");
+                for cell in table_cells(lines[i]) {
+                    out.push_str(&format!("<td>{}</td>", render_md_inline(&cell)));
+                }
+                out.push_str("</tr>");
+                i += 1;
+            }
+            out.push_str("</tbody></table>");
+        } else {
+            let mut paragraph = vec![line.trim()];
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines[i]) {
+                paragraph.push(lines[i].trim());
+                i += 1;
+            }
+            out.push_str(&format!(
+                "<p>{}</p>",
+                render_md_inline(&paragraph.join("\n"))
+            ));
+        }
+    }
+}
+
+// Render the list starting at `lines[start_index]`, returning the index after it
+fn render_md_list(
+    lines: &[&str],
+    start_index: usize,
+    ordered: bool,
+    start: usize,
+    out: &mut String,
+) -> usize {
+    let tag = if ordered { "ol" } else { "ul" };
+    if ordered && start != 1 {
+        out.push_str(&format!("<ol start=\"{}\">", start));
+    } else {
+        out.push_str(&format!("<{}>", tag));
+    }
+
+    let mut i = start_index;
+    while let Some((item_ordered, _, first)) = lines.get(i).and_then(|line| list_item(line)) {
+        if item_ordered != ordered {
+            break;
+        }
+        // Indented lines (and blank lines followed by them) belong to the item
+        let mut content = vec![first];
+        i += 1;
+        while i < lines.len() {
+            let line = lines[i];
+            if line.starts_with("  ") || line.starts_with('\t') {
+                content.push(dedent(line));
+                i += 1;
+            } else if line.trim().is_empty()
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| next.starts_with("  ") || next.starts_with('\t'))
+            {
+                content.push("");
+                i += 1;
+            } else {
+                break;
+            }
+        }
+
+        let mut inner = String::new();
+        render_md_blocks(&content, &mut inner);
+        // Tight items hold their text directly
+        if let Some(text) = inner
+            .strip_prefix("<p>")
+            .and_then(|rest| rest.split_once("</p>"))
+            .filter(|(_, rest)| !rest.contains("<p>"))
+        {
+            inner = format!("{}{}", text.0, text.1);
+        }
+        out.push_str(&format!("<li>{}</li>", inner));
+    }
+    out.push_str(&format!("</{}>", tag));
+    i
+}
+
+// Strip one level of list indentation (up to four spaces or a tab)
+fn dedent(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix('\t') {
+        return rest;
+    }
+    let spaces = line.chars().take(4).take_while(|&c| c == ' ').count();
+    &line[spaces..]
+}
+
+// Find `delimiter` closing an inline span that starts at `from`
+fn find_closing(chars: &[char], from: usize, delimiter: &str) -> Option<usize> {
+    let delimiter: Vec<char> = delimiter.chars().collect();
+    if chars.get(from).is_none_or(|c| c.is_whitespace()) {
+        return None;
+    }
+    (from + 1..=chars.len().saturating_sub(delimiter.len()))
+        .find(|&j| chars[j..].starts_with(&delimiter) && !chars[j - 1].is_whitespace())
+}
+
+fn render_md_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        let rest = &chars[i..];
+        let collect = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+
+        if ch == '\\' && chars.get(i + 1).is_some_and(|c| c.is_ascii_punctuation()) {
+            out.push_str(&escape_html(&chars[i + 1].to_string()));
+            i += 2;
+            continue;
+        }
+
+        if ch == '`' {
+            let run = rest.iter().take_while(|&&c| c == '`').count();
+            let fence: String = "`".repeat(run);
+            let start = i + run;
+            let close = (start + 1..=chars.len().saturating_sub(run)).find(|&j| {
+                collect(j, j + run) == fence
+                    && chars[j - 1] != '`'
+                    && chars.get(j + run) != Some(&'`')
+            });
+            if let Some(close) = close {
+                let code = collect(start, close);
+                let code = if code.len() > 2 && code.starts_with(' ') && code.ends_with(' ') {
+                    &code[1..code.len() - 1]
+                } else {
+                    &code
+                };
+                out.push_str(&format!("<code>{}</code>", escape_html(code)));
+                i = close + run;
+            } else {
+                out.push_str(&fence);
+                i = start;
+            }
+            continue;
+        }
+
+        if ch == '!' && chars.get(i + 1) == Some(&'[') {
+            if let Some((alt, url, end)) = link_at(&chars, i + 1) {
+                out.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\">",
+                    escape_html(&url),
+                    escape_html(&alt)
+                ));
+                i = end;
+                continue;
+            }
+        }
+
+        if ch == '[' {
+            if let Some((label, url, end)) = link_at(&chars, i) {
+                out.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(&url),
+                    render_md_inline(&label)
+                ));
+                i = end;
+                continue;
+            }
+        }
+
+        let spans = [
+            ("**", "strong"),
+            ("__", "strong"),
+            ("~~", "del"),
+            ("*", "em"),
+            ("_", "em"),
+        ];
+        let mut matched = false;
+        for (delimiter, tag) in spans {
+            if !collect(i, (i + delimiter.len()).min(chars.len())).eq(delimiter) {
+                continue;
+            }
+            // `snake_case` words aren't emphasis
+            if delimiter.starts_with('_') && i > 0 && chars[i - 1].is_alphanumeric() {
+                continue;
+            }
+            let start = i + delimiter.len();
+            if let Some(close) = find_closing(&chars, start, delimiter) {
+                out.push_str(&format!(
+                    "<{}>{}</{}>",
+                    tag,
+                    render_md_inline(&collect(start, close)),
+                    tag
+                ));
+                i = close + delimiter.len();
+                matched = true;
+                break;
+            }
+        }
+        if matched {
+            continue;
+        }
+
+        if ch == '\n' {
+            // Two trailing spaces mark a hard line break
+            if out.ends_with("  ") {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push_str("<br>");
+            } else {
+                out.push('\n');
+            }
+        } else {
+            out.push_str(&escape_html(&ch.to_string()));
+        }
+        i += 1;
+    }
+    out
+}
+
+// `[label](url)` starting at `chars[start] == '['`: the label, URL and the index after it
+fn link_at(chars: &[char], start: usize) -> Option<(String, String, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    for (j, &c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = (close + 2..chars.len()).find(|&j| chars[j] == ')')?;
+    let label: String = chars[start + 1..close].iter().collect();
+    let url: String = chars[close + 2..url_end].iter().collect();
+    let url = url.split_whitespace().next().unwrap_or("").to_string();
+    Some((label, url, url_end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+
+    fn md(source: &str) -> String {
+        from_html(&html::sanitize(html::parse(source)))
+    }
+
+    #[test]
+    fn test_paragraphs_and_inline_formatting() {
+        assert_eq!(
+            md("<p>Hello <strong>bold</strong> and <em>it</em>\n  text</p><p>Use <code>a_b</code> and <a href=\"https://mistral.ai\">Mistral</a>.</p>"),
+            "Hello **bold** and *it* text\n\nUse `a_b` and [Mistral](https://mistral.ai)."
+        );
+    }
+
+    #[test]
+    fn test_markdown_characters_in_text_are_escaped() {
+        assert_eq!(md("<p>2 * 3 = my_var</p>"), "2 \\* 3 = my\\_var");
+    }
+
+    #[test]
+    fn test_headings_and_rules() {
+        assert_eq!(
+            md("<h2>Title</h2><hr><h3>Sub</h3>"),
+            "## Title\n\n---\n\n### Sub"
+        );
+    }
+
+    #[test]
+    fn test_code_block_keeps_language_and_whitespace() {
+        assert_eq!(
+            md("<pre><code class=\"language-rust\">fn main() {\n    println!(\"&lt;hi&gt;\");\n}\n</code></pre>"),
+            "```rust\nfn main() {\n    println!(\"<hi>\");\n}\n```"
+        );
+        assert_eq!(md("<pre><code>a ``` b</code></pre>"), "````\na ``` b\n````");
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        assert_eq!(
+            md("<ol><li>First<ul><li>a</li><li>b</li></ul></li><li><p>Second</p></li></ol>"),
+            "1. First\n   - a\n   - b\n2. Second"
+        );
+        assert_eq!(md("<ol start=\"3\"><li>x</li></ol>"), "3. x");
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(
+            md("<table><thead><tr><th>Name</th><th>Value</th></tr></thead><tbody><tr><td>a|b</td><td><code>1</code></td></tr><tr><td>c</td></tr></tbody></table>"),
+            "| Name | Value |\n| --- | --- |\n| a\\|b | `1` |\n| c |  |"
+        );
+    }
+
+    #[test]
+    fn test_blockquote() {
+        assert_eq!(
+            md("<blockquote><p>one</p><p>two</p></blockquote>"),
+            "> one\n>\n> two"
+        );
+    }
+
+    #[test]
+    fn test_unwrapped_containers_become_paragraphs() {
+        assert_eq!(
+            md("<div class=\"prose\"><span>Intro</span><ul><li>x</li></ul>outro</div>"),
+            "Intro\n\n- x\n\noutro"
+        );
+    }
+
+    // Markdown → HTML → Markdown should come back unchanged for what `from_html` emits
+    fn round_trip(source: &str) -> String {
+        from_html(&html::sanitize(html::parse(&to_html(source))))
+    }
+
+    #[test]
+    fn test_to_html_blocks() {
+        assert_eq!(
+            to_html("# Title\n\nSome *it* and **bold**\ntext.\n\n---\n\n> quote"),
+            "<h1>Title</h1><p>Some <em>it</em> and <strong>bold</strong>\ntext.</p><hr><blockquote><p>quote</p></blockquote>"
+        );
+        assert_eq!(
+            to_html("```rust\nlet a = 1 < 2;\n```"),
+            "<pre><code class=\"language-rust\">let a = 1 &lt; 2;\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_to_html_inline() {
+        assert_eq!(
+            to_html("Use `a<b` and [docs](https://docs.mistral.ai), not my_var or 2 * 3"),
+            "<p>Use <code>a&lt;b</code> and <a href=\"https://docs.mistral.ai\">docs</a>, not my_var or 2 * 3</p>"
+        );
+        assert_eq!(to_html("\\*literal\\*"), "<p>*literal*</p>");
+    }
+
+    #[test]
+    fn test_to_html_unterminated_fence_while_streaming() {
+        assert_eq!(
+            to_html("Here:\n```py\nprint(1)"),
+            "<p>Here:</p><pre><code class=\"language-py\">print(1)\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_to_html_round_trips() {
+        for source in [
+            "1. First\n   - a\n   - b\n2. Second",
+            "| Name | Value |\n| --- | --- |\n| a\\|b | `1` |",
+            "## Title\n\nHello **bold** and *it*\n\n```rust\nfn main() {}\n```",
+            "- one\n- two\n\n> quoted",
+        ] {
+            assert_eq!(round_trip(source), source);
+        }
+    }
+
+    #[test]
+    fn test_to_html_output_is_sanitized_by_caller() {
+        let html = html::serialize(&html::sanitize(html::parse(&to_html(
+            "[x](javascript:alert(1)) <script>y()</script>",
+        ))));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("<script"));
+    }
+}
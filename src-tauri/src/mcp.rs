@@ -0,0 +1,345 @@
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+pub use crate::pipeline::Snapshot;
+use crate::pipeline::{self, AppSession, AskMessage, SessionError};
+
+// `le-chat mcp`: a Model Context Protocol server on stdin/stdout for local
+// agents. It holds no session of its own; every call goes through the running
+// app over an `ask-connect` session, so prompts are typed into Le Chat with
+// the user's signed-in account and answers are read back from the page.
+
+// Newest first; an unknown version from the client is answered with the newest
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+pub const CONVERSATION_URI: &str = "lechat://conversation/current";
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_REQUEST: i64 = -32600;
+const PARSE_ERROR: i64 = -32700;
+// MCP's code for a resource that can't be read
+const RESOURCE_UNAVAILABLE: i64 = -32002;
+
+// Where tool calls and resource reads are sent
+pub trait Backend {
+    fn ask(&mut self, prompt: &str, new_chat: bool) -> Result<String, String>;
+    fn last_response(&mut self) -> Result<Snapshot, String>;
+    fn conversation(&mut self) -> Result<Snapshot, String>;
+}
+
+#[derive(serde::Deserialize)]
+struct AskArguments {
+    prompt: String,
+    #[serde(default = "default_new_chat")]
+    new_chat: bool,
+}
+
+fn default_new_chat() -> bool {
+    true
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "ask_le_chat",
+            "title": "Ask Le Chat",
+            "description": "Send a prompt to Le Chat in the user's desktop app, signed in to their account, and return the answer as Markdown.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "prompt": { "type": "string", "description": "The message to send" },
+                    "new_chat": {
+                        "type": "boolean",
+                        "description": "Start a new conversation (default) instead of continuing the current one",
+                        "default": true
+                    }
+                },
+                "required": ["prompt"]
+            }
+        },
+        {
+            "name": "get_last_response",
+            "title": "Get Le Chat's last response",
+            "description": "Return the most recent answer Le Chat gave in the desktop app, as Markdown.",
+            "inputSchema": { "type": "object", "properties": {} }
+        }
+    ])
+}
+
+fn resources() -> Value {
+    json!([{
+        "uri": CONVERSATION_URI,
+        "name": "current-conversation",
+        "title": "Current Le Chat conversation",
+        "description": "The conversation open in the Le Chat desktop app, as Markdown",
+        "mimeType": "text/markdown"
+    }])
+}
+
+fn tool_result(result: Result<String, String>) -> Value {
+    let (text, is_error) = match result {
+        Ok(text) => (text, false),
+        Err(error) => (error, true),
+    };
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+fn snapshot_text(snapshot: Snapshot) -> String {
+    if snapshot.url.is_empty() {
+        return snapshot.markdown;
+    }
+    format!("{}\n\n<{}>", snapshot.markdown, snapshot.url)
+}
+
+fn invalid_params(e: impl std::fmt::Display) -> (i64, String) {
+    (INVALID_PARAMS, e.to_string())
+}
+
+// Answer one request. Errors are JSON-RPC errors; a tool that fails returns a
+// result with `isError` so the agent sees why.
+fn handle(backend: &mut impl Backend, method: &str, params: Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = PROTOCOL_VERSIONS
+                .iter()
+                .find(|v| Some(**v) == requested)
+                .unwrap_or(&PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {}, "resources": {} },
+                "serverInfo": { "name": "le-chat", "version": env!("CARGO_PKG_VERSION") },
+                "instructions": "Consults Le Chat (Mistral AI) through the user's desktop app. Prompts appear in their chat history."
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => {
+            let name = params
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid_params("missing tool name"))?;
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            match name {
+                "ask_le_chat" => {
+                    let arguments: AskArguments =
+                        serde_json::from_value(arguments).map_err(invalid_params)?;
+                    if arguments.prompt.trim().is_empty() {
+                        return Err(invalid_params("the prompt is empty"));
+                    }
+                    Ok(tool_result(
+                        backend.ask(&arguments.prompt, arguments.new_chat),
+                    ))
+                }
+                "get_last_response" => Ok(tool_result(backend.last_response().map(snapshot_text))),
+                _ => Err(invalid_params(format!("unknown tool '{}'", name))),
+            }
+        }
+        "resources/list" => Ok(json!({ "resources": resources() })),
+        "resources/templates/list" => Ok(json!({ "resourceTemplates": [] })),
+        "resources/read" => {
+            let uri = params
+                .get("uri")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if uri != CONVERSATION_URI {
+                return Err(invalid_params(format!("unknown resource '{}'", uri)));
+            }
+            let snapshot = backend
+                .conversation()
+                .map_err(|e| (RESOURCE_UNAVAILABLE, e))?;
+            Ok(json!({
+                "contents": [{
+                    "uri": CONVERSATION_URI,
+                    "mimeType": "text/markdown",
+                    "text": snapshot_text(snapshot)
+                }]
+            }))
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+    }
+}
+
+// The reply to one line from the client, if it needs one
+pub fn respond(backend: &mut impl Backend, line: &str) -> Option<Value> {
+    let error = |id: Value, code: i64, message: String| {
+        Some(json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }))
+    };
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => return error(Value::Null, PARSE_ERROR, e.to_string()),
+    };
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        // Responses to requests we never send, or garbage
+        return id.and_then(|id| error(id, INVALID_REQUEST, "missing method".to_string()));
+    };
+    // Notifications such as `notifications/initialized` need no answer
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or(json!({}));
+    match handle(backend, method, params) {
+        Ok(result) => Some(json!({ "jsonrpc": "2.0", "id": id, "result": result })),
+        Err((code, message)) => error(id, code, message),
+    }
+}
+
+// Serve newline-delimited JSON-RPC until the client closes its end
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    backend: &mut impl Backend,
+) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = respond(backend, &line) {
+            writeln!(output, "{}", reply)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+// Backend reaching the running app; the session is opened on first use and
+// again after it breaks, e.g. because the app was restarted
+#[derive(Default)]
+pub struct AppBackend {
+    session: Option<AppSession>,
+}
+
+impl AppBackend {
+    fn with_session<T>(
+        &mut self,
+        call: impl FnOnce(&mut AppSession) -> Result<T, SessionError>,
+    ) -> Result<T, String> {
+        let session = match self.session.as_mut() {
+            Some(session) => session,
+            None => self
+                .session
+                .insert(AppSession::open().map_err(|e| e.to_string())?),
+        };
+        let result = call(session);
+        // A failed answer leaves the session usable; a broken connection doesn't
+        if !matches!(&result, Ok(_) | Err(SessionError::Failed(_))) {
+            self.session = None;
+        }
+        result.map_err(|e| e.to_string())
+    }
+}
+
+impl Backend for AppBackend {
+    fn ask(&mut self, prompt: &str, new_chat: bool) -> Result<String, String> {
+        self.with_session(|session| {
+            session.ask(prompt, new_chat, pipeline::DEFAULT_ANSWER_TIMEOUT, |_| {})
+        })
+    }
+
+    fn last_response(&mut self) -> Result<Snapshot, String> {
+        self.with_session(|session| session.snapshot(AskMessage::GetLastResponse))
+    }
+
+    fn conversation(&mut self) -> Result<Snapshot, String> {
+        self.with_session(|session| session.snapshot(AskMessage::GetConversation))
+    }
+}
+
+// `le-chat mcp`, run in the agent's child process. Returns the exit code.
+pub fn run_stdio() -> i32 {
+    let stdin = std::io::stdin();
+    match serve(stdin.lock(), std::io::stdout(), &mut AppBackend::default()) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("le-chat mcp: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Offline;
+
+    impl Backend for Offline {
+        fn ask(&mut self, _prompt: &str, _new_chat: bool) -> Result<String, String> {
+            Err("Le Chat is offline".to_string())
+        }
+        fn last_response(&mut self) -> Result<Snapshot, String> {
+            Err("Le Chat hasn't answered anything yet".to_string())
+        }
+        fn conversation(&mut self) -> Result<Snapshot, String> {
+            Err("No conversation is open".to_string())
+        }
+    }
+
+    #[test]
+    fn test_initialize_negotiates_the_version() {
+        let reply = respond(
+            &mut Offline,
+            r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"t","version":"1"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(reply["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(reply["result"]["serverInfo"]["name"], "le-chat");
+
+        let reply = respond(
+            &mut Offline,
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#,
+        )
+        .unwrap();
+        assert_eq!(reply["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[test]
+    fn test_notifications_get_no_reply() {
+        assert_eq!(
+            respond(
+                &mut Offline,
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tool_failures_are_results() {
+        let reply = respond(
+            &mut Offline,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"ask_le_chat","arguments":{"prompt":"hi"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(reply["result"]["isError"], true);
+        assert_eq!(reply["result"]["content"][0]["text"], "Le Chat is offline");
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let call = |line: &str| respond(&mut Offline, line).unwrap()["error"]["code"].clone();
+        assert_eq!(call("{"), PARSE_ERROR);
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":3,"method":"sampling/createMessage"}"#),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"rm_rf"}}"#),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            call(
+                r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"ask_le_chat","arguments":{}}}"#
+            ),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            call(
+                r#"{"jsonrpc":"2.0","id":6,"method":"resources/read","params":{"uri":"file:///etc/passwd"}}"#
+            ),
+            INVALID_PARAMS
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::Value;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.mistral.ai/v1";
+pub const DEFAULT_MODEL: &str = "mistral-small-latest";
+
+// Where the La Plateforme API key is kept in the OS keyring / Secret Service
+const KEYRING_SERVICE: &str = "le-chat";
+const KEYRING_USER: &str = "mistral-api-key";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// How long a streamed reply may go quiet, before it starts or between parts,
+// and how long the whole reply may take
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+// Longest error body quoted back to the user
+const MAX_ERROR_CHARS: usize = 200;
+
+// Connection settings for the launcher's "API" backend. The key is not part of
+// these; it lives in the keyring.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    // Chat completions endpoint root, e.g. a local mock server for testing
+    pub base_url: String,
+    pub model: String,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+        }
+    }
+}
+
+fn key_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())
+}
+
+// The stored API key, if one has been set
+pub fn api_key() -> Result<Option<String>, String> {
+    match key_entry()?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn set_api_key(key: &str) -> Result<(), String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err("The API key is empty".to_string());
+    }
+    key_entry()?.set_password(key).map_err(|e| e.to_string())
+}
+
+pub fn delete_api_key() -> Result<(), String> {
+    match key_entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    fn new(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+// The conversation held with the API, so launcher follow-ups keep their context
+#[derive(Default)]
+pub struct ApiConversation {
+    messages: Mutex<Vec<ChatMessage>>,
+    // Held for a whole request, so a follow-up is only sent once the reply it
+    // follows is part of the conversation
+    turn: tokio::sync::Mutex<()>,
+}
+
+impl ApiConversation {
+    pub async fn take_turn(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.turn.lock().await
+    }
+
+    // Add the user's message, starting over for a new chat, and return the
+    // messages to send
+    pub fn begin(&self, message: &str, new_chat: bool) -> Vec<ChatMessage> {
+        let mut messages = self.messages.lock().unwrap();
+        if new_chat {
+            messages.clear();
+        }
+        messages.push(ChatMessage::new("user", message));
+        messages.clone()
+    }
+
+    pub fn finish(&self, reply: &str) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push(ChatMessage::new("assistant", reply));
+    }
+
+    // Drop an unanswered user message so the next request stays well-formed
+    pub fn abandon(&self) {
+        let mut messages = self.messages.lock().unwrap();
+        if messages.last().is_some_and(|m| m.role == "user") {
+            messages.pop();
+        }
+    }
+}
+
+fn endpoint(base_url: &str, path: &str) -> String {
+    format!("{}/{}", base_url.trim().trim_end_matches('/'), path)
+}
+
+fn chat_request_body(model: &str, messages: &[ChatMessage]) -> Value {
+    serde_json::json!({
+        "model": model,
+        "messages": messages,
+        "stream": true,
+    })
+}
+
+// Splits a `text/event-stream` body into the `data` of each event. Chunks may
+// end anywhere, including inside a line or a multi-byte character.
+#[derive(Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(data) = line.strip_prefix("data:") {
+                self.data
+                    .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+            }
+            // Comments (`:`) and other fields (`event:`, `id:`) aren't used by the API
+        }
+        events
+    }
+}
+
+// The text added by one streamed completion chunk
+fn delta_content(data: &str) -> Result<String, String> {
+    let chunk: Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+    Ok(chunk
+        .pointer("/choices/0/delta/content")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string())
+}
+
+// A readable message for a failed API request
+fn error_message(status: u16, body: &str) -> String {
+    let parsed = serde_json::from_str::<Value>(body).ok();
+    let detail = parsed
+        .as_ref()
+        .and_then(|v| {
+            v.get("message")
+                .or_else(|| v.pointer("/error/message"))
+                .or_else(|| v.get("detail"))
+        })
+        .map(|detail| match detail {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .unwrap_or_else(|| body.trim().chars().take(MAX_ERROR_CHARS).collect());
+    match status {
+        401 => "The API key was rejected — check it in Settings".to_string(),
+        _ if detail.is_empty() => format!("API request failed ({})", status),
+        _ => format!("API request failed ({}): {}", status, detail),
+    }
+}
+
+fn parse_models(body: &Value) -> Vec<String> {
+    let mut models: Vec<String> = body
+        .get("data")
+        .and_then(Value::as_array)
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| m.get("id")?.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort();
+    models.dedup();
+    models
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(error_message(status.as_u16(), &body))
+}
+
+// Model ids offered by the server, for the settings model picker
+pub async fn list_models(settings: &ApiSettings, key: &str) -> Result<Vec<String>, String> {
+    let response = client()?
+        .get(endpoint(&settings.base_url, "models"))
+        .bearer_auth(key)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let body = check_status(response)
+        .await?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+    let body: Value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    Ok(parse_models(&body))
+}
+
+// Limits on a streamed reply: `idle` for each wait, `total` for all of them
+struct ReplyLimits {
+    idle: Duration,
+    total: Duration,
+    deadline: tokio::time::Instant,
+}
+
+impl ReplyLimits {
+    fn start(idle: Duration, total: Duration) -> Self {
+        Self {
+            idle,
+            total,
+            deadline: tokio::time::Instant::now() + total,
+        }
+    }
+
+    async fn wait<T>(
+        &self,
+        future: impl std::future::Future<Output = Result<T, String>>,
+    ) -> Result<T, String> {
+        let limit = self.deadline.min(tokio::time::Instant::now() + self.idle);
+        match tokio::time::timeout_at(limit, future).await {
+            Ok(result) => result,
+            Err(_) if limit == self.deadline => Err(format!(
+                "The API didn't finish the reply within {}s",
+                self.total.as_secs()
+            )),
+            Err(_) => Err(format!("The API sent nothing for {}s", self.idle.as_secs())),
+        }
+    }
+}
+
+// Send the conversation to the chat completions endpoint and stream the reply.
+// `on_text` receives the whole reply so far each time it grows; the complete
+// reply is returned. Fails if the server stalls or the reply takes too long.
+pub async fn stream_chat(
+    settings: &ApiSettings,
+    key: &str,
+    messages: &[ChatMessage],
+    on_text: impl FnMut(&str),
+) -> Result<String, String> {
+    let limits = ReplyLimits::start(IDLE_TIMEOUT, REPLY_TIMEOUT);
+    stream_chat_within(settings, key, messages, limits, on_text).await
+}
+
+async fn stream_chat_within(
+    settings: &ApiSettings,
+    key: &str,
+    messages: &[ChatMessage],
+    limits: ReplyLimits,
+    mut on_text: impl FnMut(&str),
+) -> Result<String, String> {
+    let body = chat_request_body(&settings.model, messages);
+    let request = client()?
+        .post(endpoint(&settings.base_url, "chat/completions"))
+        .bearer_auth(key)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .body(body.to_string());
+    let mut response = limits
+        .wait(async {
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            check_status(response).await
+        })
+        .await?;
+
+    let mut decoder = SseDecoder::default();
+    let mut reply = String::new();
+    while let Some(chunk) = limits
+        .wait(async { response.chunk().await.map_err(|e| e.to_string()) })
+        .await?
+    {
+        for data in decoder.push(&chunk) {
+            if data == "[DONE]" {
+                return Ok(reply);
+            }
+            let text = delta_content(&data)?;
+            if !text.is_empty() {
+                reply.push_str(&text);
+                on_text(&reply);
+            }
+        }
+    }
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A server that answers with the start of a reply and then goes quiet
+    fn stalling_server() -> ApiSettings {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n\
+                  data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            );
+            std::thread::sleep(Duration::from_secs(5));
+        });
+        ApiSettings {
+            base_url,
+            model: DEFAULT_MODEL.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_fails_when_the_server_stalls() {
+        let messages = [ChatMessage::new("user", "Hello")];
+        let mut partial = String::new();
+        let limits = ReplyLimits::start(Duration::from_millis(200), Duration::from_secs(3));
+        let err = stream_chat_within(&stalling_server(), "key", &messages, limits, |reply| {
+            partial = reply.to_string()
+        })
+        .await
+        .unwrap_err();
+        assert!(err.contains("sent nothing"), "{}", err);
+        assert_eq!(partial, "Hel");
+
+        let limits = ReplyLimits::start(Duration::from_secs(3), Duration::from_millis(200));
+        let err = stream_chat_within(&stalling_server(), "key", &messages, limits, |_| {})
+            .await
+            .unwrap_err();
+        assert!(err.contains("didn't finish the reply"), "{}", err);
+    }
+
+    #[test]
+    fn test_endpoint_joins_paths() {
+        assert_eq!(
+            endpoint("https://api.mistral.ai/v1", "chat/completions"),
+            "https://api.mistral.ai/v1/chat/completions"
+        );
+        assert_eq!(
+            endpoint(" http://localhost:8080/v1/ ", "models"),
+            "http://localhost:8080/v1/models"
+        );
+    }
+
+    #[test]
+    fn test_chat_request_body() {
+        let body = chat_request_body("mistral-small-latest", &[ChatMessage::new("user", "Hello")]);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "model": "mistral-small-latest",
+                "messages": [{ "role": "user", "content": "Hello" }],
+                "stream": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_sse_decoder_handles_split_chunks() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: {\"a\":").is_empty());
+        assert_eq!(
+            decoder.push(b"1}\r\n\r\n: ping\n\ndata: [DO"),
+            vec!["{\"a\":1}"]
+        );
+        assert_eq!(decoder.push(b"NE]\n\n"), vec!["[DONE]"]);
+
+        // A multi-byte character split across chunks
+        let event = "data: é\n\n".as_bytes();
+        assert!(decoder.push(&event[..7]).is_empty());
+        assert_eq!(decoder.push(&event[7..]), vec!["é"]);
+    }
+
+    #[test]
+    fn test_delta_content() {
+        let chunk =
+            r#"{"id":"x","choices":[{"index":0,"delta":{"role":"assistant","content":"Hi"}}]}"#;
+        assert_eq!(delta_content(chunk).unwrap(), "Hi");
+        let last = r#"{"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#;
+        assert_eq!(delta_content(last).unwrap(), "");
+        assert!(delta_content("not json").is_err());
+    }
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            error_message(400, r#"{"object":"error","message":"Invalid model: x"}"#),
+            "API request failed (400): Invalid model: x"
+        );
+        assert_eq!(
+            error_message(429, r#"{"error":{"message":"Rate limit exceeded"}}"#),
+            "API request failed (429): Rate limit exceeded"
+        );
+        assert_eq!(error_message(502, ""), "API request failed (502)");
+        assert!(error_message(401, "").contains("API key"));
+    }
+
+    #[test]
+    fn test_parse_models() {
+        let body = serde_json::json!({
+            "data": [{ "id": "mistral-small-latest" }, { "id": "codestral-latest" }, { "id": "mistral-small-latest" }]
+        });
+        assert_eq!(
+            parse_models(&body),
+            vec!["codestral-latest", "mistral-small-latest"]
+        );
+    }
+
+    #[test]
+    fn test_conversation_follow_ups() {
+        let conversation = ApiConversation::default();
+        assert_eq!(conversation.begin("one", true).len(), 1);
+        conversation.finish("reply");
+        let messages = conversation.begin("two", false);
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user"]);
+
+        conversation.abandon();
+        assert_eq!(conversation.begin("three", false).len(), 3);
+        assert_eq!(conversation.begin("fresh", true).len(), 1);
+    }
+}
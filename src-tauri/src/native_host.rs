@@ -0,0 +1,651 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tauri::Url;
+
+use crate::deep_link::MAX_PROMPT_CHARS;
+use crate::pipeline::AppSession;
+
+// WebExtension native-messaging host, so a browser extension can send the
+// selected text or the page's URL to the app. The browser starts the binary
+// with the extension's origin (Chromium) or the manifest path and extension id
+// (Firefox) as arguments, then exchanges JSON messages on stdin/stdout, each
+// preceded by its length as a 32-bit integer in native byte order. Prompts
+// reach the running app the same way `le-chat ask` does.
+
+// Name browsers look the host up by; also the manifest's file name
+pub const HOST_NAME: &str = "ai.mistral.lechat";
+
+// Browsers drop hosts that send more than 1 MB in one message
+pub const MAX_OUTGOING_BYTES: usize = 1024 * 1024;
+// A full-length selection plus the page's URL and title fits comfortably
+pub const MAX_INCOMING_BYTES: usize = 64 * 1024;
+const MAX_URL_CHARS: usize = 2048;
+// Longer titles are cut rather than rejected
+const MAX_TITLE_CHARS: usize = 200;
+// How long the app gets to queue a prompt, including starting up
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Message(Vec<u8>),
+    // Skipped without reading it, since it's over MAX_INCOMING_BYTES
+    TooLarge(usize),
+}
+
+// The next message from the browser, or None once it closes the port
+pub fn read_frame(reader: &mut impl Read) -> Result<Option<Frame>, String> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err("message length cut short".to_string()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    let length = u32::from_ne_bytes(header) as usize;
+    if length > MAX_INCOMING_BYTES {
+        // Read past it so the next message can still be understood
+        let skipped = std::io::copy(
+            &mut reader.by_ref().take(length as u64),
+            &mut std::io::sink(),
+        )
+        .map_err(|e| e.to_string())?;
+        if skipped < length as u64 {
+            return Err("message cut short".to_string());
+        }
+        return Ok(Some(Frame::TooLarge(length)));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| "message cut short".to_string())?;
+    Ok(Some(Frame::Message(body)))
+}
+
+pub fn write_frame(writer: &mut impl Write, message: &Value) -> Result<(), String> {
+    let body = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+    if body.len() > MAX_OUTGOING_BYTES {
+        return Err(format!(
+            "reply of {} bytes is over the browser's limit",
+            body.len()
+        ));
+    }
+    writer
+        .write_all(&(body.len() as u32).to_ne_bytes())
+        .and_then(|_| writer.write_all(&body))
+        .and_then(|_| writer.flush())
+        .map_err(|e| e.to_string())
+}
+
+// Where the extension wants the text to go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    // Waits in the launcher so the user can add a question before sending
+    #[default]
+    Launcher,
+    // Sent right away, in a new conversation in the main window
+    NewChat,
+}
+
+// Messages from the extension
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HostRequest {
+    Ping,
+    Send {
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        url: String,
+        #[serde(default)]
+        title: String,
+        #[serde(default)]
+        target: Target,
+    },
+}
+
+fn check_page_url(url: &str) -> Result<(), String> {
+    if url.chars().count() > MAX_URL_CHARS {
+        return Err(format!(
+            "the URL is longer than {} characters",
+            MAX_URL_CHARS
+        ));
+    }
+    let parsed = Url::parse(url).map_err(|e| format!("invalid URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("'{}' is not a web page", url));
+    }
+    Ok(())
+}
+
+// The prompt for a selection and the page it came from. With only a URL, the
+// prompt is the page itself.
+pub fn build_prompt(text: &str, url: &str, title: &str) -> Result<String, String> {
+    let text = text.trim();
+    let url = url.trim();
+    let title: String = title.trim().chars().take(MAX_TITLE_CHARS).collect();
+    if !url.is_empty() {
+        check_page_url(url)?;
+    }
+
+    let source = match (title.is_empty(), url.is_empty()) {
+        (_, true) => String::new(),
+        (true, false) => url.to_string(),
+        (false, false) => format!("{} ({})", title, url),
+    };
+    let prompt = match (text.is_empty(), source.is_empty()) {
+        (true, true) => return Err("Nothing to send: select some text first".to_string()),
+        (false, true) => text.to_string(),
+        (true, false) => source,
+        (false, false) => format!("{}\n\nSource: {}", text, source),
+    };
+    if prompt.chars().count() > MAX_PROMPT_CHARS {
+        return Err(format!(
+            "the selection is longer than {} characters",
+            MAX_PROMPT_CHARS
+        ));
+    }
+    Ok(prompt)
+}
+
+// Where prompts are delivered
+pub trait Delivery {
+    fn prefill(&mut self, prompt: &str) -> Result<(), String>;
+    // Returns the submission id
+    fn new_chat(&mut self, prompt: &str) -> Result<u64, String>;
+}
+
+fn error_reply(error: impl std::fmt::Display) -> Value {
+    json!({ "ok": false, "error": error.to_string() })
+}
+
+fn handle(delivery: &mut impl Delivery, request: HostRequest) -> Value {
+    let HostRequest::Send {
+        text,
+        url,
+        title,
+        target,
+    } = request
+    else {
+        return json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") });
+    };
+    let prompt = match build_prompt(&text, &url, &title) {
+        Ok(prompt) => prompt,
+        Err(e) => return error_reply(e),
+    };
+    let delivered = match target {
+        Target::Launcher => delivery.prefill(&prompt).map(|()| json!({ "ok": true })),
+        Target::NewChat => delivery
+            .new_chat(&prompt)
+            .map(|id| json!({ "ok": true, "submission_id": id })),
+    };
+    delivered.unwrap_or_else(error_reply)
+}
+
+// The reply to one message. An `id` in the request is echoed back so an
+// extension with several messages in flight can match the replies up.
+pub fn respond(delivery: &mut impl Delivery, frame: Frame) -> Value {
+    let body = match frame {
+        Frame::Message(body) => body,
+        Frame::TooLarge(length) => {
+            return error_reply(format!(
+                "message of {} bytes is over the {} byte limit",
+                length, MAX_INCOMING_BYTES
+            ))
+        }
+    };
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => return error_reply(format!("invalid message: {}", e)),
+    };
+    let id = message.get("id").cloned();
+    let mut reply = match serde_json::from_value::<HostRequest>(message) {
+        Ok(request) => handle(delivery, request),
+        Err(e) => error_reply(format!("invalid message: {}", e)),
+    };
+    if let Some(id) = id {
+        reply["id"] = id;
+    }
+    reply
+}
+
+// Answer messages until the browser closes the port
+pub fn serve(
+    mut input: impl Read,
+    mut output: impl Write,
+    delivery: &mut impl Delivery,
+) -> Result<(), String> {
+    while let Some(frame) = read_frame(&mut input)? {
+        write_frame(&mut output, &respond(delivery, frame))?;
+    }
+    Ok(())
+}
+
+// Delivery to the running app, connected on the first prompt
+#[derive(Default)]
+pub struct AppDelivery {
+    session: Option<AppSession>,
+}
+
+impl AppDelivery {
+    fn session(&mut self) -> Result<&mut AppSession, String> {
+        match self.session {
+            Some(ref mut session) => Ok(session),
+            None => Ok(self
+                .session
+                .insert(AppSession::open().map_err(|e| e.to_string())?)),
+        }
+    }
+}
+
+impl Delivery for AppDelivery {
+    fn prefill(&mut self, prompt: &str) -> Result<(), String> {
+        let result = self.session()?.prefill(prompt, None);
+        if result.is_err() {
+            self.session = None;
+        }
+        result.map_err(|e| e.to_string())
+    }
+
+    fn new_chat(&mut self, prompt: &str) -> Result<u64, String> {
+        let result = self.session()?.submit(prompt, true, true, SUBMIT_TIMEOUT);
+        if result.is_err() {
+            self.session = None;
+        }
+        result.map_err(|e| e.to_string())
+    }
+}
+
+// Run as the browser's host. Returns the exit code.
+pub fn run_stdio() -> i32 {
+    let stdin = std::io::stdin();
+    match serve(stdin.lock(), std::io::stdout(), &mut AppDelivery::default()) {
+        Ok(()) => 0,
+        Err(e) => {
+            // Browsers show the host's stderr in their console
+            eprintln!("le-chat native host: {}", e);
+            1
+        }
+    }
+}
+
+// Whether the browser started us as its native-messaging host
+pub fn is_host_launch(args: &[String]) -> bool {
+    match args {
+        [origin, ..] if origin.starts_with("chrome-extension://") => true,
+        [manifest, _extension_id] => Path::new(manifest)
+            .file_name()
+            .is_some_and(|name| name == format!("{}.json", HOST_NAME).as_str()),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    Chrome,
+    Chromium,
+}
+
+impl Browser {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "firefox" => Ok(Browser::Firefox),
+            "chrome" => Ok(Browser::Chrome),
+            "chromium" => Ok(Browser::Chromium),
+            _ => Err(format!(
+                "unknown browser '{}': use firefox, chrome or chromium",
+                name
+            )),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Browser::Firefox => "firefox",
+            Browser::Chrome => "chrome",
+            Browser::Chromium => "chromium",
+        }
+    }
+}
+
+// Chromium ids are 32 letters a-p; Firefox ids are an email-like name or a {GUID}
+pub fn check_extension_id(browser: Browser, id: &str) -> Result<(), String> {
+    let valid = match browser {
+        Browser::Chrome | Browser::Chromium => {
+            id.len() == 32 && id.bytes().all(|b| (b'a'..=b'p').contains(&b))
+        }
+        Browser::Firefox => {
+            let name_chars = id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._@{}".contains(c));
+            let shaped = (id.starts_with('{') && id.ends_with('}')) || id.contains('@');
+            !id.is_empty() && id.len() <= 80 && name_chars && shaped
+        }
+    };
+    if !valid {
+        return Err(format!(
+            "'{}' is not a {} extension id",
+            id,
+            browser.label()
+        ));
+    }
+    Ok(())
+}
+
+// The host manifest that lets one extension start `exe`
+pub fn manifest(browser: Browser, extension_id: &str, exe: &Path) -> Result<Value, String> {
+    check_extension_id(browser, extension_id)?;
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Send text from the browser to Le Chat",
+        "path": exe,
+        "type": "stdio",
+    });
+    match browser {
+        Browser::Firefox => manifest["allowed_extensions"] = json!([extension_id]),
+        Browser::Chrome | Browser::Chromium => {
+            manifest["allowed_origins"] = json!([format!("chrome-extension://{}/", extension_id)])
+        }
+    }
+    Ok(manifest)
+}
+
+// The per-user directory the browser reads host manifests from
+#[cfg(all(unix, not(target_os = "macos")))]
+fn manifest_dir(browser: Browser, home: &Path) -> PathBuf {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"));
+    match browser {
+        Browser::Firefox => home.join(".mozilla/native-messaging-hosts"),
+        Browser::Chrome => config.join("google-chrome/NativeMessagingHosts"),
+        Browser::Chromium => config.join("chromium/NativeMessagingHosts"),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn manifest_dir(browser: Browser, home: &Path) -> PathBuf {
+    let support = home.join("Library/Application Support");
+    match browser {
+        Browser::Firefox => support.join("Mozilla/NativeMessagingHosts"),
+        Browser::Chrome => support.join("Google/Chrome/NativeMessagingHosts"),
+        Browser::Chromium => support.join("Chromium/NativeMessagingHosts"),
+    }
+}
+
+// On Windows the manifest can live anywhere; the registry points to it
+#[cfg(windows)]
+fn manifest_dir(browser: Browser, _home: &Path) -> PathBuf {
+    let local = std::env::var_os("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    local
+        .join(HOST_NAME)
+        .join("NativeMessagingHosts")
+        .join(browser.label())
+}
+
+#[cfg(windows)]
+fn register(browser: Browser, manifest_path: &Path) -> Result<(), String> {
+    let vendor = match browser {
+        Browser::Firefox => "Mozilla",
+        Browser::Chrome => "Google\\Chrome",
+        Browser::Chromium => "Chromium",
+    };
+    let key = format!(
+        "HKCU\\Software\\{}\\NativeMessagingHosts\\{}",
+        vendor, HOST_NAME
+    );
+    let status = std::process::Command::new("reg")
+        .arg("add")
+        .arg(&key)
+        .args(["/ve", "/t", "REG_SZ", "/f", "/d"])
+        .arg(manifest_path)
+        .stdout(std::process::Stdio::null())
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("failed to register {}", key));
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn register(_browser: Browser, _manifest_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+// Write the manifest where the browser finds it. Returns its path.
+pub fn install(browser: Browser, extension_id: &str) -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let manifest = manifest(browser, extension_id, &exe)?;
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .ok_or("can't find the home directory")?;
+    let dir = manifest_dir(browser, &home);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    let contents = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+    register(browser, &path)?;
+    Ok(path)
+}
+
+// `le-chat native-host`: print the manifest, or install it. Returns the exit code.
+pub fn run_manifest(browser: Browser, extension_id: &str, install_it: bool) -> i32 {
+    let result = if install_it {
+        install(browser, extension_id).map(|path| format!("Installed {}", path.display()))
+    } else {
+        std::env::current_exe()
+            .map_err(|e| e.to_string())
+            .and_then(|exe| manifest(browser, extension_id, &exe))
+            .and_then(|manifest| serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string()))
+    };
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            0
+        }
+        Err(e) => {
+            eprintln!("le-chat: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(body: &[u8]) -> Vec<u8> {
+        let mut bytes = (body.len() as u32).to_ne_bytes().to_vec();
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    #[derive(Default)]
+    struct FakeApp {
+        prefilled: Vec<String>,
+        sent: Vec<String>,
+    }
+
+    impl Delivery for FakeApp {
+        fn prefill(&mut self, prompt: &str) -> Result<(), String> {
+            self.prefilled.push(prompt.to_string());
+            Ok(())
+        }
+
+        fn new_chat(&mut self, prompt: &str) -> Result<u64, String> {
+            self.sent.push(prompt.to_string());
+            Ok(self.sent.len() as u64)
+        }
+    }
+
+    #[test]
+    fn test_frames_round_trip() {
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, &json!({ "ok": true })).unwrap();
+        assert_eq!(bytes, frame(br#"{"ok":true}"#));
+
+        let mut input = &bytes[..];
+        assert_eq!(
+            read_frame(&mut input),
+            Ok(Some(Frame::Message(br#"{"ok":true}"#.to_vec())))
+        );
+        assert_eq!(read_frame(&mut input), Ok(None));
+    }
+
+    #[test]
+    fn test_truncated_frames_are_errors() {
+        assert!(read_frame(&mut &[1u8, 0][..]).is_err());
+        let mut bytes = frame(b"{}");
+        bytes.pop();
+        assert!(read_frame(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_oversized_frames_are_skipped() {
+        let mut bytes = frame(&vec![b' '; MAX_INCOMING_BYTES + 1]);
+        bytes.extend(frame(br#"{"action":"ping"}"#));
+        let mut input = &bytes[..];
+        assert_eq!(
+            read_frame(&mut input),
+            Ok(Some(Frame::TooLarge(MAX_INCOMING_BYTES + 1)))
+        );
+        assert!(matches!(
+            read_frame(&mut input),
+            Ok(Some(Frame::Message(_)))
+        ));
+
+        let reply = respond(&mut FakeApp::default(), Frame::TooLarge(1 << 20));
+        assert_eq!(reply["ok"], false);
+    }
+
+    #[test]
+    fn test_replies_over_the_browser_limit_are_refused() {
+        let mut bytes = Vec::new();
+        let huge = json!({ "text": "x".repeat(MAX_OUTGOING_BYTES) });
+        assert!(write_frame(&mut bytes, &huge).is_err());
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(
+            build_prompt("  some text ", "", ""),
+            Ok("some text".to_string())
+        );
+        assert_eq!(
+            build_prompt("some text", "https://example.com/a", "Example"),
+            Ok("some text\n\nSource: Example (https://example.com/a)".to_string())
+        );
+        assert_eq!(
+            build_prompt("", "https://example.com/a", ""),
+            Ok("https://example.com/a".to_string())
+        );
+        assert!(build_prompt(" ", "", "Title only").is_err());
+        assert!(build_prompt("x", "file:///etc/passwd", "").is_err());
+        assert!(build_prompt("x", "not a url", "").is_err());
+        assert!(build_prompt(&"x".repeat(MAX_PROMPT_CHARS + 1), "", "").is_err());
+        let long_title = "t".repeat(1000);
+        let prompt = build_prompt("x", "https://example.com", &long_title).unwrap();
+        assert!(prompt.len() < 300);
+    }
+
+    #[test]
+    fn test_messages_are_delivered() {
+        let mut app = FakeApp::default();
+        let mut input = Vec::new();
+        for message in [
+            json!({ "action": "ping", "id": 1 }),
+            json!({ "action": "send", "text": "Explain this", "id": 2 }),
+            json!({ "action": "send", "url": "https://example.com", "target": "new_chat" }),
+            json!({ "action": "send", "target": "new_chat" }),
+            json!({ "action": "delete_history" }),
+        ] {
+            input.extend(frame(message.to_string().as_bytes()));
+        }
+        input.extend(frame(b"not json"));
+
+        let mut output = Vec::new();
+        serve(&input[..], &mut output, &mut app).unwrap();
+        let mut replies = Vec::new();
+        let mut output = &output[..];
+        while let Some(Frame::Message(body)) = read_frame(&mut output).unwrap() {
+            replies.push(serde_json::from_slice::<Value>(&body).unwrap());
+        }
+
+        assert_eq!(replies.len(), 6);
+        assert_eq!(replies[0]["id"], 1);
+        assert_eq!(replies[0]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(replies[1], json!({ "ok": true, "id": 2 }));
+        assert_eq!(replies[2], json!({ "ok": true, "submission_id": 1 }));
+        for reply in &replies[3..] {
+            assert_eq!(reply["ok"], false);
+        }
+        assert_eq!(app.prefilled, vec!["Explain this"]);
+        assert_eq!(app.sent, vec!["https://example.com"]);
+    }
+
+    #[test]
+    fn test_host_launch_detection() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(is_host_launch(&args(&[
+            "chrome-extension://abcdefghijklmnopabcdefghijklmnop/"
+        ])));
+        assert!(is_host_launch(&args(&[
+            "chrome-extension://abcdefghijklmnopabcdefghijklmnop/",
+            "--parent-window=0"
+        ])));
+        assert!(is_host_launch(&args(&[
+            "/home/u/.mozilla/native-messaging-hosts/ai.mistral.lechat.json",
+            "send-to-le-chat@example.org"
+        ])));
+        assert!(!is_host_launch(&args(&[
+            "open",
+            "https://chat.mistral.ai/chat/1"
+        ])));
+        assert!(!is_host_launch(&args(&[])));
+    }
+
+    #[test]
+    fn test_manifests() {
+        let exe = Path::new("/usr/bin/le-chat");
+        let chrome = manifest(Browser::Chrome, "abcdefghijklmnopabcdefghijklmnop", exe).unwrap();
+        assert_eq!(chrome["name"], HOST_NAME);
+        assert_eq!(chrome["type"], "stdio");
+        assert_eq!(chrome["path"], "/usr/bin/le-chat");
+        assert_eq!(
+            chrome["allowed_origins"],
+            json!(["chrome-extension://abcdefghijklmnopabcdefghijklmnop/"])
+        );
+        assert!(chrome.get("allowed_extensions").is_none());
+
+        let firefox = manifest(Browser::Firefox, "send-to-le-chat@example.org", exe).unwrap();
+        assert_eq!(
+            firefox["allowed_extensions"],
+            json!(["send-to-le-chat@example.org"])
+        );
+        assert!(manifest(
+            Browser::Firefox,
+            "{0f8e7d6c-1234-4abc-9def-0123456789ab}",
+            exe
+        )
+        .is_ok());
+
+        assert!(manifest(Browser::Chromium, "too-short", exe).is_err());
+        assert!(manifest(Browser::Chrome, "ABCDEFGHIJKLMNOPABCDEFGHIJKLMNOP", exe).is_err());
+        assert!(manifest(Browser::Firefox, "no at sign", exe).is_err());
+        assert!(Browser::parse("Firefox").is_ok());
+        assert!(Browser::parse("safari").is_err());
+    }
+}
@@ -0,0 +1,356 @@
+use tauri::Url;
+
+// Hosts the main window may load, subdomains included: Le Chat itself and its sign-in pages
+const MISTRAL_DOMAINS: &[&str] = &["mistral.ai"];
+
+// Identity providers Le Chat signs in through, directly or via enterprise SSO
+const AUTH_DOMAINS: &[&str] = &[
+    "accounts.google.com",
+    "login.microsoftonline.com",
+    "login.microsoft.com",
+    "login.live.com",
+    "appleid.apple.com",
+    "okta.com",
+    "oktapreview.com",
+    "onelogin.com",
+    "auth0.com",
+    "pingidentity.com",
+    "duosecurity.com",
+];
+
+// Le Chat's own sign-in pages
+const MISTRAL_AUTH_HOST: &str = "auth.mistral.ai";
+
+// Where sign-in popups hand back to Le Chat
+const CHAT_HOST: &str = "chat.mistral.ai";
+
+// Non-web links the system knows how to open, e.g. in the mail client
+const SYSTEM_SCHEMES: &[&str] = &["mailto", "tel"];
+
+// Which links the main window keeps and what happens to the rest
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct NavigationSettings {
+    // Extra hosts allowed in the main window, e.g. an enterprise SSO domain.
+    // Subdomains are included.
+    pub allowed_domains: Vec<String>,
+    // Open other links in the default browser; when off they're ignored
+    pub open_external: bool,
+}
+
+impl Default for NavigationSettings {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            open_external: true,
+        }
+    }
+}
+
+// Where a URL requested by the main window ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    // Loaded in the window that asked for it
+    Window,
+    // Opened in a sign-in popup that shares the main window's session
+    Popup,
+    // Handed to the default browser or the system's handler for the scheme
+    Browser,
+    // Dropped
+    Blocked,
+}
+
+// "*.Example.com." → "example.com"
+fn normalize_domain(domain: &str) -> String {
+    domain
+        .trim()
+        .trim_start_matches("*.")
+        .trim_matches('.')
+        .to_ascii_lowercase()
+}
+
+fn matches_domain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn is_user_allowed_host(host: &str, settings: &NavigationSettings) -> bool {
+    settings
+        .allowed_domains
+        .iter()
+        .map(|domain| normalize_domain(domain))
+        .any(|domain| !domain.is_empty() && matches_domain(host, &domain))
+}
+
+fn is_allowed_host(host: &str, settings: &NavigationSettings) -> bool {
+    let host = host.to_ascii_lowercase();
+    MISTRAL_DOMAINS
+        .iter()
+        .chain(AUTH_DOMAINS)
+        .any(|domain| matches_domain(&host, domain))
+        || is_user_allowed_host(&host, settings)
+}
+
+// Identity provider pages, Mistral's own or one added in the settings
+fn is_sign_in_page(url: &Url, settings: &NavigationSettings) -> bool {
+    let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+        return false;
+    };
+    url.scheme() == "https"
+        && (host == MISTRAL_AUTH_HOST
+            || AUTH_DOMAINS
+                .iter()
+                .any(|domain| matches_domain(&host, domain))
+            || is_user_allowed_host(&host, settings))
+}
+
+// Whether a sign-in popup has redirected back to Le Chat and is done
+pub fn returns_to_chat(url: &Url) -> bool {
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .is_some_and(|host| host.eq_ignore_ascii_case(CHAT_HOST))
+}
+
+// The app's own pages, e.g. the offline page (tauri.localhost on Windows)
+fn is_local_page(url: &Url) -> bool {
+    url.scheme() == "tauri"
+        || (matches!(url.scheme(), "http" | "https") && url.host_str() == Some("tauri.localhost"))
+}
+
+// Decide where a navigation in the main window goes. Only allowed hosts over
+// HTTPS and the app's own pages stay in the window.
+pub fn classify(url: &Url, settings: &NavigationSettings) -> Destination {
+    let external = if settings.open_external {
+        Destination::Browser
+    } else {
+        Destination::Blocked
+    };
+    if is_local_page(url) {
+        return Destination::Window;
+    }
+    match url.scheme() {
+        // Blank frames and files the page generated itself
+        "about" | "blob" => Destination::Window,
+        "https" if url.host_str().is_some_and(|h| is_allowed_host(h, settings)) => {
+            Destination::Window
+        }
+        "http" | "https" => external,
+        scheme if SYSTEM_SCHEMES.contains(&scheme) => external,
+        _ => Destination::Blocked,
+    }
+}
+
+// Decide where a `target=_blank` link or `window.open` goes. Sign-in pages get
+// a popup, so the page that opened it can hear back from it. There is only one
+// main window, so other allowed pages replace it; blank and local pages have
+// nowhere to go.
+pub fn classify_new_window(url: &Url, settings: &NavigationSettings) -> Destination {
+    if is_sign_in_page(url, settings) {
+        return Destination::Popup;
+    }
+    match classify(url, settings) {
+        Destination::Window if url.scheme() != "https" || is_local_page(url) => {
+            Destination::Blocked
+        }
+        destination => destination,
+    }
+}
+
+// Reject entries that aren't plain host names
+pub fn validate(settings: &NavigationSettings) -> Result<(), String> {
+    for entry in &settings.allowed_domains {
+        let domain = normalize_domain(entry);
+        let parsed = Url::parse(&format!("https://{}/", domain)).ok();
+        let is_host = !domain.is_empty()
+            && parsed
+                .as_ref()
+                .is_some_and(|url| url.host_str() == Some(domain.as_str()) && url.port().is_none());
+        if !is_host {
+            return Err(format!("Allowed domain \"{}\" is not a host name", entry));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn classify_default(s: &str) -> Destination {
+        classify(&url(s), &NavigationSettings::default())
+    }
+
+    #[test]
+    fn test_mistral_and_auth_pages_stay_in_the_window() {
+        for allowed in [
+            "https://chat.mistral.ai/chat/abc",
+            "https://auth.mistral.ai/ui/login",
+            "https://mistral.ai/",
+            "https://CHAT.Mistral.AI/chat",
+            "https://accounts.google.com/o/oauth2/auth?client_id=x",
+            "https://login.microsoftonline.com/common/oauth2/v2.0/authorize",
+            "tauri://localhost/index.html",
+            "http://tauri.localhost/index.html",
+            "about:blank",
+        ] {
+            assert_eq!(
+                classify_default(allowed),
+                Destination::Window,
+                "{}",
+                allowed
+            );
+        }
+    }
+
+    #[test]
+    fn test_other_links_open_in_the_browser() {
+        for external in [
+            "https://docs.python.org/3/",
+            "https://mistral.ai.example.com/",
+            "https://notmistral.ai/",
+            "http://chat.mistral.ai/chat",
+            "https://google.com/search?q=x",
+            "mailto:someone@example.com",
+        ] {
+            assert_eq!(
+                classify_default(external),
+                Destination::Browser,
+                "{}",
+                external
+            );
+        }
+        for blocked in [
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "data:text/html,<p>hi</p>",
+            "lechat://settings",
+        ] {
+            assert_eq!(
+                classify_default(blocked),
+                Destination::Blocked,
+                "{}",
+                blocked
+            );
+        }
+    }
+
+    #[test]
+    fn test_settings_extend_and_restrict() {
+        let settings = NavigationSettings {
+            allowed_domains: vec!["*.SSO.Corp.example.".to_string(), " ".to_string()],
+            open_external: false,
+        };
+        assert_eq!(
+            classify(&url("https://sso.corp.example/login"), &settings),
+            Destination::Window
+        );
+        assert_eq!(
+            classify(&url("https://idp.sso.corp.example/saml"), &settings),
+            Destination::Window
+        );
+        assert_eq!(
+            classify(&url("https://corp.example/"), &settings),
+            Destination::Blocked
+        );
+        assert_eq!(
+            classify(&url("mailto:someone@example.com"), &settings),
+            Destination::Blocked
+        );
+        assert_eq!(
+            classify(&url("https://chat.mistral.ai/chat"), &settings),
+            Destination::Window
+        );
+    }
+
+    #[test]
+    fn test_new_windows() {
+        let settings = NavigationSettings::default();
+        let new_window = |s: &str| classify_new_window(&url(s), &settings);
+        assert_eq!(
+            new_window("https://chat.mistral.ai/chat/abc"),
+            Destination::Window
+        );
+        assert_eq!(
+            new_window("https://github.com/mistralai"),
+            Destination::Browser
+        );
+        assert_eq!(new_window("about:blank"), Destination::Blocked);
+        assert_eq!(
+            new_window("blob:https://chat.mistral.ai/1234"),
+            Destination::Blocked
+        );
+        assert_eq!(
+            new_window("tauri://localhost/index.html"),
+            Destination::Blocked
+        );
+    }
+
+    #[test]
+    fn test_sign_in_popups() {
+        let settings = NavigationSettings {
+            allowed_domains: vec!["sso.corp.example".to_string()],
+            open_external: true,
+        };
+        let new_window = |s: &str| classify_new_window(&url(s), &settings);
+        for popup in [
+            "https://accounts.google.com/o/oauth2/v2/auth?client_id=x",
+            "https://login.microsoftonline.com/common/oauth2/v2.0/authorize",
+            "https://auth.mistral.ai/self-service/login/browser",
+            "https://acme.okta.com/app/mistral/sso/saml",
+            "https://sso.corp.example/login",
+        ] {
+            assert_eq!(new_window(popup), Destination::Popup, "{}", popup);
+        }
+        assert_eq!(
+            new_window("http://accounts.google.com/o/oauth2/auth"),
+            Destination::Browser
+        );
+        assert_eq!(
+            new_window("https://okta.com.example/"),
+            Destination::Browser
+        );
+
+        // Identity providers redirect through each other inside the popup
+        assert_eq!(
+            classify(&url("https://acme.okta.com/login/callback"), &settings),
+            Destination::Window
+        );
+        assert!(returns_to_chat(&url(
+            "https://chat.mistral.ai/api/auth/callback?code=x"
+        )));
+        assert!(!returns_to_chat(&url("https://auth.mistral.ai/ui/login")));
+        assert!(!returns_to_chat(&url("http://chat.mistral.ai/chat")));
+    }
+
+    #[test]
+    fn test_validate() {
+        let with = |domains: &[&str]| NavigationSettings {
+            allowed_domains: domains.iter().map(|d| d.to_string()).collect(),
+            open_external: true,
+        };
+        assert!(validate(&with(&[])).is_ok());
+        assert!(validate(&with(&[
+            "sso.corp.example",
+            "*.okta.com",
+            "Login.Example.COM"
+        ]))
+        .is_ok());
+        for bad in [
+            "",
+            "https://sso.corp.example",
+            "sso.corp.example/login",
+            "host:8443",
+            "a b",
+        ] {
+            let error = validate(&with(&[bad])).unwrap_err();
+            assert!(error.starts_with("Allowed domain"), "{}", error);
+        }
+    }
+}
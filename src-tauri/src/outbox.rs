@@ -0,0 +1,160 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+const STORE_FILE: &str = "outbox.json";
+const ITEMS_KEY: &str = "items";
+
+// Serializes read-modify-write cycles on the outbox store
+static OUTBOX_LOCK: Mutex<()> = Mutex::new(());
+
+// A launcher prompt that couldn't be sent because Le Chat was unreachable
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OutboxItem {
+    pub id: u64,
+    pub message: String,
+    pub new_chat: bool,
+    // Unix timestamp (seconds) of the failed attempt
+    pub created_at: u64,
+    // Why the last attempt failed
+    pub error: Option<String>,
+}
+
+// Decode stored items, dropping entries that can't be read rather than the whole outbox
+fn decode(raw: &Value) -> Vec<OutboxItem> {
+    let Some(items) = raw.as_array() else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| match serde_json::from_value(item.clone()) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                eprintln!("Dropping unreadable outbox entry: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn next_id(items: &[OutboxItem]) -> u64 {
+    items.iter().map(|item| item.id).max().unwrap_or(0) + 1
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn read(app: &AppHandle) -> Result<Vec<OutboxItem>, String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    Ok(store
+        .get(ITEMS_KEY)
+        .map(|raw| decode(&raw))
+        .unwrap_or_default())
+}
+
+fn write(app: &AppHandle, items: &[OutboxItem]) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.set(
+        ITEMS_KEY,
+        serde_json::to_value(items).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())
+}
+
+// Pending items, oldest first. Never fails; an unreadable outbox is empty.
+pub fn load(app: &AppHandle) -> Vec<OutboxItem> {
+    let _guard = OUTBOX_LOCK.lock().unwrap();
+    read(app).unwrap_or_else(|e| {
+        eprintln!("Failed to open outbox store: {}", e);
+        Vec::new()
+    })
+}
+
+pub fn add(
+    app: &AppHandle,
+    message: &str,
+    new_chat: bool,
+    error: Option<String>,
+) -> Result<OutboxItem, String> {
+    let _guard = OUTBOX_LOCK.lock().unwrap();
+    let mut items = read(app)?;
+    let item = OutboxItem {
+        id: next_id(&items),
+        message: message.to_string(),
+        new_chat,
+        created_at: now(),
+        error,
+    };
+    items.push(item.clone());
+    write(app, &items)?;
+    Ok(item)
+}
+
+// Remove an item, returning it so it can be resent
+pub fn take(app: &AppHandle, id: u64) -> Result<Option<OutboxItem>, String> {
+    let _guard = OUTBOX_LOCK.lock().unwrap();
+    let mut items = read(app)?;
+    let Some(index) = items.iter().position(|item| item.id == id) else {
+        return Ok(None);
+    };
+    let item = items.remove(index);
+    write(app, &items)?;
+    Ok(Some(item))
+}
+
+pub fn take_all(app: &AppHandle) -> Result<Vec<OutboxItem>, String> {
+    let _guard = OUTBOX_LOCK.lock().unwrap();
+    let items = read(app)?;
+    if !items.is_empty() {
+        write(app, &[])?;
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(id: u64) -> OutboxItem {
+        OutboxItem {
+            id,
+            message: format!("message {}", id),
+            new_chat: true,
+            created_at: 1_700_000_000,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_items_round_trip() {
+        let items = vec![item(1), item(2)];
+        let raw = serde_json::to_value(&items).unwrap();
+        assert_eq!(decode(&raw), items);
+    }
+
+    #[test]
+    fn test_unreadable_entries_are_dropped() {
+        let raw = json!([
+            serde_json::to_value(item(1)).unwrap(),
+            { "id": "two", "message": 2 },
+            serde_json::to_value(item(3)).unwrap(),
+        ]);
+        let ids: Vec<u64> = decode(&raw).iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert!(decode(&json!({ "not": "a list" })).is_empty());
+    }
+
+    #[test]
+    fn test_next_id_follows_highest() {
+        assert_eq!(next_id(&[]), 1);
+        assert_eq!(next_id(&[item(4), item(2)]), 5);
+    }
+}
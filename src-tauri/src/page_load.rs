@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+// How long message delivery and the offline page switch wait for a page to load
+pub const NAVIGATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Load progress of one navigation of the main webview
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Navigation {
+    // Increments with every navigation; 0 means nothing has started loading yet
+    pub id: u64,
+    pub url: String,
+    pub started: bool,
+    pub dom_ready: bool,
+    pub finished: bool,
+}
+
+impl Navigation {
+    // A finished load implies the DOM was ready, even if the page couldn't report it
+    // (e.g. an error page without IPC access).
+    pub fn is_ready(&self) -> bool {
+        self.dom_ready || self.finished
+    }
+}
+
+// Tracks the main webview's page-load lifecycle so commands can wait for a
+// specific navigation instead of sleeping for a fixed time.
+pub struct PageLoadTracker {
+    current: watch::Sender<Navigation>,
+}
+
+impl Default for PageLoadTracker {
+    fn default() -> Self {
+        Self {
+            current: watch::Sender::new(Navigation::default()),
+        }
+    }
+}
+
+impl PageLoadTracker {
+    pub fn current(&self) -> Navigation {
+        self.current.borrow().clone()
+    }
+
+    // A new navigation started loading. Returns its id.
+    pub fn started(&self, url: &str) -> u64 {
+        let mut id = 0;
+        self.current.send_modify(|nav| {
+            *nav = Navigation {
+                id: nav.id + 1,
+                url: url.to_string(),
+                started: true,
+                dom_ready: false,
+                finished: false,
+            };
+            id = nav.id;
+        });
+        id
+    }
+
+    // A document fired DOMContentLoaded. Reports are matched by URL so a late
+    // report from the previous document can't mark a new navigation as ready;
+    // after a redirect the navigation is marked ready by `finished` instead.
+    pub fn dom_ready(&self, url: &str) {
+        self.current.send_if_modified(|nav| {
+            if nav.url != url || nav.dom_ready {
+                return false;
+            }
+            nav.dom_ready = true;
+            true
+        });
+    }
+
+    // The current navigation finished loading (possibly after redirects)
+    pub fn finished(&self, url: &str) {
+        self.current.send_modify(|nav| {
+            nav.url = url.to_string();
+            nav.finished = true;
+        });
+    }
+
+    // Wait until a navigation with id >= `since` is ready.
+    // Pass `current().id + 1` before calling `navigate()` to wait for that navigation,
+    // or `current().id` to wait for whatever is loading now.
+    pub async fn wait_until_ready(
+        &self,
+        since: u64,
+        timeout: Duration,
+    ) -> Result<Navigation, String> {
+        let mut receiver = self.current.subscribe();
+        let wait = receiver.wait_for(|nav| nav.id >= since && nav.is_ready());
+        let result = tokio::time::timeout(timeout, wait)
+            .await
+            .map(|ready| ready.map(|nav| nav.clone()));
+        match result {
+            Ok(Ok(nav)) => Ok(nav),
+            Ok(Err(_)) => Err("Page load tracking stopped".to_string()),
+            Err(_) => {
+                let nav = self.current();
+                Err(format!(
+                    "Timed out after {}s waiting for the page to load (last: {} {})",
+                    timeout.as_secs(),
+                    nav.url,
+                    if nav.id >= since && nav.started {
+                        "still loading"
+                    } else {
+                        "navigation never started"
+                    }
+                ))
+            }
+        }
+    }
+}
+
+// Initialization script for the main webview: reports DOMContentLoaded of every
+// document so Rust knows when injected scripts can find the page's elements.
+pub fn get_dom_ready_js() -> String {
+    r#"
+    (function() {
+        function report() {
+            if (window.__TAURI__) {
+                window.__TAURI__.core.invoke('report_dom_ready', { url: window.location.href }).catch(() => {});
+            }
+        }
+        if (document.readyState === 'loading') {
+            document.addEventListener('DOMContentLoaded', report, { once: true });
+        } else {
+            report();
+        }
+    })();
+    "#
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAT_URL: &str = "https://chat.mistral.ai/chat";
+
+    #[test]
+    fn test_started_resets_state() {
+        let tracker = PageLoadTracker::default();
+        assert_eq!(tracker.started(CHAT_URL), 1);
+        tracker.finished(CHAT_URL);
+        assert!(tracker.current().is_ready());
+
+        assert_eq!(tracker.started("tauri://localhost/index.html"), 2);
+        let nav = tracker.current();
+        assert!(nav.started);
+        assert!(!nav.is_ready());
+        assert_eq!(nav.url, "tauri://localhost/index.html");
+    }
+
+    #[test]
+    fn test_finished_records_redirect_target() {
+        let tracker = PageLoadTracker::default();
+        tracker.started("https://chat.mistral.ai/");
+        tracker.finished(CHAT_URL);
+        assert_eq!(tracker.current().url, CHAT_URL);
+    }
+
+    #[test]
+    fn test_dom_ready_from_previous_document_is_ignored() {
+        let tracker = PageLoadTracker::default();
+        tracker.started("https://chat.mistral.ai/chat/abc");
+        tracker.started(CHAT_URL);
+        tracker.dom_ready("https://chat.mistral.ai/chat/abc");
+        assert!(!tracker.current().is_ready());
+        tracker.dom_ready(CHAT_URL);
+        assert!(tracker.current().dom_ready);
+    }
+
+    #[tokio::test]
+    async fn test_wait_ignores_previous_navigation() {
+        let tracker = std::sync::Arc::new(PageLoadTracker::default());
+        tracker.started(CHAT_URL);
+        tracker.finished(CHAT_URL);
+
+        let since = tracker.current().id + 1;
+        let waiter = {
+            let tracker = tracker.clone();
+            tokio::spawn(async move {
+                tracker
+                    .wait_until_ready(since, Duration::from_secs(5))
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+        tracker.started(CHAT_URL);
+        tracker.dom_ready(CHAT_URL);
+
+        let nav = waiter.await.unwrap().unwrap();
+        assert_eq!(nav.id, 2);
+        assert!(nav.dom_ready);
+    }
+
+    #[tokio::test]
+    async fn test_wait_returns_immediately_when_ready() {
+        let tracker = PageLoadTracker::default();
+        tracker.started(CHAT_URL);
+        tracker.finished(CHAT_URL);
+        let since = tracker.current().id;
+        let nav = tracker
+            .wait_until_ready(since, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(nav.id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out_with_clear_error() {
+        let tracker = PageLoadTracker::default();
+        tracker.started(CHAT_URL);
+        let err = tracker
+            .wait_until_ready(2, Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Timed out"));
+        assert!(err.contains("navigation never started"));
+    }
+
+    #[test]
+    fn test_dom_ready_js_is_valid() {
+        let js = get_dom_ready_js();
+        assert!(js.contains("report_dom_ready"));
+        assert!(js.contains("DOMContentLoaded"));
+    }
+}
@@ -0,0 +1,767 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+// `le-chat ask`: a second process hands a prompt (plus anything piped to it) to
+// the running app and prints the answer as Markdown.
+//
+// The single-instance plugin only carries arguments one way and then exits the
+// second process, so the caller listens on a loopback port and spawns a helper
+// `le-chat ask-connect <addr> <token>` that is forwarded like any other verb.
+// The app connects back, proves it got the token, and then answers requests
+// over that connection as JSON lines: prompts, whose answers are streamed back,
+// snapshots of the last reply or the open conversation (for `le-chat mcp`), and
+// prompts to put in the launcher (for the browser's native-messaging host).
+
+// How long the app gets to connect back, including starting up if it wasn't running
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// How long either side waits for the next line during the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+// Matches the response watcher's own limit
+pub const DEFAULT_ANSWER_TIMEOUT: Duration = Duration::from_secs(300);
+// How long reading the conversation from the page may take
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+// Longest the app relays a single answer
+const SERVE_LIMIT: Duration = Duration::from_secs(600);
+
+// Exit codes of `le-chat ask`
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_TIMEOUT: i32 = 124;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AskOptions {
+    // Instruction given on the command line; piped input is appended to it
+    pub prompt: String,
+    // Print the answer instead of returning once the prompt is queued
+    pub wait: bool,
+    pub new_chat: bool,
+    pub timeout: Duration,
+}
+
+// JSON lines exchanged between `le-chat ask` and the app
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AskMessage {
+    // App → caller, first line: proves the connection comes from the app
+    Hello {
+        token: String,
+    },
+    // Caller → app
+    Request {
+        prompt: String,
+        new_chat: bool,
+        wait: bool,
+        // Bring up the main window on the prompt instead of answering in the background
+        #[serde(default)]
+        show: bool,
+    },
+    // App → caller: the prompt is queued
+    Accepted {
+        submission_id: u64,
+    },
+    // App → caller: the whole answer so far
+    Progress {
+        markdown: String,
+    },
+    Done {
+        markdown: String,
+    },
+    Error {
+        message: String,
+    },
+    // Caller → app: the last captured reply
+    GetLastResponse,
+    // Caller → app: the conversation open in the main window
+    GetConversation,
+    // App → caller
+    Snapshot(Snapshot),
+    // Caller → app: put a prompt in the launcher for the user to check and send
+    Prefill {
+        prompt: String,
+        new_chat: Option<bool>,
+    },
+    // App → caller: the launcher shows the prompt
+    Prefilled,
+}
+
+// A reply or conversation as Markdown, with where it came from
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub markdown: String,
+    pub url: String,
+    pub title: String,
+}
+
+impl AskMessage {
+    fn is_final(&self) -> bool {
+        matches!(
+            self,
+            AskMessage::Done { .. } | AskMessage::Error { .. } | AskMessage::Snapshot(_)
+        )
+    }
+}
+
+// Combine the command-line instruction with piped input
+pub fn build_prompt(instruction: &str, piped: Option<&str>) -> Result<String, String> {
+    let instruction = instruction.trim();
+    let piped = piped.map(str::trim_end).filter(|p| !p.trim().is_empty());
+    match (instruction.is_empty(), piped) {
+        (true, None) => Err("nothing to ask: give a prompt or pipe some input".to_string()),
+        (true, Some(piped)) => Ok(piped.to_string()),
+        (false, None) => Ok(instruction.to_string()),
+        (false, Some(piped)) => Ok(format!("{}\n\n{}", instruction, piped)),
+    }
+}
+
+// An unguessable token tying the app's connection to this caller
+pub fn new_token() -> String {
+    let part = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(std::process::id());
+        hasher.write_u128(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default(),
+        );
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", part(), part())
+}
+
+// The app only ever connects back to this machine
+pub fn parse_reply_address(raw: &str) -> Result<SocketAddr, String> {
+    let addr: SocketAddr = raw
+        .parse()
+        .map_err(|_| format!("invalid reply address '{}'", raw))?;
+    if !addr.ip().is_loopback() {
+        return Err(format!("reply address '{}' is not a loopback address", raw));
+    }
+    Ok(addr)
+}
+
+// What to print after `printed`, the output so far, given the answer so far.
+// While streaming only whole lines are printed, since the end of a partial
+// answer can still change, and nothing is printed while the answer differs
+// from or is shorter than what was printed. If an earlier line changed after
+// all, the final answer is printed again from that line.
+pub fn next_output(printed: &str, markdown: &str, done: bool) -> Option<String> {
+    if let Some(rest) = markdown.strip_prefix(printed) {
+        let rest = if done {
+            rest
+        } else {
+            &rest[..rest.rfind('\n').map(|i| i + 1).unwrap_or(0)]
+        };
+        return (!rest.is_empty()).then(|| rest.to_string());
+    }
+    if !done || printed.starts_with(markdown) {
+        return None;
+    }
+    let common = printed
+        .char_indices()
+        .zip(markdown.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or(printed.len().min(markdown.len()));
+    let line = markdown[..common].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    Some(markdown[line..].to_string())
+}
+
+fn send(stream: &mut TcpStream, message: &AskMessage) -> Result<(), String> {
+    let mut line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).map_err(|e| e.to_string())
+}
+
+fn receive(reader: &mut impl BufRead) -> Result<AskMessage, std::io::Error> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    serde_json::from_str(&line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+// Wait for the app to connect and present `token`; other connections are dropped
+fn accept_app(listener: &TcpListener, token: &str) -> Result<TcpStream, String> {
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    while Instant::now() < deadline {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let _ = stream.set_nonblocking(false);
+                let _ = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+                match receive(&mut BufReader::new(reader)) {
+                    Ok(AskMessage::Hello { token: given }) if given == token => return Ok(stream),
+                    _ => continue,
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Err("Le Chat didn't pick up the prompt".to_string())
+}
+
+// `le-chat ask`, run in the caller's process. Returns the exit code.
+pub fn run_client(options: AskOptions) -> i32 {
+    let stdin = std::io::stdin();
+    let piped = if stdin.is_terminal() {
+        None
+    } else {
+        let mut input = String::new();
+        if let Err(e) = stdin.lock().read_to_string(&mut input) {
+            eprintln!("le-chat: failed to read stdin: {}", e);
+            return EXIT_FAILED;
+        }
+        Some(input)
+    };
+    let prompt = match build_prompt(&options.prompt, piped.as_deref()) {
+        Ok(prompt) => prompt,
+        Err(e) => {
+            eprintln!("le-chat: {}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    match ask(&options, prompt) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("le-chat: {}", e);
+            match e {
+                SessionError::Timeout => EXIT_TIMEOUT,
+                SessionError::Failed(_) => EXIT_FAILED,
+            }
+        }
+    }
+}
+
+fn ask(options: &AskOptions, prompt: String) -> Result<(), SessionError> {
+    let mut session = AppSession::open()?;
+    if !options.wait {
+        return session
+            .submit(&prompt, options.new_chat, true, options.timeout)
+            .map(|_| ());
+    }
+
+    let mut stdout = std::io::stdout();
+    let mut printed = String::new();
+    let mut print = |markdown: &str, done: bool| {
+        if let Some(output) = next_output(&printed, markdown, done) {
+            let _ = stdout.write_all(output.as_bytes());
+            let _ = stdout.flush();
+            printed.push_str(&output);
+        }
+    };
+    let answer = session.ask(&prompt, options.new_chat, options.timeout, |partial| {
+        print(partial, false)
+    })?;
+    print(&answer, true);
+    if !printed.ends_with('\n') {
+        let _ = stdout.write_all(b"\n");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    Timeout,
+    Failed(String),
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Timeout => write!(f, "timed out waiting for Le Chat"),
+            SessionError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for SessionError {
+    fn from(message: String) -> Self {
+        SessionError::Failed(message)
+    }
+}
+
+// The caller's end of a connection to the app. Carries one request at a time,
+// as many as the caller needs; after an error it should be dropped.
+pub struct AppSession {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl AppSession {
+    // Forward `ask-connect` to the running app, or start it, and wait for it
+    // to connect back
+    pub fn open() -> Result<Self, SessionError> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let token = new_token();
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        Command::new(exe)
+            .args(["ask-connect", &addr.to_string(), &token])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("failed to reach Le Chat: {}", e))?;
+        Self::accept(&listener, &token)
+    }
+
+    fn accept(listener: &TcpListener, token: &str) -> Result<Self, SessionError> {
+        let stream = accept_app(listener, token)?;
+        let reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+        Ok(Self { stream, reader })
+    }
+
+    pub fn send(&mut self, message: &AskMessage) -> Result<(), SessionError> {
+        Ok(send(&mut self.stream, message)?)
+    }
+
+    // The next message from the app, if it comes before `deadline`
+    pub fn receive_until(&mut self, deadline: Instant) -> Result<AskMessage, SessionError> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(SessionError::Timeout);
+        }
+        let _ = self.stream.set_read_timeout(Some(remaining));
+        receive(&mut self.reader).map_err(|e| match e.kind() {
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => SessionError::Timeout,
+            _ => SessionError::Failed(format!("lost connection to Le Chat: {}", e)),
+        })
+    }
+
+    // Send a prompt and wait for the whole answer as Markdown. `on_progress`
+    // gets the answer so far each time it grows.
+    pub fn ask(
+        &mut self,
+        prompt: &str,
+        new_chat: bool,
+        timeout: Duration,
+        mut on_progress: impl FnMut(&str),
+    ) -> Result<String, SessionError> {
+        self.send(&AskMessage::Request {
+            prompt: prompt.to_string(),
+            new_chat,
+            wait: true,
+            show: false,
+        })?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.receive_until(deadline)? {
+                AskMessage::Progress { markdown } => on_progress(&markdown),
+                AskMessage::Done { markdown } => return Ok(markdown),
+                AskMessage::Error { message } => return Err(SessionError::Failed(message)),
+                _ => {}
+            }
+        }
+    }
+
+    // Queue a prompt without waiting for the answer. Returns the submission id.
+    pub fn submit(
+        &mut self,
+        prompt: &str,
+        new_chat: bool,
+        show: bool,
+        timeout: Duration,
+    ) -> Result<u64, SessionError> {
+        self.send(&AskMessage::Request {
+            prompt: prompt.to_string(),
+            new_chat,
+            wait: false,
+            show,
+        })?;
+        match self.receive_until(Instant::now() + timeout)? {
+            AskMessage::Accepted { submission_id } => Ok(submission_id),
+            AskMessage::Error { message } => Err(SessionError::Failed(message)),
+            _ => Err(SessionError::Failed("unexpected reply".to_string())),
+        }
+    }
+
+    // Show a prompt in the launcher, where it waits for the user to send it
+    pub fn prefill(&mut self, prompt: &str, new_chat: Option<bool>) -> Result<(), SessionError> {
+        self.send(&AskMessage::Prefill {
+            prompt: prompt.to_string(),
+            new_chat,
+        })?;
+        match self.receive_until(Instant::now() + HANDSHAKE_TIMEOUT)? {
+            AskMessage::Prefilled => Ok(()),
+            AskMessage::Error { message } => Err(SessionError::Failed(message)),
+            _ => Err(SessionError::Failed("unexpected reply".to_string())),
+        }
+    }
+
+    // The last captured reply, or the conversation open in the main window
+    pub fn snapshot(&mut self, request: AskMessage) -> Result<Snapshot, SessionError> {
+        self.send(&request)?;
+        match self.receive_until(Instant::now() + SNAPSHOT_TIMEOUT)? {
+            AskMessage::Snapshot(snapshot) => Ok(snapshot),
+            AskMessage::Error { message } => Err(SessionError::Failed(message)),
+            _ => Err(SessionError::Failed("unexpected reply".to_string())),
+        }
+    }
+}
+
+// Callers waiting on a submission's answer, keyed by submission id
+#[derive(Default)]
+pub struct AskSubscribers(Mutex<HashMap<u64, mpsc::Sender<AskMessage>>>);
+
+impl AskSubscribers {
+    pub fn subscribe(&self, submission_id: u64) -> mpsc::Receiver<AskMessage> {
+        let (sender, receiver) = mpsc::channel();
+        self.0.lock().unwrap().insert(submission_id, sender);
+        receiver
+    }
+
+    // Pass a message on to whoever waits for this submission, if anyone
+    pub fn notify(&self, submission_id: u64, message: AskMessage) {
+        let mut subscribers = self.0.lock().unwrap();
+        let Some(sender) = subscribers.get(&submission_id) else {
+            return;
+        };
+        let is_final = message.is_final();
+        if sender.send(message).is_err() || is_final {
+            subscribers.remove(&submission_id);
+        }
+    }
+}
+
+// What the app does for `ask-connect` requests
+pub trait AskHandler {
+    // Queue a prompt and subscribe to its answer
+    fn submit(
+        &self,
+        prompt: String,
+        new_chat: bool,
+        show: bool,
+    ) -> Result<(u64, mpsc::Receiver<AskMessage>), String>;
+    fn last_response(&self) -> Result<Snapshot, String>;
+    fn conversation(&self) -> Result<Snapshot, String>;
+    fn prefill(&self, prompt: String, new_chat: Option<bool>) -> Result<(), String>;
+}
+
+fn snapshot_reply(snapshot: Result<Snapshot, String>) -> AskMessage {
+    match snapshot {
+        Ok(snapshot) => AskMessage::Snapshot(snapshot),
+        Err(message) => AskMessage::Error { message },
+    }
+}
+
+fn relay_answer(
+    stream: &mut TcpStream,
+    handler: &impl AskHandler,
+    prompt: String,
+    new_chat: bool,
+    wait: bool,
+    show: bool,
+) -> Result<(), String> {
+    let (submission_id, answers) = match handler.submit(prompt, new_chat, show) {
+        Ok(submitted) => submitted,
+        Err(message) => return send(stream, &AskMessage::Error { message }),
+    };
+    send(stream, &AskMessage::Accepted { submission_id })?;
+    if !wait {
+        return Ok(());
+    }
+
+    let deadline = Instant::now() + SERVE_LIMIT;
+    while let Ok(message) = answers.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+    {
+        let is_final = message.is_final();
+        send(stream, &message)?;
+        if is_final {
+            return Ok(());
+        }
+    }
+    send(
+        stream,
+        &AskMessage::Error {
+            message: "Le Chat didn't finish answering".to_string(),
+        },
+    )
+}
+
+// App side of `ask-connect`: connect back to the caller and answer its
+// requests until it hangs up. Blocks, so it runs on its own thread.
+pub fn serve(addr: SocketAddr, token: &str, handler: &impl AskHandler) -> Result<(), String> {
+    let mut stream =
+        TcpStream::connect_timeout(&addr, HANDSHAKE_TIMEOUT).map_err(|e| e.to_string())?;
+    send(
+        &mut stream,
+        &AskMessage::Hello {
+            token: token.to_string(),
+        },
+    )?;
+    // The first request follows right away; later ones come whenever the caller needs them
+    stream
+        .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    loop {
+        let request = match receive(&mut reader) {
+            Ok(request) => request,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+        stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+        match request {
+            AskMessage::Request {
+                prompt,
+                new_chat,
+                wait,
+                show,
+            } => relay_answer(&mut stream, handler, prompt, new_chat, wait, show)?,
+            AskMessage::GetLastResponse => {
+                send(&mut stream, &snapshot_reply(handler.last_response()))?
+            }
+            AskMessage::GetConversation => {
+                send(&mut stream, &snapshot_reply(handler.conversation()))?
+            }
+            AskMessage::Prefill { prompt, new_chat } => {
+                let reply = match handler.prefill(prompt, new_chat) {
+                    Ok(()) => AskMessage::Prefilled,
+                    Err(message) => AskMessage::Error { message },
+                };
+                send(&mut stream, &reply)?
+            }
+            _ => send(
+                &mut stream,
+                &AskMessage::Error {
+                    message: "unexpected request".to_string(),
+                },
+            )?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(
+            build_prompt("review this", Some("diff --git a b\n+x\n\n")).unwrap(),
+            "review this\n\ndiff --git a b\n+x"
+        );
+        assert_eq!(build_prompt("  hi ", None).unwrap(), "hi");
+        assert_eq!(build_prompt("", Some("just input")).unwrap(), "just input");
+        assert!(build_prompt(" ", Some("\n")).is_err());
+    }
+
+    #[test]
+    fn test_reply_address_must_be_loopback() {
+        assert!(parse_reply_address("127.0.0.1:4567").is_ok());
+        assert!(parse_reply_address("[::1]:4567").is_ok());
+        assert!(parse_reply_address("10.0.0.2:4567").is_err());
+        assert!(parse_reply_address("localhost").is_err());
+    }
+
+    #[test]
+    fn test_tokens_differ() {
+        let token = new_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, new_token());
+    }
+
+    #[test]
+    fn test_next_output_prints_whole_lines_while_streaming() {
+        assert_eq!(next_output("", "Hello **wor", false), None);
+        assert_eq!(
+            next_output("", "Line one\nLine **t", false).as_deref(),
+            Some("Line one\n")
+        );
+        assert_eq!(
+            next_output("Line one\n", "Line one\nLine **two**", true).as_deref(),
+            Some("Line **two**")
+        );
+        // A rewritten earlier part is only reconciled at the end
+        assert_eq!(next_output("a - b\n", "a \\- b\nc\n", false), None);
+        assert_eq!(
+            next_output("a - b\n", "a \\- b\nc", true).as_deref(),
+            Some("a \\- b\nc")
+        );
+    }
+
+    #[test]
+    fn test_next_output_keeps_partial_lines_until_confirmed() {
+        let mut printed = String::new();
+        for (markdown, done) in [("a\nb", false), ("a\nbc\nd", false), ("a\nbc\nde", true)] {
+            if let Some(output) = next_output(&printed, markdown, done) {
+                printed.push_str(&output);
+            }
+        }
+        assert_eq!(printed, "a\nbc\nde");
+    }
+
+    #[test]
+    fn test_next_output_ignores_a_shorter_answer() {
+        assert_eq!(
+            next_output("Line one\nLine two\n", "Line one\n", false),
+            None
+        );
+        assert_eq!(
+            next_output("Line one\nLine two\n", "Line one\nLine two", true),
+            None
+        );
+        assert_eq!(next_output("Line one\n", "Line one", true), None);
+    }
+
+    #[test]
+    fn test_messages_are_tagged_json_lines() {
+        let line = serde_json::to_string(&AskMessage::Progress {
+            markdown: "x".to_string(),
+        })
+        .unwrap();
+        assert_eq!(line, r#"{"type":"progress","markdown":"x"}"#);
+        // Requests from older callers don't carry `show`
+        let parsed: AskMessage =
+            serde_json::from_str(r#"{"type":"request","prompt":"p","new_chat":true,"wait":false}"#)
+                .unwrap();
+        assert!(matches!(parsed, AskMessage::Request { show: false, .. }));
+        let parsed: AskMessage =
+            serde_json::from_str(r#"{"type":"error","message":"Failed"}"#).unwrap();
+        assert!(parsed.is_final());
+    }
+
+    #[test]
+    fn test_subscribers_stop_after_final_message() {
+        let subscribers = AskSubscribers::default();
+        let answers = subscribers.subscribe(4);
+        subscribers.notify(
+            3,
+            AskMessage::Done {
+                markdown: String::new(),
+            },
+        );
+        subscribers.notify(
+            4,
+            AskMessage::Progress {
+                markdown: "a".to_string(),
+            },
+        );
+        subscribers.notify(
+            4,
+            AskMessage::Done {
+                markdown: "ab".to_string(),
+            },
+        );
+        subscribers.notify(
+            4,
+            AskMessage::Error {
+                message: "late".to_string(),
+            },
+        );
+        let received: Vec<AskMessage> = answers.try_iter().collect();
+        assert_eq!(received.len(), 2);
+        assert!(received[1].is_final());
+    }
+
+    struct FakeApp;
+
+    impl AskHandler for FakeApp {
+        fn submit(
+            &self,
+            prompt: String,
+            new_chat: bool,
+            _show: bool,
+        ) -> Result<(u64, mpsc::Receiver<AskMessage>), String> {
+            assert_eq!(prompt, "hello");
+            assert!(new_chat);
+            let (sender, receiver) = mpsc::channel();
+            sender
+                .send(AskMessage::Progress {
+                    markdown: "Hi".to_string(),
+                })
+                .unwrap();
+            sender
+                .send(AskMessage::Done {
+                    markdown: "Hi there".to_string(),
+                })
+                .unwrap();
+            Ok((9, receiver))
+        }
+
+        fn last_response(&self) -> Result<Snapshot, String> {
+            Ok(Snapshot {
+                markdown: "Hi there".to_string(),
+                url: "https://chat.mistral.ai/chat/1".to_string(),
+                title: "Greetings".to_string(),
+            })
+        }
+
+        fn conversation(&self) -> Result<Snapshot, String> {
+            Err("No conversation is open".to_string())
+        }
+
+        fn prefill(&self, prompt: String, _new_chat: Option<bool>) -> Result<(), String> {
+            if prompt.is_empty() {
+                return Err("Message is empty".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_session_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = std::thread::spawn(move || serve(addr, "secret", &FakeApp));
+
+        let mut session = AppSession::accept(&listener, "secret").unwrap();
+        let mut progress = Vec::new();
+        let answer = session
+            .ask("hello", true, Duration::from_secs(5), |partial| {
+                progress.push(partial.to_string())
+            })
+            .unwrap();
+        assert_eq!(answer, "Hi there");
+        assert_eq!(progress, vec!["Hi"]);
+
+        // The same connection serves further requests
+        let last = session.snapshot(AskMessage::GetLastResponse).unwrap();
+        assert_eq!(last.title, "Greetings");
+        assert_eq!(
+            session.snapshot(AskMessage::GetConversation),
+            Err(SessionError::Failed("No conversation is open".to_string()))
+        );
+        assert_eq!(
+            session.submit("hello", true, true, Duration::from_secs(5)),
+            Ok(9)
+        );
+        assert_eq!(session.prefill("hello", Some(false)), Ok(()));
+        assert_eq!(
+            session.prefill("", None),
+            Err(SessionError::Failed("Message is empty".to_string()))
+        );
+
+        drop(session);
+        app.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_connections_without_the_token_are_ignored() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let impostor = std::thread::spawn(move || serve(addr, "guess", &FakeApp));
+        let app = std::thread::spawn(move || {
+            let _ = impostor.join().unwrap();
+            serve(addr, "secret", &FakeApp)
+        });
+        let mut session = AppSession::accept(&listener, "secret").unwrap();
+        assert!(session.snapshot(AskMessage::GetLastResponse).is_ok());
+        drop(session);
+        app.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::navigation::NavigationSettings;
+use crate::settings::AppSettings;
+
+// The profile the app starts out with. Its webview data is where it has always
+// been and its settings are the top-level ones.
+pub const DEFAULT_PROFILE: &str = "default";
+
+// A named account with its own webview data (cookies, local storage, caches)
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    // Directory name under `profiles/`, e.g. "work"
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub settings: ProfileSettings,
+}
+
+// Settings each profile keeps for itself; everything else is shared
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    pub new_chat_default: bool,
+    pub inline_answers: bool,
+    pub notifications_enabled: bool,
+    pub navigation: NavigationSettings,
+}
+
+impl Default for ProfileSettings {
+    fn default() -> Self {
+        Self::of(&AppSettings::default())
+    }
+}
+
+impl ProfileSettings {
+    pub fn of(settings: &AppSettings) -> Self {
+        Self {
+            new_chat_default: settings.new_chat_default,
+            inline_answers: settings.inline_answers,
+            notifications_enabled: settings.notifications_enabled,
+            navigation: settings.navigation.clone(),
+        }
+    }
+
+    pub fn apply(&self, settings: &mut AppSettings) {
+        settings.new_chat_default = self.new_chat_default;
+        settings.inline_answers = self.inline_answers;
+        settings.notifications_enabled = self.notifications_enabled;
+        settings.navigation = self.navigation.clone();
+    }
+}
+
+// Settings as the open profile sees them
+pub fn effective(mut settings: AppSettings) -> AppSettings {
+    let own = settings
+        .profiles
+        .iter()
+        .find(|profile| profile.id == settings.active_profile)
+        .map(|profile| profile.settings.clone());
+    if let Some(own) = own {
+        own.apply(&mut settings);
+    }
+    settings
+}
+
+// Undo `effective` before storing edited settings: the per-profile fields go
+// back to the profile that was open when they were loaded, and the default
+// profile keeps its stored values. Saving settings never switches profiles.
+pub fn fold(stored: &AppSettings, mut edited: AppSettings) -> AppSettings {
+    edited.active_profile = stored.active_profile.clone();
+    let own = ProfileSettings::of(&edited);
+    if let Some(profile) = edited
+        .profiles
+        .iter_mut()
+        .find(|profile| profile.id == stored.active_profile)
+    {
+        profile.settings = own;
+        ProfileSettings::of(stored).apply(&mut edited);
+    }
+    edited
+}
+
+pub fn exists(profiles: &[Profile], id: &str) -> bool {
+    id == DEFAULT_PROFILE || profiles.iter().any(|profile| profile.id == id)
+}
+
+pub fn display_name<'a>(profiles: &'a [Profile], id: &str) -> &'a str {
+    profiles
+        .iter()
+        .find(|profile| profile.id == id)
+        .map_or("Default", |profile| profile.name.as_str())
+}
+
+// Webview data directory of a profile, under the app's local data directory.
+// The default profile uses the webview's own default location.
+pub fn data_directory(base: &Path, id: &str) -> Option<PathBuf> {
+    (id != DEFAULT_PROFILE).then(|| base.join("profiles").join(id))
+}
+
+// Stable identifier of a profile's data store, for WebKit on macOS which has no
+// data directories. FNV-1a over the id, with two offsets for 128 bits.
+pub fn data_store_identifier(id: &str) -> [u8; 16] {
+    let fnv = |offset: u64| {
+        id.bytes().fold(offset, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    };
+    let mut identifier = [0; 16];
+    identifier[..8].copy_from_slice(&fnv(0xcbf2_9ce4_8422_2325).to_be_bytes());
+    identifier[8..].copy_from_slice(&fnv(0x6c62_272e_07bb_0142).to_be_bytes());
+    identifier
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+// Check the profile list before saving it. `active` is the open profile,
+// which can't be removed.
+pub fn validate(profiles: &[Profile], active: &str) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+    for (index, profile) in profiles.iter().enumerate() {
+        let label = format!("Profile {} (\"{}\")", index + 1, profile.name);
+        if profile.name.trim().is_empty() {
+            return Err(format!("Profile {}: name is empty", index + 1));
+        }
+        if !is_valid_id(&profile.id) || profile.id == DEFAULT_PROFILE {
+            return Err(format!(
+                "{}: id must be lowercase letters, digits and dashes, and not \"{}\"",
+                label, DEFAULT_PROFILE
+            ));
+        }
+        if !ids.insert(profile.id.as_str()) {
+            return Err(format!("{}: id \"{}\" is used twice", label, profile.id));
+        }
+        if !names.insert(profile.name.trim().to_lowercase()) {
+            return Err(format!("{}: another profile has this name", label));
+        }
+    }
+    if !exists(profiles, active) {
+        return Err(format!(
+            "Profile \"{}\" is open; switch to another profile before removing it",
+            active
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(id: &str, name: &str) -> Profile {
+        Profile {
+            id: id.to_string(),
+            name: name.to_string(),
+            settings: ProfileSettings::default(),
+        }
+    }
+
+    fn with_work_profile() -> AppSettings {
+        let mut work = profile("work", "Work");
+        work.settings.new_chat_default = false;
+        work.settings.navigation.allowed_domains = vec!["sso.corp.example".to_string()];
+        AppSettings {
+            profiles: vec![work],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_effective_and_fold_round_trip() {
+        let mut stored = with_work_profile();
+        assert!(effective(stored.clone()).new_chat_default);
+
+        stored.active_profile = "work".to_string();
+        let mut edited = effective(stored.clone());
+        assert!(!edited.new_chat_default);
+        assert_eq!(edited.navigation.allowed_domains, ["sso.corp.example"]);
+
+        // Edits to the open profile's fields stay with it; shared fields are shared
+        edited.inline_answers = true;
+        edited.control_socket = true;
+        let saved = fold(&stored, edited);
+        assert!(!saved.inline_answers, "default profile keeps its own value");
+        assert!(saved.new_chat_default);
+        assert!(saved.navigation.allowed_domains.is_empty());
+        assert!(saved.control_socket);
+        assert!(saved.profiles[0].settings.inline_answers);
+        assert!(!saved.profiles[0].settings.new_chat_default);
+
+        // Loading and saving unchanged settings changes nothing
+        let reloaded = effective(saved.clone());
+        assert_eq!(
+            ProfileSettings::of(&effective(fold(&saved, reloaded.clone()))),
+            ProfileSettings::of(&reloaded)
+        );
+        assert_eq!(fold(&saved, reloaded).profiles, saved.profiles);
+    }
+
+    #[test]
+    fn test_saving_never_switches_profiles() {
+        let stored = with_work_profile();
+        let mut edited = effective(stored.clone());
+        edited.active_profile = "work".to_string();
+        edited.new_chat_default = false;
+        let saved = fold(&stored, edited);
+        assert_eq!(saved.active_profile, DEFAULT_PROFILE);
+        assert!(!saved.new_chat_default);
+        assert!(
+            !saved.profiles[0].settings.new_chat_default,
+            "the work profile's own value is untouched"
+        );
+        assert_eq!(
+            saved.profiles[0].settings.navigation.allowed_domains.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_data_locations() {
+        let base = Path::new("/data/ai.mistral.lechat");
+        assert_eq!(data_directory(base, DEFAULT_PROFILE), None);
+        assert_eq!(
+            data_directory(base, "work"),
+            Some(base.join("profiles").join("work"))
+        );
+        assert_eq!(data_store_identifier("work"), data_store_identifier("work"));
+        assert_ne!(data_store_identifier("work"), data_store_identifier("home"));
+        assert_ne!(
+            data_store_identifier("work")[..8],
+            data_store_identifier("work")[8..]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let profiles = vec![profile("work", "Work"), profile("home", "Home")];
+        assert!(validate(&profiles, DEFAULT_PROFILE).is_ok());
+        assert!(validate(&profiles, "home").is_ok());
+        assert_eq!(display_name(&profiles, "home"), "Home");
+        assert_eq!(display_name(&profiles, DEFAULT_PROFILE), "Default");
+
+        let error = validate(&profiles[..1], "home").unwrap_err();
+        assert!(error.contains("switch to another profile"), "{}", error);
+        for bad in [
+            vec![profile("work", " ")],
+            vec![profile("Work", "Work")],
+            vec![profile("default", "Default")],
+            vec![profile("../x", "Escape")],
+            vec![profile("work", "Work"), profile("work", "Job")],
+            vec![profile("work", "Work"), profile("job", "work")],
+        ] {
+            let error = validate(&bad, DEFAULT_PROFILE).unwrap_err();
+            assert!(error.starts_with("Profile"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{html, markdown};
+
+// Length of the plain-text excerpt used for notifications
+const EXCERPT_CHARS: usize = 140;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// The assistant's reply, captured from the main window when a response completes
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CapturedResponse {
+    // Submission that prompted the reply, if it came from the launcher
+    pub submission_id: Option<u64>,
+    pub html: String,
+    pub markdown: String,
+    // Conversation the reply belongs to
+    pub url: String,
+    pub title: String,
+    // Unix timestamp (seconds)
+    pub captured_at: u64,
+}
+
+impl CapturedResponse {
+    // Sanitize the reply's HTML and convert it to Markdown
+    pub fn capture(submission_id: Option<u64>, raw_html: &str, url: &str, title: &str) -> Self {
+        let nodes = html::sanitize(html::parse(raw_html));
+        Self {
+            submission_id,
+            html: html::serialize(&nodes),
+            markdown: markdown::from_html(&nodes),
+            url: url.to_string(),
+            title: title.to_string(),
+            captured_at: now(),
+        }
+    }
+
+    // A reply from the chat completions API, which answers in Markdown
+    pub fn from_markdown(submission_id: Option<u64>, markdown: &str, title: &str) -> Self {
+        let nodes = html::sanitize(html::parse(&markdown::to_html(markdown)));
+        Self {
+            submission_id,
+            html: html::serialize(&nodes),
+            markdown: markdown.to_string(),
+            url: String::new(),
+            title: title.to_string(),
+            captured_at: now(),
+        }
+    }
+
+    // First line(s) of the reply as plain text, for notifications
+    pub fn excerpt(&self) -> String {
+        let text = html::parse(&self.html)
+            .iter()
+            .map(html::Node::text)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut excerpt = words.join(" ");
+        if excerpt.chars().count() > EXCERPT_CHARS {
+            excerpt = excerpt.chars().take(EXCERPT_CHARS).collect();
+            excerpt.push('…');
+        }
+        excerpt
+    }
+}
+
+// A reply shown in the launcher, sent as 'inline-answer' events while it streams in
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InlineAnswer {
+    pub submission_id: u64,
+    pub html: String,
+    pub markdown: String,
+    pub url: String,
+    // Le Chat has finished the response
+    pub done: bool,
+}
+
+impl InlineAnswer {
+    // The reply so far, from a 'response-progress' event
+    pub fn partial(submission_id: u64, raw_html: &str, url: &str) -> Self {
+        let nodes = html::sanitize(html::parse(raw_html));
+        Self {
+            submission_id,
+            html: html::serialize(&nodes),
+            markdown: markdown::from_html(&nodes),
+            url: url.to_string(),
+            done: false,
+        }
+    }
+
+    // An API reply so far, in Markdown
+    pub fn from_markdown(submission_id: u64, markdown: &str) -> Self {
+        let nodes = html::sanitize(html::parse(&markdown::to_html(markdown)));
+        Self {
+            submission_id,
+            html: html::serialize(&nodes),
+            markdown: markdown.to_string(),
+            url: String::new(),
+            done: false,
+        }
+    }
+
+    pub fn complete(submission_id: u64, response: &CapturedResponse) -> Self {
+        Self {
+            submission_id,
+            html: response.html.clone(),
+            markdown: response.markdown.clone(),
+            url: response.url.clone(),
+            done: true,
+        }
+    }
+}
+
+// The most recent reply seen in the main window
+#[derive(Default)]
+pub struct LastResponse(Mutex<Option<CapturedResponse>>);
+
+impl LastResponse {
+    pub fn get(&self) -> Option<CapturedResponse> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, response: CapturedResponse) {
+        *self.0.lock().unwrap() = Some(response);
+    }
+}
+
+// One message of a conversation read from the main window
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SnapshotMessage {
+    // "user" or "assistant"
+    pub role: String,
+    pub html: String,
+}
+
+// Payload of the `conversation-snapshot` event: the conversation on the page
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ConversationSnapshot {
+    pub id: u64,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub messages: Vec<SnapshotMessage>,
+}
+
+impl ConversationSnapshot {
+    // The conversation as Markdown, one section per message
+    pub fn to_markdown(&self) -> String {
+        self.messages
+            .iter()
+            .map(|message| {
+                let author = if message.role == "user" {
+                    "You"
+                } else {
+                    "Le Chat"
+                };
+                let nodes = html::sanitize(html::parse(&message.html));
+                format!("## {}\n\n{}", author, markdown::from_html(&nodes))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+// Conversation snapshots asked of the main window, keyed by request id
+#[derive(Default)]
+pub struct SnapshotRequests(Mutex<(u64, HashMap<u64, mpsc::Sender<ConversationSnapshot>>)>);
+
+impl SnapshotRequests {
+    pub fn expect(&self) -> (u64, mpsc::Receiver<ConversationSnapshot>) {
+        let mut inner = self.0.lock().unwrap();
+        inner.0 += 1;
+        let id = inner.0;
+        let (sender, receiver) = mpsc::channel();
+        inner.1.insert(id, sender);
+        (id, receiver)
+    }
+
+    pub fn resolve(&self, snapshot: ConversationSnapshot) {
+        if let Some(sender) = self.0.lock().unwrap().1.remove(&snapshot.id) {
+            let _ = sender.send(snapshot);
+        }
+    }
+
+    pub fn cancel(&self, id: u64) {
+        self.0.lock().unwrap().1.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_sanitizes_and_converts() {
+        let response = CapturedResponse::capture(
+            Some(3),
+            r#"<div class="prose"><p>Hi <b>there</b></p><button>Copy</button><script>x()</script></div>"#,
+            "https://chat.mistral.ai/chat/abc",
+            "Greeting - Le Chat",
+        );
+        assert_eq!(response.submission_id, Some(3));
+        assert_eq!(response.html, "<p>Hi <b>there</b></p>");
+        assert_eq!(response.markdown, "Hi **there**");
+        assert_eq!(response.url, "https://chat.mistral.ai/chat/abc");
+    }
+
+    #[test]
+    fn test_excerpt_is_plain_and_short() {
+        let long = format!("<p>{}</p><ul><li>item</li></ul>", "word ".repeat(100));
+        let response = CapturedResponse::capture(None, &long, "", "");
+        let excerpt = response.excerpt();
+        assert!(excerpt.starts_with("word word"));
+        assert_eq!(excerpt.chars().count(), EXCERPT_CHARS + 1);
+
+        let short = CapturedResponse::capture(None, "<p>Done</p><p>Next</p>", "", "");
+        assert_eq!(short.excerpt(), "Done Next");
+    }
+
+    #[test]
+    fn test_inline_answer_is_sanitized() {
+        let partial = InlineAnswer::partial(
+            7,
+            r#"<p onclick="x()">Partial <em>answ</em></p><script>x()</script>"#,
+            "https://chat.mistral.ai/chat/abc",
+        );
+        assert_eq!(partial.html, "<p>Partial <em>answ</em></p>");
+        assert_eq!(partial.markdown, "Partial *answ*");
+        assert!(!partial.done);
+
+        let response = CapturedResponse::capture(Some(7), "<p>Done</p>", "u", "t");
+        let complete = InlineAnswer::complete(7, &response);
+        assert_eq!(complete.markdown, "Done");
+        assert!(complete.done);
+    }
+
+    #[test]
+    fn test_api_replies_keep_their_markdown() {
+        let reply = "Use `cargo` *now*\n\n<img src=x onerror=alert(1)>";
+        let response = CapturedResponse::from_markdown(Some(2), reply, "mistral-small-latest");
+        assert_eq!(response.markdown, reply);
+        assert!(response
+            .html
+            .starts_with("<p>Use <code>cargo</code> <em>now</em></p>"));
+        // Raw HTML from the model is shown as text, never rendered
+        assert!(!response.html.contains("<img"));
+
+        let partial = InlineAnswer::from_markdown(2, "**Hal");
+        assert_eq!(partial.markdown, "**Hal");
+        assert!(partial.url.is_empty());
+        assert!(!partial.done);
+    }
+
+    #[test]
+    fn test_conversation_snapshot_markdown() {
+        let snapshot: ConversationSnapshot = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "url": "https://chat.mistral.ai/chat/abc",
+            "title": "Rust help",
+            "messages": [
+                { "role": "user", "html": "<p>What is <code>Rc</code>?</p>" },
+                { "role": "assistant", "html": "<p>A <b>shared</b> pointer.</p><button>Copy</button>" },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(
+            snapshot.to_markdown(),
+            "## You\n\nWhat is `Rc`?\n\n## Le Chat\n\nA **shared** pointer."
+        );
+    }
+
+    #[test]
+    fn test_snapshot_requests_resolve_by_id() {
+        let requests = SnapshotRequests::default();
+        let (first, first_rx) = requests.expect();
+        let (second, second_rx) = requests.expect();
+        assert_ne!(first, second);
+        requests.resolve(ConversationSnapshot {
+            id: second,
+            ..Default::default()
+        });
+        assert_eq!(second_rx.try_recv().unwrap().id, second);
+        assert!(first_rx.try_recv().is_err());
+        requests.cancel(first);
+        assert!(first_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_last_response() {
+        let last = LastResponse::default();
+        assert!(last.get().is_none());
+        last.set(CapturedResponse::capture(None, "<p>a</p>", "", ""));
+        assert_eq!(last.get().unwrap().markdown, "a");
+    }
+}
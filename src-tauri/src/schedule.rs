@@ -0,0 +1,709 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{
+    DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Timelike,
+};
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+const STORE_FILE: &str = "schedule.json";
+const LAST_RUNS_KEY: &str = "last_runs";
+
+// Serializes read-modify-write cycles on the schedule store
+static SCHEDULE_LOCK: Mutex<()> = Mutex::new(());
+
+// How late a run may start before it counts as missed, e.g. after the
+// computer slept through it or the app wasn't running
+pub const MISSED_AFTER: TimeDelta = TimeDelta::minutes(2);
+
+// Largest file a `{file:…}` placeholder may pull into a prompt
+const MAX_FILE_BYTES: u64 = 100 * 1024;
+
+// How far ahead to look for the next run; covers a Feb 29 that is 8 years away
+const SEARCH_DAYS: u32 = 8 * 366;
+
+// A prompt sent in a new chat on a recurring schedule
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ScheduledPrompt {
+    // Identifies the prompt's run history, so it must be unique
+    pub name: String,
+    // "weekdays 9:00", "every monday 8:30", "daily 18:00" or a five-field cron expression
+    pub schedule: String,
+    // May include `{file:~/notes.md}` placeholders, read each time the prompt runs
+    pub prompt: String,
+    pub enabled: bool,
+}
+
+impl Default for ScheduledPrompt {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            schedule: String::new(),
+            prompt: String::new(),
+            enabled: true,
+        }
+    }
+}
+
+// What to do about runs that were due while the app wasn't running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRuns {
+    // Run once as soon as possible, however many were missed
+    #[default]
+    CatchUp,
+    Skip,
+}
+
+// The outcome of checking a prompt's schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    // Nothing is due
+    Wait,
+    Run,
+    // A missed run that the settings say to drop
+    Skip,
+}
+
+// The minutes a schedule fires on, as cron-style sets. Bit n stands for value n.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u32,
+    // 1-31
+    days: u32,
+    // 1-12
+    months: u16,
+    // 0 (Sunday) - 6
+    weekdays: u8,
+    // Cron runs on either day field when both are restricted
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+fn parse_weekday(word: &str) -> Option<u32> {
+    // "mon", "monday" or "mondays"
+    let word = word.strip_suffix('s').unwrap_or(word);
+    WEEKDAY_NAMES
+        .iter()
+        .position(|name| word == *name || word == format!("{}day", full_weekday_stem(name)))
+        .map(|i| i as u32)
+}
+
+// "tue" → "tues", as in "tuesday"
+fn full_weekday_stem(short: &str) -> &str {
+    match short {
+        "tue" => "tues",
+        "wed" => "wednes",
+        "thu" => "thurs",
+        "sat" => "satur",
+        _ => short,
+    }
+}
+
+// "9:00", "09:30" or "17:05"
+fn parse_time(text: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("\"{}\" is not a time like 9:00 or 17:30", text);
+    let (hour, minute) = text.split_once(':').ok_or_else(invalid)?;
+    if minute.len() != 2 {
+        return Err(invalid());
+    }
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+    if hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+    Ok((hour, minute))
+}
+
+// One cron field: `*`, `5`, `1-5`, `*/15`, `mon-fri`, `1,15` and combinations
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    offset: u32,
+) -> Result<u64, String> {
+    let invalid = || format!("\"{}\" is not a valid cron field", field);
+    let value = |text: &str| -> Result<u32, String> {
+        let lower = text.to_ascii_lowercase();
+        if let Some(i) = names.iter().position(|name| *name == lower) {
+            return Ok(i as u32 + offset);
+        }
+        let n: u32 = text.parse().map_err(|_| invalid())?;
+        if n < min || n > max {
+            return Err(format!("{} is out of range in \"{}\"", n, field));
+        }
+        Ok(n)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| invalid())?;
+                if step == 0 {
+                    return Err(invalid());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else {
+            let start = value(range)?;
+            // `5/15` means from 5 to the end in steps of 15
+            (start, if step > 1 { max } else { start })
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for n in (start..=end).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+impl Schedule {
+    // Parse a cron expression ("0 9 * * 1-5") or a simple schedule:
+    // "[every] day|weekday|weekend|<days> [at] H:MM", e.g. "every weekday 9:00",
+    // "daily 18:30", "weekly mon,thu 8:15" or "saturdays at 10:00"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("The schedule is empty".to_string());
+        }
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() == 5 && !fields[4].contains(':') {
+            return Self::parse_cron(&fields);
+        }
+        Self::parse_simple(text)
+    }
+
+    fn parse_cron(fields: &[&str]) -> Result<Self, String> {
+        let mut weekdays = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES, 0)?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & 0x7f;
+        }
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, &[], 0)?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)? as u32,
+            days: parse_field(fields[2], 1, 31, &[], 0)? as u32,
+            months: parse_field(fields[3], 1, 12, &MONTH_NAMES, 1)? as u16,
+            weekdays: weekdays as u8,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    fn parse_simple(text: &str) -> Result<Self, String> {
+        let lower = text.to_ascii_lowercase();
+        let mut words: Vec<&str> = lower
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty() && *word != "and")
+            .collect();
+        let time = words.pop().ok_or("The schedule needs a time")?;
+        let (hour, minute) = parse_time(time)?;
+        if words.last() == Some(&"at") {
+            words.pop();
+        }
+        let weekly = words.first() == Some(&"weekly");
+        if matches!(words.first(), Some(&"every") | Some(&"weekly")) {
+            words.remove(0);
+        }
+        // "weekly" alone would run every day
+        if weekly && matches!(words.as_slice(), [] | ["day"] | ["daily"]) {
+            return Err(format!(
+                "\"{}\" needs the days to run on, like \"weekly mon 9:00\"",
+                text
+            ));
+        }
+
+        let weekdays = match words.as_slice() {
+            [] | ["day"] | ["daily"] => 0x7f,
+            ["weekday"] | ["weekdays"] => 0b0011_1110,
+            ["weekend"] | ["weekends"] => 0b0100_0001,
+            days => {
+                let mut bits = 0u8;
+                for day in days {
+                    let n = parse_weekday(day).ok_or_else(|| {
+                        format!(
+                            "\"{}\" isn't a schedule like \"weekdays 9:00\" or a cron expression",
+                            text
+                        )
+                    })?;
+                    bits |= 1 << n;
+                }
+                bits
+            }
+        };
+        Ok(Self {
+            minutes: 1 << minute,
+            hours: 1 << hour,
+            days: !0,
+            months: !0,
+            weekdays,
+            any_day: true,
+            any_weekday: weekdays == 0x7f,
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    // The first matching minute on `date` at or after `from`
+    fn first_time(&self, from: NaiveTime) -> Option<NaiveTime> {
+        (from.hour()..24)
+            .filter(|hour| self.hours & (1 << hour) != 0)
+            .find_map(|hour| {
+                let first_minute = if hour == from.hour() {
+                    from.minute()
+                } else {
+                    0
+                };
+                (first_minute..60)
+                    .find(|minute| self.minutes & (1 << minute) != 0)
+                    .and_then(|minute| NaiveTime::from_hms_opt(hour, minute, 0))
+            })
+    }
+
+    // The first matching wall-clock minute strictly after `after`
+    fn next_naive(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let mut date = start.date();
+        let mut from = start.time();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_date(date) {
+                if let Some(time) = self.first_time(from) {
+                    return Some(date.and_time(time));
+                }
+            }
+            date = date.succ_opt()?;
+            from = NaiveTime::MIN;
+        }
+        None
+    }
+
+    // The next run strictly after `after`, in `after`'s time zone. Wall-clock
+    // times skipped by a daylight saving change don't run that day.
+    pub fn next_run<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let zone = after.timezone();
+        let mut cursor = after.naive_local();
+        loop {
+            cursor = self.next_naive(cursor)?;
+            if let Some(run) = zone.from_local_datetime(&cursor).earliest() {
+                if run > *after {
+                    return Some(run);
+                }
+            }
+        }
+    }
+
+    // Whether a run is due at `now`, given that the schedule was last run
+    // (or started being tracked) at `since`
+    pub fn decide<Tz: TimeZone>(
+        &self,
+        since: &DateTime<Tz>,
+        now: &DateTime<Tz>,
+        missed_runs: MissedRuns,
+    ) -> Decision {
+        let late = now.clone() - MISSED_AFTER;
+        let recent_from = if *since > late { since } else { &late };
+        if self.next_run(recent_from).is_some_and(|run| run <= *now) {
+            return Decision::Run;
+        }
+        if self.next_run(since).is_some_and(|run| run <= late) {
+            return match missed_runs {
+                MissedRuns::CatchUp => Decision::Run,
+                MissedRuns::Skip => Decision::Skip,
+            };
+        }
+        Decision::Wait
+    }
+}
+
+// A scheduled prompt that was sent and is waiting for its answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledRun {
+    pub name: String,
+    // Label of the hidden window the prompt was sent from
+    pub window: String,
+}
+
+// Scheduled runs in progress, by submission id
+#[derive(Default)]
+pub struct ScheduledRuns(Mutex<HashMap<u64, ScheduledRun>>);
+
+impl ScheduledRuns {
+    pub fn start(&self, id: u64, run: ScheduledRun) {
+        self.0.lock().unwrap().insert(id, run);
+    }
+
+    // Returns the run once; later answers or timeouts for it get nothing
+    pub fn finish(&self, id: u64) -> Option<ScheduledRun> {
+        self.0.lock().unwrap().remove(&id)
+    }
+}
+
+// Check scheduled prompts before they're saved
+pub fn validate(prompts: &[ScheduledPrompt]) -> Result<(), String> {
+    for (i, prompt) in prompts.iter().enumerate() {
+        let label = format!("Scheduled prompt {}", i + 1);
+        if prompt.name.trim().is_empty() {
+            return Err(format!("{} needs a name", label));
+        }
+        if prompts[..i].iter().any(|other| other.name == prompt.name) {
+            return Err(format!(
+                "{} (\"{}\"): another prompt has the same name",
+                label, prompt.name
+            ));
+        }
+        if prompt.prompt.trim().is_empty() {
+            return Err(format!(
+                "{} (\"{}\"): the prompt is empty",
+                label, prompt.name
+            ));
+        }
+        Schedule::parse(&prompt.schedule)
+            .map_err(|e| format!("{} (\"{}\"): {}", label, prompt.name, e))?;
+    }
+    Ok(())
+}
+
+// Replace each `{file:path}` with the file's contents. A leading `~/` is the
+// home directory.
+pub fn expand_files(prompt: &str, home: Option<&Path>) -> Result<String, String> {
+    let mut expanded = String::with_capacity(prompt.len());
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{file:") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let spec = &rest[start + "{file:".len()..start + len];
+        let path = match (spec.strip_prefix("~/"), home) {
+            (Some(relative), Some(home)) => home.join(relative),
+            (Some(_), None) => return Err("The home directory is unknown".to_string()),
+            (None, _) => Path::new(spec).to_path_buf(),
+        };
+        let size = std::fs::metadata(&path)
+            .map_err(|e| format!("Couldn't read {}: {}", spec, e))?
+            .len();
+        if size > MAX_FILE_BYTES {
+            return Err(format!(
+                "{} is larger than {} KB",
+                spec,
+                MAX_FILE_BYTES / 1024
+            ));
+        }
+        let contents =
+            std::fs::read_to_string(&path).map_err(|e| format!("Couldn't read {}: {}", spec, e))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(contents.trim_end());
+        rest = &rest[start + len + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+// Decode stored run times, dropping entries that can't be read
+fn decode(raw: &Value) -> BTreeMap<String, i64> {
+    let Some(entries) = raw.as_object() else {
+        return BTreeMap::new();
+    };
+    entries
+        .iter()
+        .filter_map(|(name, time)| Some((name.clone(), time.as_i64()?)))
+        .collect()
+}
+
+// Unix timestamps (seconds) of each prompt's last run, or of when the app
+// started tracking it, by name
+pub fn load_last_runs(app: &AppHandle) -> BTreeMap<String, i64> {
+    let _guard = SCHEDULE_LOCK.lock().unwrap();
+    match app.store(STORE_FILE) {
+        Ok(store) => store
+            .get(LAST_RUNS_KEY)
+            .map(|raw| decode(&raw))
+            .unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Failed to open schedule store: {}", e);
+            BTreeMap::new()
+        }
+    }
+}
+
+pub fn save_last_runs(app: &AppHandle, last_runs: &BTreeMap<String, i64>) -> Result<(), String> {
+    let _guard = SCHEDULE_LOCK.lock().unwrap();
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.set(
+        LAST_RUNS_KEY,
+        serde_json::to_value(last_runs).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn at(text: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_utc()
+    }
+
+    fn next(schedule: &str, after: &str) -> String {
+        Schedule::parse(schedule)
+            .unwrap()
+            .next_run(&at(after))
+            .unwrap()
+            .format("%a %Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn test_simple_schedules() {
+        // 2026-10-16 is a Friday
+        assert_eq!(
+            next("every weekday 9:00", "2026-10-16 08:59"),
+            "Fri 2026-10-16 09:00"
+        );
+        assert_eq!(
+            next("every weekday 9:00", "2026-10-16 09:00"),
+            "Mon 2026-10-19 09:00"
+        );
+        assert_eq!(
+            next("weekdays at 09:00", "2026-10-17 12:00"),
+            "Mon 2026-10-19 09:00"
+        );
+        assert_eq!(
+            next("daily 18:30", "2026-10-16 18:30"),
+            "Sat 2026-10-17 18:30"
+        );
+        assert_eq!(
+            next("weekly Mon, Thu 8:15", "2026-10-16 00:00"),
+            "Mon 2026-10-19 08:15"
+        );
+        assert_eq!(
+            next("saturdays at 10:00", "2026-10-16 00:00"),
+            "Sat 2026-10-17 10:00"
+        );
+        assert_eq!(
+            next("every weekend 7:05", "2026-10-18 07:05"),
+            "Sat 2026-10-24 07:05"
+        );
+        assert_eq!(
+            next("every tuesday and friday 23:59", "2026-10-16 23:59"),
+            "Tue 2026-10-20 23:59"
+        );
+    }
+
+    #[test]
+    fn test_cron_schedules() {
+        assert_eq!(
+            next("0 9 * * 1-5", "2026-10-16 09:00"),
+            "Mon 2026-10-19 09:00"
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2026-10-16 09:01"),
+            "Fri 2026-10-16 09:15"
+        );
+        assert_eq!(
+            next("30 8 1 * *", "2026-10-16 00:00"),
+            "Sun 2026-11-01 08:30"
+        );
+        assert_eq!(
+            next("0 0 29 feb *", "2026-10-16 00:00"),
+            "Tue 2028-02-29 00:00"
+        );
+        assert_eq!(
+            next("0 12 * * sun", "2026-10-16 00:00"),
+            "Sun 2026-10-18 12:00"
+        );
+        assert_eq!(
+            next("0 12 * * 7", "2026-10-16 00:00"),
+            "Sun 2026-10-18 12:00"
+        );
+        // Both day fields restricted: either one matches
+        assert_eq!(
+            next("0 6 20 * mon", "2026-10-16 00:00"),
+            "Mon 2026-10-19 06:00"
+        );
+        assert_eq!(
+            next("0 6 17 * mon", "2026-10-16 00:00"),
+            "Sat 2026-10-17 06:00"
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_schedules() {
+        for text in [
+            "",
+            "9:00am",
+            "weekdays",
+            "every fortnight 9:00",
+            "weekly 9:00",
+            "weekly at 9:00",
+            "daily 24:00",
+            "daily 9:5",
+            "60 * * * *",
+            "0 9 * * 1-9",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 9 * *",
+        ] {
+            assert!(
+                Schedule::parse(text).is_err(),
+                "{:?} should be rejected",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_weekly_needs_days() {
+        let err = Schedule::parse("weekly 9:00").unwrap_err();
+        assert!(err.contains("needs the days"), "{}", err);
+        assert!(Schedule::parse("weekly fri 9:00").is_ok());
+    }
+
+    #[test]
+    fn test_runs_in_the_given_time_zone() {
+        let paris = FixedOffset::east_opt(2 * 3600).unwrap();
+        let after = paris.with_ymd_and_hms(2026, 10, 16, 8, 0, 0).unwrap();
+        let run = Schedule::parse("daily 9:00")
+            .unwrap()
+            .next_run(&after)
+            .unwrap();
+        assert_eq!(run.naive_local().to_string(), "2026-10-16 09:00:00");
+        assert_eq!(run.naive_utc().to_string(), "2026-10-16 07:00:00");
+    }
+
+    #[test]
+    fn test_decide_runs_on_time_and_handles_missed_runs() {
+        let schedule = Schedule::parse("every weekday 9:00").unwrap();
+        let since = at("2026-10-15 09:00");
+
+        // Nothing due yet
+        let now = at("2026-10-16 08:59");
+        assert_eq!(
+            schedule.decide(&since, &now, MissedRuns::CatchUp),
+            Decision::Wait
+        );
+        // The scheduler's check lands shortly after 9:00
+        let now = at("2026-10-16 09:01");
+        assert_eq!(
+            schedule.decide(&since, &now, MissedRuns::Skip),
+            Decision::Run
+        );
+        // Already run this morning
+        assert_eq!(
+            schedule.decide(&now, &at("2026-10-16 09:02"), MissedRuns::CatchUp),
+            Decision::Wait
+        );
+
+        // The app was closed over 9:00
+        let now = at("2026-10-16 11:00");
+        assert_eq!(
+            schedule.decide(&since, &now, MissedRuns::CatchUp),
+            Decision::Run
+        );
+        assert_eq!(
+            schedule.decide(&since, &now, MissedRuns::Skip),
+            Decision::Skip
+        );
+
+        // A run that is due now wins over older missed ones
+        let since = at("2026-10-12 09:00");
+        let now = at("2026-10-16 09:00");
+        assert_eq!(
+            schedule.decide(&since, &now, MissedRuns::Skip),
+            Decision::Run
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let prompt = |name: &str, schedule: &str| ScheduledPrompt {
+            name: name.to_string(),
+            schedule: schedule.to_string(),
+            prompt: "Summarise {file:~/standup.md}".to_string(),
+            enabled: true,
+        };
+        assert!(validate(&[
+            prompt("Standup", "weekdays 9:00"),
+            prompt("Review", "0 17 * * fri")
+        ])
+        .is_ok());
+
+        let error = validate(&[
+            prompt("Standup", "weekdays 9:00"),
+            prompt("Standup", "daily 9:00"),
+        ])
+        .unwrap_err();
+        assert!(
+            error.starts_with("Scheduled prompt 2 (\"Standup\")"),
+            "{}",
+            error
+        );
+        let error = validate(&[prompt("Standup", "sometimes")]).unwrap_err();
+        assert!(error.contains("sometimes"), "{}", error);
+        assert!(validate(&[prompt(" ", "daily 9:00")]).is_err());
+        assert!(validate(&[ScheduledPrompt {
+            prompt: " ".to_string(),
+            ..prompt("Empty", "daily 9:00")
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn test_expand_files() {
+        let dir = std::env::temp_dir().join(format!("le-chat-schedule-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("standup.md"), "- shipped the fix\n").unwrap();
+
+        let expanded = expand_files(
+            "Summarise these notes: {file:~/standup.md} thanks",
+            Some(&dir),
+        )
+        .unwrap();
+        assert_eq!(expanded, "Summarise these notes: - shipped the fix thanks");
+
+        let absolute = format!("{{file:{}}}", dir.join("standup.md").display());
+        assert_eq!(expand_files(&absolute, None).unwrap(), "- shipped the fix");
+        assert_eq!(
+            expand_files("no {file: braces", None).unwrap(),
+            "no {file: braces"
+        );
+
+        let error = expand_files("{file:~/missing.md}", Some(&dir)).unwrap_err();
+        assert!(error.starts_with("Couldn't read ~/missing.md"), "{}", error);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::BTreeMap;
+
+use tauri::{webview::PageLoadEvent, Runtime, Webview};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    MacOS,
+    Windows,
+    Linux,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOS
+        } else if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+const ALL_PLATFORMS: &[Platform] = &[Platform::MacOS, Platform::Windows, Platform::Linux];
+
+// A named script that is evaluated in the main webview after every page load
+// whose URL matches one of its patterns. Scripts must tolerate running more
+// than once per document.
+pub struct PageScript {
+    pub name: &'static str,
+    pub description: &'static str,
+    // Glob-style patterns where `*` matches any run of characters
+    pub url_patterns: &'static [&'static str],
+    pub platforms: &'static [Platform],
+    pub enabled_by_default: bool,
+    // Only run in the main window, e.g. because the script calls its commands.
+    // Other windows with the page scripts, like the private window, skip it.
+    pub main_window_only: bool,
+    pub source: fn() -> String,
+}
+
+pub const PAGE_SCRIPTS: &[PageScript] = &[
+    PageScript {
+        name: "connectivity-monitor",
+        description: "Reload when the connection comes back and fall back to the offline page",
+        url_patterns: &["https://chat.mistral.ai/*"],
+        platforms: ALL_PLATFORMS,
+        enabled_by_default: true,
+        main_window_only: true,
+        source: get_connectivity_monitor_js,
+    },
+    PageScript {
+        name: "hide-titlebar-overlap",
+        description: "Hide sidebar controls that sit under the window buttons",
+        url_patterns: &["https://chat.mistral.ai/*"],
+        platforms: &[Platform::MacOS],
+        enabled_by_default: true,
+        main_window_only: false,
+        source: get_hide_titlebar_overlap_js,
+    },
+];
+
+// Script metadata sent to the settings window
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PageScriptInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub enabled: bool,
+}
+
+impl PageScript {
+    pub fn is_enabled(&self, overrides: &BTreeMap<String, bool>) -> bool {
+        overrides
+            .get(self.name)
+            .copied()
+            .unwrap_or(self.enabled_by_default)
+    }
+
+    pub fn runs_in(&self, window: &str) -> bool {
+        !self.main_window_only || window == "main"
+    }
+
+    pub fn matches(&self, url: &str, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+            && self
+                .url_patterns
+                .iter()
+                .any(|pattern| matches_pattern(pattern, url))
+    }
+}
+
+// Match `url` against a pattern where `*` stands for any (possibly empty) run of characters
+fn matches_pattern(pattern: &str, url: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return url.is_empty();
+    };
+    let Some(mut rest) = url.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the whole URL must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+// Scripts that should run after a page load of `url`
+pub fn scripts_for<'a>(
+    url: &str,
+    platform: Platform,
+    overrides: &BTreeMap<String, bool>,
+) -> Vec<&'a PageScript> {
+    PAGE_SCRIPTS
+        .iter()
+        .filter(|script| script.matches(url, platform) && script.is_enabled(overrides))
+        .collect()
+}
+
+// Scripts available on this platform, for the settings window
+pub fn available_scripts(overrides: &BTreeMap<String, bool>) -> Vec<PageScriptInfo> {
+    let platform = Platform::current();
+    PAGE_SCRIPTS
+        .iter()
+        .filter(|script| script.platforms.contains(&platform))
+        .map(|script| PageScriptInfo {
+            name: script.name,
+            description: script.description,
+            enabled: script.is_enabled(overrides),
+        })
+        .collect()
+}
+
+// Re-apply registered scripts whenever the main webview finishes loading a page,
+// so they survive navigation, reloads and the offline page round-trip.
+pub fn on_page_load<R: Runtime>(
+    webview: &Webview<R>,
+    url: &tauri::Url,
+    event: PageLoadEvent,
+    overrides: &BTreeMap<String, bool>,
+) {
+    if event != PageLoadEvent::Finished {
+        return;
+    }
+    for script in scripts_for(url.as_str(), Platform::current(), overrides)
+        .into_iter()
+        .filter(|script| script.runs_in(webview.label()))
+    {
+        if let Err(e) = webview.eval((script.source)()) {
+            eprintln!("Failed to inject page script {}: {}", script.name, e);
+        }
+    }
+}
+
+// JavaScript to inject custom styles that hide UI elements overlapping with title bar
+fn get_hide_titlebar_overlap_js() -> String {
+    r#"
+    (function() {
+        const STYLE_ID = 'le-chat-custom-styles';
+        
+        function injectStyles() {
+            // Avoid duplicate injection
+            if (document.getElementById(STYLE_ID)) return;
+            
+            const style = document.createElement('style');
+            style.id = STYLE_ID;
+            style.textContent = `
+                /* Hide the workspace menu button that overlaps title bar */
+                div[data-sidebar="header"] button[aria-haspopup="menu"] {
+                    display: none !important;
+                }
+                
+                /* Hide the flex-1 wrapper containing the workspace button */
+                div[data-sidebar="header"] .flex-1:has(button[aria-haspopup="menu"]) {
+                    display: none !important;
+                }
+                
+                /* Make the button container full width and push buttons to the right */
+                div[data-sidebar="header"] > div.flex {
+                    width: 100% !important;
+                    justify-content: flex-end !important;
+                }
+                
+                /* Add top padding to sidebar header to clear macOS traffic lights */
+                div[data-sidebar="header"] {
+                    padding-top: 2.5rem !important;
+                }
+            `;
+            document.head.appendChild(style);
+            
+            // Fallback for browsers without :has() support
+            document.querySelectorAll('div[data-sidebar="header"] button[aria-haspopup="menu"]').forEach(btn => {
+                btn.style.display = 'none';
+                // Also hide the flex-1 wrapper parent
+                const wrapper = btn.closest('.flex-1');
+                if (wrapper) {
+                    wrapper.style.display = 'none';
+                }
+            });
+            
+            // Push buttons to the right (fallback)
+            document.querySelectorAll('div[data-sidebar="header"] > div.flex').forEach(container => {
+                container.style.width = '100%';
+                container.style.justifyContent = 'flex-end';
+            });
+            
+            console.log('[Le Chat] Custom styles injected');
+        }
+        
+        // Retry until DOM is ready
+        if (document.readyState === 'loading') {
+            document.addEventListener('DOMContentLoaded', injectStyles);
+        } else {
+            injectStyles();
+        }
+        
+        // Re-inject on dynamic navigation (React SPA)
+        new MutationObserver(() => injectStyles()).observe(
+            document.documentElement, 
+            { childList: true, subtree: true }
+        );
+    })();
+    "#.to_string()
+}
+
+// PWA-aware connectivity monitoring for the main window.
+// Respects Le Chat's service worker for offline caching — only falls back
+// to the local offline page when no service worker is registered (first launch).
+fn get_connectivity_monitor_js() -> String {
+    r#"
+    (function() {
+        if (window.__leChatConnectivityMonitor) return;
+        window.__leChatConnectivityMonitor = true;
+
+        const CHAT_URL = 'https://chat.mistral.ai/chat';
+
+        // Check if Le Chat's service worker is registered
+        async function checkServiceWorker() {
+            if (!('serviceWorker' in navigator)) {
+                console.log('[Le Chat] Service workers not supported in this webview');
+                return { supported: false, registered: false };
+            }
+            try {
+                const registrations = await navigator.serviceWorker.getRegistrations();
+                const hasSW = registrations.length > 0;
+                console.log('[Le Chat] Service worker supported: true, registered:', hasSW,
+                    hasSW ? '(PWA active)' : '(no PWA cache yet)');
+                for (const reg of registrations) {
+                    console.log('[Le Chat]   SW scope:', reg.scope, 'state:',
+                        reg.active ? 'active' : reg.installing ? 'installing' : reg.waiting ? 'waiting' : 'unknown');
+                }
+                return { supported: true, registered: hasSW };
+            } catch (e) {
+                console.log('[Le Chat] Service worker check failed:', e.message);
+                return { supported: true, registered: false };
+            }
+        }
+
+        // Monitor online/offline events
+        window.addEventListener('offline', () => {
+            console.log('[Le Chat] Browser went offline');
+        });
+        window.addEventListener('online', () => {
+            console.log('[Le Chat] Browser came online — reloading');
+            if (window.location.href.includes('chat.mistral.ai')) {
+                window.location.reload();
+            } else {
+                window.location.href = CHAT_URL;
+            }
+        });
+
+        // Check if page loaded successfully after a delay.
+        // If the PWA service worker is registered, let it handle offline.
+        // Only fall back to the local offline page on first launch with no cache.
+        setTimeout(async () => {
+            const isErrorPage = !navigator.onLine
+                || document.title.toLowerCase().includes('error')
+                || document.title.toLowerCase().includes('not found')
+                || document.title === ''
+                || (document.body && document.body.innerText.length < 50
+                    && !document.querySelector('[data-sidebar]'));
+
+            if (!isErrorPage || window.location.href.includes('tauri')) {
+                // Page loaded fine or we're on a local page — just log SW status
+                await checkServiceWorker();
+                return;
+            }
+
+            // Page failed to load — check if PWA service worker can handle it
+            const sw = await checkServiceWorker();
+            if (sw.registered) {
+                // Reload guard: prevent infinite reload loop via sessionStorage flag
+                const reloadKey = '__le_chat_sw_reload';
+                if (sessionStorage.getItem(reloadKey)) {
+                    console.log('[Le Chat] Already tried SW reload — falling back to offline page');
+                    sessionStorage.removeItem(reloadKey);
+                    if (window.__TAURI__) {
+                        window.__TAURI__.core.invoke('navigate_to_offline').catch(() => {});
+                    }
+                } else {
+                    sessionStorage.setItem(reloadKey, '1');
+                    console.log('[Le Chat] Page failed but PWA service worker is active — reloading to use cache');
+                    window.location.reload();
+                }
+            } else {
+                // No service worker (first launch or SW not installed) — local offline page
+                console.log('[Le Chat] Page failed and no service worker — showing offline page');
+                if (window.__TAURI__) {
+                    window.__TAURI__.core.invoke('navigate_to_offline').catch(() => {});
+                }
+            }
+        }, 5000);
+    })();
+    "#
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAT_URL: &str = "https://chat.mistral.ai/chat";
+    const OFFLINE_URL: &str = "tauri://localhost/index.html";
+
+    fn names(scripts: &[&PageScript]) -> Vec<&'static str> {
+        scripts.iter().map(|s| s.name).collect()
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("https://chat.mistral.ai/*", CHAT_URL));
+        assert!(matches_pattern(
+            "https://chat.mistral.ai/*",
+            "https://chat.mistral.ai/chat/1234?x=1"
+        ));
+        assert!(matches_pattern(
+            "https://*.mistral.ai/*",
+            "https://auth.mistral.ai/login"
+        ));
+        assert!(matches_pattern("tauri://localhost/index.html", OFFLINE_URL));
+        assert!(matches_pattern("*", OFFLINE_URL));
+        assert!(!matches_pattern("https://chat.mistral.ai/*", OFFLINE_URL));
+        assert!(!matches_pattern(
+            "https://chat.mistral.ai/*",
+            "https://evil.example/chat.mistral.ai/"
+        ));
+        assert!(!matches_pattern(
+            "tauri://localhost/index.html",
+            "tauri://localhost/index.html#x"
+        ));
+    }
+
+    #[test]
+    fn test_scripts_are_scoped_by_platform() {
+        let overrides = BTreeMap::new();
+        assert_eq!(
+            names(&scripts_for(CHAT_URL, Platform::MacOS, &overrides)),
+            vec!["connectivity-monitor", "hide-titlebar-overlap"]
+        );
+        assert_eq!(
+            names(&scripts_for(CHAT_URL, Platform::Linux, &overrides)),
+            vec!["connectivity-monitor"]
+        );
+    }
+
+    #[test]
+    fn test_scripts_can_be_disabled() {
+        let overrides = BTreeMap::from([("connectivity-monitor".to_string(), false)]);
+        assert!(scripts_for(CHAT_URL, Platform::Windows, &overrides).is_empty());
+        let info = available_scripts(&overrides);
+        let monitor = info
+            .iter()
+            .find(|s| s.name == "connectivity-monitor")
+            .unwrap();
+        assert!(!monitor.enabled);
+    }
+
+    #[test]
+    fn test_scripts_survive_navigation() {
+        // Every page load is treated independently, so returning to the chat
+        // after the offline page or a reload re-applies the same scripts.
+        let overrides = BTreeMap::new();
+        let navigation = [
+            CHAT_URL,
+            OFFLINE_URL,
+            CHAT_URL,
+            "https://chat.mistral.ai/chat/abc-123",
+        ];
+        let loads: Vec<Vec<&str>> = navigation
+            .iter()
+            .map(|url| names(&scripts_for(url, Platform::MacOS, &overrides)))
+            .collect();
+        assert_eq!(
+            loads[0],
+            vec!["connectivity-monitor", "hide-titlebar-overlap"]
+        );
+        assert!(
+            loads[1].is_empty(),
+            "offline page has its own reconnect logic"
+        );
+        assert_eq!(loads[2], loads[0]);
+        assert_eq!(loads[3], loads[0]);
+    }
+
+    #[test]
+    fn test_connectivity_monitor_only_runs_in_the_main_window() {
+        let script = |name: &str| PAGE_SCRIPTS.iter().find(|s| s.name == name).unwrap();
+        let monitor = script("connectivity-monitor");
+        assert!(monitor.runs_in("main"));
+        assert!(!monitor.runs_in("private"));
+        assert!(script("hide-titlebar-overlap").runs_in("private"));
+    }
+
+    #[test]
+    fn test_page_script_names_are_unique() {
+        let mut names: Vec<_> = PAGE_SCRIPTS.iter().map(|s| s.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), PAGE_SCRIPTS.len());
+    }
+
+    #[test]
+    fn test_connectivity_monitor_js_is_valid() {
+        let js = get_connectivity_monitor_js();
+        assert!(js.contains("__leChatConnectivityMonitor"));
+        assert!(js.contains("navigate_to_offline"));
+        assert!(js.contains("checkServiceWorker"));
+    }
+
+    #[test]
+    fn test_hide_titlebar_overlap_js_is_valid() {
+        let js = get_hide_titlebar_overlap_js();
+        assert!(!js.is_empty());
+        assert!(js.contains("le-chat-custom-styles"));
+        assert!(js.contains("data-sidebar"));
+        assert!(js.contains("MutationObserver"));
+    }
+}
@@ -0,0 +1,591 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::hooks::Hook;
+use crate::mistral_api::ApiSettings;
+use crate::navigation::NavigationSettings;
+use crate::profiles::{self, Profile, DEFAULT_PROFILE};
+use crate::schedule::{MissedRuns, ScheduledPrompt};
+use crate::shortcuts::ShortcutSettings;
+
+const STORE_FILE: &str = "settings.json";
+const BACKUP_STORE_FILE: &str = "settings-backup.json";
+const SETTINGS_KEY: &str = "app_settings";
+
+// Where launcher prompts are sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LauncherBackend {
+    // Typed into Le Chat in the main window
+    #[default]
+    Webview,
+    // Sent straight to the chat completions API, answered inline
+    Api,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub new_chat_default: bool,
+    // Launcher shows replies in place instead of switching to the main window
+    pub inline_answers: bool,
+    pub launcher_backend: LauncherBackend,
+    pub api: ApiSettings,
+    pub notifications_enabled: bool,
+    // Which links stay in the main window and whether the rest open in the browser
+    pub navigation: NavigationSettings,
+    // Serve the local JSON-RPC control socket for editor plugins and scripts
+    pub control_socket: bool,
+    // Commands and local URLs told about app events
+    pub hooks: Vec<Hook>,
+    // Prompts sent in a new chat on a recurring schedule
+    pub scheduled_prompts: Vec<ScheduledPrompt>,
+    // Whether a scheduled run missed while the app was closed runs late or not at all
+    pub missed_runs: MissedRuns,
+    pub shortcuts: ShortcutSettings,
+    // Page script name → enabled. Scripts not listed use their own default.
+    pub page_scripts: BTreeMap<String, bool>,
+    // Accounts besides the default one, each with its own webview data
+    pub profiles: Vec<Profile>,
+    // Id of the profile open in the main window
+    pub active_profile: String,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            new_chat_default: true,
+            inline_answers: false,
+            launcher_backend: LauncherBackend::Webview,
+            api: ApiSettings::default(),
+            notifications_enabled: true,
+            navigation: NavigationSettings::default(),
+            control_socket: false,
+            hooks: Vec::new(),
+            scheduled_prompts: Vec::new(),
+            missed_runs: MissedRuns::CatchUp,
+            shortcuts: ShortcutSettings::default(),
+            page_scripts: BTreeMap::new(),
+            profiles: Vec::new(),
+            active_profile: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+// Each migration upgrades the stored settings object by one schema version:
+// MIGRATIONS[n] turns version n into version n + 1. Append new migrations
+// here whenever a field is renamed or its meaning changes; plain additions
+// don't need one since missing fields fall back to their defaults.
+type Migration = fn(&mut Map<String, Value>);
+
+const MIGRATIONS: &[Migration] = &[
+    // v0 → v1: v0 stored the bare settings object without an envelope.
+    // The fields themselves carry over unchanged.
+    |_settings| {},
+];
+
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// Settings decoded from the store, along with what had to be repaired
+#[derive(Debug)]
+pub struct LoadedSettings {
+    pub settings: AppSettings,
+    // Schema version the value was stored with
+    pub version: u32,
+    // JSON pointers of fields whose stored value was invalid and got reset to the default
+    pub rejected: Vec<String>,
+    // Top-level fields this version doesn't know about (e.g. written by a newer app)
+    pub unknown: Map<String, Value>,
+}
+
+impl LoadedSettings {
+    // Whether the stored value should be rewritten in the current format
+    fn needs_rewrite(&self) -> bool {
+        self.version < CURRENT_VERSION || !self.rejected.is_empty()
+    }
+
+    // Whether the stored value should be backed up before it gets rewritten
+    fn needs_backup(&self) -> bool {
+        self.version > CURRENT_VERSION || !self.rejected.is_empty()
+    }
+}
+
+// Split a stored value into its schema version and settings object.
+// Values without an envelope predate versioning and are treated as version 0.
+fn unwrap_envelope(raw: &Value) -> Result<(u32, Map<String, Value>), String> {
+    let object = raw
+        .as_object()
+        .ok_or_else(|| format!("expected an object, found {}", raw))?;
+
+    if !object.contains_key("version") {
+        return Ok((0, object.clone()));
+    }
+
+    let version = object
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or("invalid schema version")?;
+    let settings = object
+        .get("settings")
+        .and_then(Value::as_object)
+        .ok_or("missing settings object")?;
+    Ok((version, settings.clone()))
+}
+
+fn wrap_envelope(settings: Map<String, Value>) -> Value {
+    serde_json::json!({
+        "version": CURRENT_VERSION,
+        "settings": settings,
+    })
+}
+
+// Copy stored fields over the defaults one at a time, keeping each only if the
+// result still deserializes. Struct-like objects are merged field by field so
+// one bad nested value doesn't reset its siblings.
+fn overlay_fields(
+    merged: &mut Value,
+    pointer: &str,
+    defaults: &Map<String, Value>,
+    stored: &Map<String, Value>,
+    rejected: &mut Vec<String>,
+    mut unknown: Option<&mut Map<String, Value>>,
+) {
+    for (key, value) in stored {
+        let Some(default_value) = defaults.get(key) else {
+            if let Some(unknown) = unknown.as_deref_mut() {
+                unknown.insert(key.clone(), value.clone());
+            }
+            continue;
+        };
+
+        let child = format!("{}/{}", pointer, key);
+        match (default_value, value) {
+            // Non-empty default objects are structs; empty ones are maps and are taken whole
+            (Value::Object(default_object), Value::Object(stored_object))
+                if !default_object.is_empty() =>
+            {
+                overlay_fields(
+                    merged,
+                    &child,
+                    default_object,
+                    stored_object,
+                    rejected,
+                    None,
+                );
+            }
+            _ => {
+                let Some(slot) = merged.pointer_mut(&child) else {
+                    continue;
+                };
+                let previous = std::mem::replace(slot, value.clone());
+                if serde_json::from_value::<AppSettings>(merged.clone()).is_err() {
+                    if let Some(slot) = merged.pointer_mut(&child) {
+                        *slot = previous;
+                    }
+                    rejected.push(child);
+                }
+            }
+        }
+    }
+}
+
+// Decode a raw stored value: unwrap the envelope, run pending migrations,
+// then deserialize field by field. Only fails if the value isn't usable at all.
+pub fn decode(raw: &Value) -> Result<LoadedSettings, String> {
+    let (version, mut stored) = unwrap_envelope(raw)?;
+
+    for migration in MIGRATIONS.iter().skip(version as usize) {
+        migration(&mut stored);
+    }
+
+    let defaults = serde_json::to_value(AppSettings::default()).map_err(|e| e.to_string())?;
+    let Value::Object(default_fields) = &defaults else {
+        return Err("default settings are not an object".to_string());
+    };
+
+    let mut merged = defaults.clone();
+    let mut rejected = Vec::new();
+    let mut unknown = Map::new();
+    overlay_fields(
+        &mut merged,
+        "",
+        default_fields,
+        &stored,
+        &mut rejected,
+        Some(&mut unknown),
+    );
+
+    let settings = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+    Ok(LoadedSettings {
+        settings,
+        version,
+        rejected,
+        unknown,
+    })
+}
+
+// Serialize settings into the current envelope, keeping unknown fields so
+// that a downgrade followed by an upgrade doesn't lose newer settings.
+pub fn encode(settings: &AppSettings, unknown: &Map<String, Value>) -> Result<Value, String> {
+    let Value::Object(mut fields) = serde_json::to_value(settings).map_err(|e| e.to_string())?
+    else {
+        return Err("settings did not serialize to an object".to_string());
+    };
+    for (key, value) in unknown {
+        fields.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    Ok(wrap_envelope(fields))
+}
+
+// Keep a copy of a stored value that couldn't be read as-is
+fn backup(app: &AppHandle, raw: &Value, reason: &str) {
+    let result = app.store(BACKUP_STORE_FILE).and_then(|store| {
+        // Avoid piling up identical backups on every load
+        if store
+            .values()
+            .iter()
+            .any(|entry| entry.get("value") == Some(raw))
+        {
+            return Ok(());
+        }
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        store.set(
+            format!("{}_{}", SETTINGS_KEY, timestamp),
+            serde_json::json!({ "reason": reason, "value": raw }),
+        );
+        store.save()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to back up settings: {}", e);
+    }
+}
+
+// Settings as the open profile sees them, see `profiles::effective`
+pub fn load(app: &AppHandle) -> AppSettings {
+    profiles::effective(load_stored(app))
+}
+
+// Load settings from the store, migrating and repairing them in place.
+// Never fails: unreadable values are backed up and replaced by defaults.
+fn load_stored(app: &AppHandle) -> AppSettings {
+    let store = match app.store(STORE_FILE) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Failed to open settings store: {}", e);
+            return AppSettings::default();
+        }
+    };
+    let Some(raw) = store.get(SETTINGS_KEY) else {
+        return AppSettings::default();
+    };
+
+    let loaded = match decode(&raw) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Stored settings are corrupt, using defaults: {}", e);
+            backup(app, &raw, &e);
+            return AppSettings::default();
+        }
+    };
+
+    if loaded.needs_backup() {
+        let reason = if loaded.version > CURRENT_VERSION {
+            format!("written by newer schema version {}", loaded.version)
+        } else {
+            format!("invalid fields: {}", loaded.rejected.join(", "))
+        };
+        eprintln!("Backing up stored settings ({})", reason);
+        backup(app, &raw, &reason);
+    }
+
+    if loaded.needs_rewrite() {
+        match encode(&loaded.settings, &loaded.unknown) {
+            Ok(value) => {
+                store.set(SETTINGS_KEY, value);
+                if let Err(e) = store.save() {
+                    eprintln!("Failed to save migrated settings: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to encode migrated settings: {}", e),
+        }
+    }
+
+    loaded.settings
+}
+
+// Save settings as returned by `load`, possibly edited
+pub fn save(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let stored = load_stored(app);
+    write(app, &profiles::fold(&stored, settings.clone()))
+}
+
+// Open another profile; its settings apply from the next `load`
+pub fn set_active_profile(app: &AppHandle, id: &str) -> Result<(), String> {
+    let mut stored = load_stored(app);
+    if !profiles::exists(&stored.profiles, id) {
+        return Err(format!("No profile \"{}\"", id));
+    }
+    stored.active_profile = id.to_string();
+    write(app, &stored)
+}
+
+fn write(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let unknown = store
+        .get(SETTINGS_KEY)
+        .and_then(|raw| decode(&raw).ok())
+        .map(|loaded| loaded.unknown)
+        .unwrap_or_default();
+    store.set(SETTINGS_KEY, encode(settings, &unknown)?);
+    store.save().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::{HookAction, HookEvent};
+
+    #[test]
+    fn test_app_settings_default() {
+        let settings = AppSettings::default();
+        assert!(settings.new_chat_default, "new_chat_default should be true");
+        assert!(
+            settings.notifications_enabled,
+            "notifications_enabled should be true"
+        );
+    }
+
+    #[test]
+    fn test_app_settings_serialization_roundtrip() {
+        let settings = AppSettings {
+            new_chat_default: false,
+            inline_answers: true,
+            launcher_backend: LauncherBackend::Api,
+            api: ApiSettings {
+                base_url: "http://localhost:8080/v1".to_string(),
+                ..Default::default()
+            },
+            notifications_enabled: true,
+            navigation: NavigationSettings {
+                allowed_domains: vec!["sso.corp.example".to_string()],
+                open_external: false,
+            },
+            control_socket: true,
+            hooks: vec![Hook {
+                event: HookEvent::ResponseComplete,
+                action: HookAction::Command {
+                    command: "notes append".to_string(),
+                },
+                timeout_secs: 10,
+                enabled: true,
+            }],
+            scheduled_prompts: vec![ScheduledPrompt {
+                name: "Standup".to_string(),
+                schedule: "every weekday 9:00".to_string(),
+                prompt: "Summarise these notes: {file:~/standup.md}".to_string(),
+                enabled: true,
+            }],
+            missed_runs: MissedRuns::Skip,
+            shortcuts: ShortcutSettings {
+                new_chat: "Ctrl+Shift+N".to_string(),
+                ..Default::default()
+            },
+            page_scripts: BTreeMap::from([("connectivity-monitor".to_string(), false)]),
+            profiles: vec![Profile {
+                id: "work".to_string(),
+                name: "Work".to_string(),
+                settings: Default::default(),
+            }],
+            active_profile: "work".to_string(),
+        };
+        let json = serde_json::to_value(&settings).unwrap();
+        let deserialized: AppSettings = serde_json::from_value(json).unwrap();
+        assert!(!deserialized.new_chat_default);
+        assert!(deserialized.inline_answers);
+        assert_eq!(deserialized.launcher_backend, LauncherBackend::Api);
+        assert_eq!(deserialized.api, settings.api);
+        assert!(deserialized.notifications_enabled);
+        assert_eq!(deserialized.navigation, settings.navigation);
+        assert!(deserialized.control_socket);
+        assert_eq!(deserialized.hooks, settings.hooks);
+        assert_eq!(deserialized.scheduled_prompts, settings.scheduled_prompts);
+        assert_eq!(deserialized.missed_runs, MissedRuns::Skip);
+        assert_eq!(deserialized.shortcuts, settings.shortcuts);
+        assert_eq!(deserialized.page_scripts, settings.page_scripts);
+        assert_eq!(deserialized.profiles, settings.profiles);
+        assert_eq!(deserialized.active_profile, "work");
+    }
+
+    #[test]
+    fn test_app_settings_deserialize_missing_fields_uses_defaults() {
+        // Simulate a stored JSON that's missing a field (e.g., after adding a new setting)
+        let json = serde_json::json!({ "new_chat_default": false });
+        let settings: AppSettings = serde_json::from_value(json).unwrap();
+        assert!(!settings.new_chat_default);
+        assert!(!settings.inline_answers);
+        assert_eq!(settings.launcher_backend, LauncherBackend::Webview);
+        assert_eq!(settings.api, ApiSettings::default());
+        assert!(settings.notifications_enabled);
+        assert_eq!(settings.navigation, NavigationSettings::default());
+        assert!(!settings.control_socket, "the control socket is opt-in");
+        assert!(settings.hooks.is_empty());
+        assert!(settings.scheduled_prompts.is_empty());
+        assert_eq!(settings.missed_runs, MissedRuns::CatchUp);
+        assert_eq!(settings.shortcuts, ShortcutSettings::default());
+        assert!(settings.profiles.is_empty());
+        assert_eq!(settings.active_profile, DEFAULT_PROFILE);
+    }
+
+    #[test]
+    fn test_decode_unversioned_settings_migrates() {
+        // Settings stored before the envelope existed
+        let raw = serde_json::json!({
+            "new_chat_default": false,
+            "notifications_enabled": false
+        });
+        let loaded = decode(&raw).unwrap();
+        assert_eq!(loaded.version, 0);
+        assert!(loaded.needs_rewrite());
+        assert!(!loaded.needs_backup());
+        assert!(!loaded.settings.new_chat_default);
+        assert!(!loaded.settings.notifications_enabled);
+    }
+
+    #[test]
+    fn test_decode_current_envelope_needs_no_rewrite() {
+        let raw = encode(&AppSettings::default(), &Map::new()).unwrap();
+        assert_eq!(raw["version"], CURRENT_VERSION);
+        let loaded = decode(&raw).unwrap();
+        assert_eq!(loaded.version, CURRENT_VERSION);
+        assert!(!loaded.needs_rewrite());
+        assert!(loaded.rejected.is_empty());
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_field_only() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION,
+            "settings": {
+                "new_chat_default": "yes please",
+                "notifications_enabled": false
+            }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert_eq!(loaded.rejected, vec!["/new_chat_default"]);
+        assert!(
+            loaded.settings.new_chat_default,
+            "invalid field falls back to default"
+        );
+        assert!(
+            !loaded.settings.notifications_enabled,
+            "valid sibling is kept"
+        );
+        assert!(loaded.needs_backup());
+    }
+
+    #[test]
+    fn test_decode_repairs_nested_fields_individually() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION,
+            "settings": {
+                "shortcuts": { "new_chat": "Ctrl+Shift+N", "stop_generating": 42 }
+            }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert_eq!(loaded.rejected, vec!["/shortcuts/stop_generating"]);
+        assert_eq!(loaded.settings.shortcuts.new_chat, "Ctrl+Shift+N");
+        assert_eq!(loaded.settings.shortcuts.stop_generating, "");
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_backend() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION,
+            "settings": {
+                "launcher_backend": "carrier_pigeon",
+                "api": { "model": "codestral-latest" }
+            }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert_eq!(loaded.rejected, vec!["/launcher_backend"]);
+        assert_eq!(loaded.settings.launcher_backend, LauncherBackend::Webview);
+        assert_eq!(loaded.settings.api.model, "codestral-latest");
+        assert_eq!(
+            loaded.settings.api.base_url,
+            ApiSettings::default().base_url
+        );
+    }
+
+    #[test]
+    fn test_decode_takes_map_fields_whole() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION,
+            "settings": { "page_scripts": { "connectivity-monitor": false } }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert!(loaded.rejected.is_empty());
+        assert_eq!(
+            loaded.settings.page_scripts.get("connectivity-monitor"),
+            Some(&false)
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_hooks() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION,
+            "settings": {
+                "notifications_enabled": false,
+                "hooks": [{ "event": "response-complete", "kind": "telepathy" }]
+            }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert_eq!(loaded.rejected, vec!["/hooks"]);
+        assert!(loaded.settings.hooks.is_empty());
+        assert!(!loaded.settings.notifications_enabled);
+    }
+
+    #[test]
+    fn test_decode_keeps_unknown_fields() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION,
+            "settings": { "new_chat_default": false, "from_the_future": [1, 2] }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert!(loaded.rejected.is_empty());
+        assert_eq!(loaded.unknown["from_the_future"], serde_json::json!([1, 2]));
+
+        let encoded = encode(&loaded.settings, &loaded.unknown).unwrap();
+        assert_eq!(
+            encoded["settings"]["from_the_future"],
+            serde_json::json!([1, 2])
+        );
+        assert_eq!(encoded["settings"]["new_chat_default"], false);
+    }
+
+    #[test]
+    fn test_decode_newer_version_is_backed_up_not_rewritten() {
+        let raw = serde_json::json!({
+            "version": CURRENT_VERSION + 1,
+            "settings": { "new_chat_default": false }
+        });
+        let loaded = decode(&raw).unwrap();
+        assert!(loaded.needs_backup());
+        assert!(!loaded.needs_rewrite());
+        assert!(!loaded.settings.new_chat_default);
+    }
+
+    #[test]
+    fn test_decode_corrupt_values_fail() {
+        assert!(decode(&serde_json::json!("garbage")).is_err());
+        assert!(decode(&serde_json::json!({ "version": "one", "settings": {} })).is_err());
+        assert!(decode(&serde_json::json!({ "version": 1 })).is_err());
+    }
+}
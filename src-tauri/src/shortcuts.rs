@@ -0,0 +1,337 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+// Actions that can be bound to a global shortcut from the settings window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ToggleLauncher,
+    ShowMainWindow,
+    NewChat,
+    StopGenerating,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 4] = [
+        ShortcutAction::ToggleLauncher,
+        ShortcutAction::ShowMainWindow,
+        ShortcutAction::NewChat,
+        ShortcutAction::StopGenerating,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleLauncher => "Toggle quick launcher",
+            ShortcutAction::ShowMainWindow => "Show main window",
+            ShortcutAction::NewChat => "New chat",
+            ShortcutAction::StopGenerating => "Stop generating",
+        }
+    }
+}
+
+// Alt+Space is grabbed by the window menu on most Linux window managers,
+// so Linux gets a different default for the launcher.
+#[cfg(target_os = "linux")]
+pub const DEFAULT_LAUNCHER_SHORTCUT: &str = "Ctrl+Shift+Space";
+#[cfg(not(target_os = "linux"))]
+pub const DEFAULT_LAUNCHER_SHORTCUT: &str = "Alt+Space";
+
+// Candidates offered (and tried, for the launcher) when a binding can't be registered
+const FALLBACK_SHORTCUTS: &[&str] = &[
+    "Ctrl+Alt+Space",
+    "Ctrl+Shift+Space",
+    "Alt+Shift+Space",
+    "Super+Shift+Space",
+    "Ctrl+Alt+K",
+    "Ctrl+Shift+K",
+    "Ctrl+Alt+L",
+];
+
+const MAX_SUGGESTIONS: usize = 3;
+
+// Shortcut strings per action, e.g. "Ctrl+Shift+K". An empty string means unbound.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub toggle_launcher: String,
+    pub show_main_window: String,
+    pub new_chat: String,
+    pub stop_generating: String,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            toggle_launcher: DEFAULT_LAUNCHER_SHORTCUT.to_string(),
+            show_main_window: String::new(),
+            new_chat: String::new(),
+            stop_generating: String::new(),
+        }
+    }
+}
+
+impl ShortcutSettings {
+    pub fn binding(&self, action: ShortcutAction) -> &str {
+        match action {
+            ShortcutAction::ToggleLauncher => &self.toggle_launcher,
+            ShortcutAction::ShowMainWindow => &self.show_main_window,
+            ShortcutAction::NewChat => &self.new_chat,
+            ShortcutAction::StopGenerating => &self.stop_generating,
+        }
+    }
+}
+
+// A binding that could not be registered, reported back to the settings window
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ShortcutFailure {
+    pub action: ShortcutAction,
+    pub shortcut: String,
+    pub error: String,
+    pub suggestions: Vec<String>,
+    // Shortcut registered in place of the requested one, if any
+    pub fallback: Option<String>,
+}
+
+// Failures from the most recent registration pass, so the settings window
+// can show them even if it was opened after startup.
+#[derive(Default)]
+pub struct ShortcutStatus(pub Mutex<Vec<ShortcutFailure>>);
+
+// Parse a user-facing shortcut string. Empty input means "unbound" and yields `None`.
+// A bare key is rejected because it would swallow normal typing system-wide.
+pub fn parse_shortcut(input: &str) -> Result<Option<Shortcut>, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if !trimmed.contains('+') {
+        return Err(format!(
+            "\"{}\" needs at least one modifier (Ctrl, Alt, Shift or Super)",
+            trimmed
+        ));
+    }
+    trimmed
+        .parse::<Shortcut>()
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+// Parse every binding and detect duplicates between actions.
+// Returns the bindings that can be registered plus the ones that can't.
+pub fn resolve_bindings(
+    settings: &ShortcutSettings,
+) -> (Vec<(ShortcutAction, Shortcut)>, Vec<ShortcutFailure>) {
+    let mut bound: Vec<(ShortcutAction, Shortcut)> = Vec::new();
+    let mut failures = Vec::new();
+
+    for action in ShortcutAction::ALL {
+        let raw = settings.binding(action);
+        match parse_shortcut(raw) {
+            Ok(None) => {}
+            Ok(Some(shortcut)) => {
+                if let Some((other, _)) = bound.iter().find(|(_, s)| *s == shortcut) {
+                    failures.push(ShortcutFailure {
+                        action,
+                        shortcut: raw.to_string(),
+                        error: format!("Already bound to \"{}\"", other.label()),
+                        suggestions: Vec::new(),
+                        fallback: None,
+                    });
+                } else {
+                    bound.push((action, shortcut));
+                }
+            }
+            Err(error) => failures.push(ShortcutFailure {
+                action,
+                shortcut: raw.to_string(),
+                error,
+                suggestions: Vec::new(),
+                fallback: None,
+            }),
+        }
+    }
+
+    (bound, failures)
+}
+
+// Pick fallback shortcuts that differ from the requested one and from those already taken.
+// `is_available` lets the caller probe the OS for each candidate.
+pub fn suggest_alternatives(
+    requested: &str,
+    taken: &[Shortcut],
+    mut is_available: impl FnMut(&Shortcut) -> bool,
+) -> Vec<String> {
+    let requested = parse_shortcut(requested).ok().flatten();
+    FALLBACK_SHORTCUTS
+        .iter()
+        .filter_map(|candidate| {
+            let shortcut = parse_shortcut(candidate).ok().flatten()?;
+            if Some(shortcut) == requested || taken.contains(&shortcut) {
+                return None;
+            }
+            is_available(&shortcut).then(|| candidate.to_string())
+        })
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+// (Re-)register all configured shortcuts, replacing any previous registrations.
+// If the launcher shortcut can't be registered, the first working fallback is
+// registered in its place so the app never ends up unreachable.
+pub fn register_shortcuts(
+    app: &AppHandle,
+    settings: &ShortcutSettings,
+    on_action: fn(&AppHandle, ShortcutAction),
+) -> Vec<ShortcutFailure> {
+    let global_shortcut = app.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        eprintln!("Failed to unregister global shortcuts: {}", e);
+    }
+
+    let register = |action: ShortcutAction, shortcut: Shortcut| {
+        let app_handle = app.clone();
+        global_shortcut.on_shortcut(shortcut, move |_app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                on_action(&app_handle, action);
+            }
+        })
+    };
+
+    let (bound, mut failures) = resolve_bindings(settings);
+    let mut taken: Vec<Shortcut> = bound.iter().map(|(_, s)| *s).collect();
+
+    for (action, shortcut) in bound {
+        if let Err(e) = register(action, shortcut) {
+            failures.push(ShortcutFailure {
+                action,
+                shortcut: settings.binding(action).to_string(),
+                error: e.to_string(),
+                suggestions: Vec::new(),
+                fallback: None,
+            });
+        }
+    }
+
+    for failure in &mut failures {
+        // Probe each candidate by registering and immediately releasing it
+        failure.suggestions = suggest_alternatives(&failure.shortcut, &taken, |candidate| {
+            global_shortcut.register(*candidate).is_ok()
+                && global_shortcut.unregister(*candidate).is_ok()
+        });
+
+        if failure.action == ShortcutAction::ToggleLauncher {
+            for suggestion in &failure.suggestions {
+                let Ok(Some(shortcut)) = parse_shortcut(suggestion) else {
+                    continue;
+                };
+                if register(failure.action, shortcut).is_ok() {
+                    taken.push(shortcut);
+                    failure.fallback = Some(suggestion.clone());
+                    break;
+                }
+            }
+        }
+    }
+
+    for failure in &failures {
+        eprintln!(
+            "Failed to register shortcut \"{}\" for {}: {}",
+            failure.shortcut,
+            failure.action.label(),
+            failure.error
+        );
+    }
+
+    if let Some(status) = app.try_state::<ShortcutStatus>() {
+        *status.0.lock().unwrap() = failures.clone();
+    }
+
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shortcut_with_modifiers() {
+        let shortcut = parse_shortcut("Ctrl+Shift+K").unwrap();
+        assert_eq!(shortcut, "control+shift+KeyK".parse::<Shortcut>().ok());
+    }
+
+    #[test]
+    fn test_parse_shortcut_tolerates_spaces() {
+        assert!(parse_shortcut(" Alt + Space ").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_parse_shortcut_empty_is_unbound() {
+        assert_eq!(parse_shortcut("").unwrap(), None);
+        assert_eq!(parse_shortcut("   ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_shortcut_rejects_bare_key() {
+        let err = parse_shortcut("K").unwrap_err();
+        assert!(err.contains("modifier"));
+    }
+
+    #[test]
+    fn test_parse_shortcut_rejects_unknown_key() {
+        assert!(parse_shortcut("Ctrl+Banana").is_err());
+    }
+
+    #[test]
+    fn test_default_launcher_shortcut_parses() {
+        assert!(parse_shortcut(DEFAULT_LAUNCHER_SHORTCUT).unwrap().is_some());
+        assert!(FALLBACK_SHORTCUTS
+            .iter()
+            .all(|s| parse_shortcut(s).unwrap().is_some()));
+    }
+
+    #[test]
+    fn test_resolve_bindings_detects_duplicates() {
+        let settings = ShortcutSettings {
+            toggle_launcher: "Ctrl+Shift+K".to_string(),
+            new_chat: "ctrl + shift + k".to_string(),
+            ..Default::default()
+        };
+        let (bound, failures) = resolve_bindings(&settings);
+        assert_eq!(bound.len(), 1);
+        assert_eq!(bound[0].0, ShortcutAction::ToggleLauncher);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].action, ShortcutAction::NewChat);
+        assert!(failures[0].error.contains("Toggle quick launcher"));
+    }
+
+    #[test]
+    fn test_resolve_bindings_reports_parse_errors() {
+        let settings = ShortcutSettings {
+            stop_generating: "Ctrl+".to_string(),
+            ..Default::default()
+        };
+        let (bound, failures) = resolve_bindings(&settings);
+        assert_eq!(bound.len(), 1);
+        assert_eq!(failures[0].action, ShortcutAction::StopGenerating);
+    }
+
+    #[test]
+    fn test_suggest_alternatives_skips_requested_and_taken() {
+        let taken = vec![parse_shortcut("Ctrl+Alt+Space").unwrap().unwrap()];
+        let suggestions = suggest_alternatives("Ctrl+Shift+Space", &taken, |_| true);
+        assert_eq!(
+            suggestions,
+            vec!["Alt+Shift+Space", "Super+Shift+Space", "Ctrl+Alt+K"]
+        );
+    }
+
+    #[test]
+    fn test_suggest_alternatives_respects_availability() {
+        let suggestions = suggest_alternatives("Alt+Space", &[], |s| {
+            *s == parse_shortcut("Ctrl+Alt+L").unwrap().unwrap()
+        });
+        assert_eq!(suggestions, vec!["Ctrl+Alt+L"]);
+    }
+}
@@ -0,0 +1,696 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::{oneshot, watch};
+
+// How many finished submissions are kept for the launcher's status display
+const MAX_RECENT: usize = 20;
+const PREVIEW_CHARS: usize = 80;
+
+// After a message is sent, how long to wait for Le Chat to start streaming
+// before assuming there is no response to wait for
+pub const STREAM_START_GRACE: Duration = Duration::from_secs(5);
+
+// Lifecycle of a launcher submission. Statuses only move forward;
+// `Answered` and `Failed` are terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    // Accepted by Rust, waiting for the main window
+    Queued,
+    // Injection script evaluated in the main window
+    Injected,
+    // The page confirmed the message was typed and sent
+    Sent,
+    // Le Chat finished responding
+    Answered,
+    Failed,
+    // Removed from the queue before it was sent
+    Cancelled,
+    // Couldn't be sent while offline; moved to the outbox to be resent later
+    Outboxed,
+}
+
+impl SubmissionStatus {
+    fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            SubmissionStatus::Answered
+                | SubmissionStatus::Failed
+                | SubmissionStatus::Cancelled
+                | SubmissionStatus::Outboxed
+        )
+    }
+
+    fn can_transition_to(self, next: SubmissionStatus) -> bool {
+        match next {
+            SubmissionStatus::Cancelled => self == SubmissionStatus::Queued,
+            SubmissionStatus::Failed => !self.is_terminal(),
+            _ => !self.is_terminal() && next > self,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Submission {
+    pub id: u64,
+    // Start of the message, for display
+    pub preview: String,
+    pub new_chat: bool,
+    // Reply is shown in the launcher instead of the main window
+    pub inline: bool,
+    pub status: SubmissionStatus,
+    pub error: Option<String>,
+}
+
+type DeliveryResult = Result<(), String>;
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    submissions: VecDeque<Submission>,
+    // Pending `inject-result` waiters and the window they expect it from,
+    // keyed by submission id
+    deliveries: HashMap<u64, (String, oneshot::Sender<DeliveryResult>)>,
+    // Window whose response watcher follows each submission's reply
+    responses: HashMap<u64, String>,
+}
+
+// Tracks launcher submissions by correlation id, from `submit_message`
+// through the injected scripts' `inject-result` and `response-complete` reports.
+#[derive(Default)]
+pub struct SubmissionTracker {
+    inner: Mutex<Inner>,
+}
+
+impl SubmissionTracker {
+    pub fn create(&self, message: &str, new_chat: bool, inline: bool) -> Submission {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let submission = Submission {
+            id: inner.next_id,
+            preview: preview(message),
+            new_chat,
+            inline,
+            status: SubmissionStatus::Queued,
+            error: None,
+        };
+        inner.submissions.push_back(submission.clone());
+
+        // Drop the oldest finished submissions; in-flight ones are always kept
+        while inner.submissions.len() > MAX_RECENT {
+            let Some(index) = inner
+                .submissions
+                .iter()
+                .position(|s| s.status.is_terminal())
+            else {
+                break;
+            };
+            if let Some(removed) = inner.submissions.remove(index) {
+                inner.responses.remove(&removed.id);
+            }
+        }
+        submission
+    }
+
+    // Move a submission to `status`. Returns the updated submission, or `None`
+    // if it is unknown or the transition would go backwards.
+    pub fn update(
+        &self,
+        id: u64,
+        status: SubmissionStatus,
+        error: Option<String>,
+    ) -> Option<Submission> {
+        let mut inner = self.inner.lock().unwrap();
+        let submission = inner.submissions.iter_mut().find(|s| s.id == id)?;
+        if !submission.status.can_transition_to(status) {
+            return None;
+        }
+        submission.status = status;
+        submission.error = error;
+        Some(submission.clone())
+    }
+
+    pub fn get(&self, id: u64) -> Option<Submission> {
+        let inner = self.inner.lock().unwrap();
+        inner.submissions.iter().find(|s| s.id == id).cloned()
+    }
+
+    // Submissions that haven't been sent yet: waiting in the queue, on their
+    // way to the page or being typed in
+    pub fn unsent(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .submissions
+            .iter()
+            .filter(|s| {
+                matches!(
+                    s.status,
+                    SubmissionStatus::Queued | SubmissionStatus::Injected
+                )
+            })
+            .count()
+    }
+
+    pub fn recent(&self) -> Vec<Submission> {
+        let inner = self.inner.lock().unwrap();
+        inner.submissions.iter().cloned().collect()
+    }
+
+    // Register interest in the `inject-result` of a submission, which only
+    // `window` may report. Must be called before the injection script is evaluated.
+    pub fn expect_delivery(&self, id: u64, window: &str) -> oneshot::Receiver<DeliveryResult> {
+        let (sender, receiver) = oneshot::channel();
+        self.inner
+            .lock()
+            .unwrap()
+            .deliveries
+            .insert(id, (window.to_string(), sender));
+        receiver
+    }
+
+    pub fn resolve_delivery(&self, id: u64, result: DeliveryResult) {
+        let delivery = self.inner.lock().unwrap().deliveries.remove(&id);
+        if let Some((_, sender)) = delivery {
+            let _ = sender.send(result);
+        }
+    }
+
+    // An `inject-result` from `window`. Returns false, leaving the waiter in
+    // place, unless a delivery of `id` to that window is pending.
+    pub fn report_delivery(&self, id: u64, window: &str, result: DeliveryResult) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.deliveries.get(&id) {
+            Some((expected, _)) if expected == window => {}
+            _ => return false,
+        }
+        if let Some((_, sender)) = inner.deliveries.remove(&id) {
+            let _ = sender.send(result);
+        }
+        true
+    }
+
+    // A response watcher for `id` was injected into `window`
+    pub fn watch_response(&self, id: u64, window: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .responses
+            .insert(id, window.to_string());
+    }
+
+    // Whether `window` is following the reply to `id`
+    pub fn is_watching(&self, id: u64, window: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .responses
+            .get(&id)
+            .is_some_and(|expected| expected == window)
+    }
+
+    // The reply to `id` finished in `window`. Returns true once; later or
+    // misdirected reports get false.
+    pub fn finish_response(&self, id: u64, window: &str) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.responses.get(&id) {
+            Some(expected) if expected == window => {}
+            _ => return false,
+        }
+        inner.responses.remove(&id);
+        true
+    }
+
+    // Stop following the reply to `id`, e.g. when it could not be sent
+    pub fn stop_watching(&self, id: u64) {
+        self.inner.lock().unwrap().responses.remove(&id);
+    }
+
+    // `window` navigated, taking its response watchers with it. Returns the
+    // submissions whose replies it was following, which won't be reported now.
+    pub fn abandon_responses(&self, window: &str) -> Vec<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut ids: Vec<u64> = inner
+            .responses
+            .iter()
+            .filter(|(_, watching)| *watching == window)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        for id in &ids {
+            inner.responses.remove(id);
+        }
+        ids
+    }
+}
+
+// A submission waiting in the queue for the main window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedMessage {
+    pub id: u64,
+    pub message: String,
+    pub new_chat: bool,
+    // Deliver without bringing the main window forward
+    pub inline: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ChatActivity {
+    // The page shows a stop button
+    streaming: bool,
+    // A message was sent and its response hasn't started streaming yet
+    awaiting_response: Option<u64>,
+    // A sent message whose reply the main window is still following
+    following: Option<u64>,
+}
+
+impl ChatActivity {
+    fn is_busy(&self) -> bool {
+        self.streaming || self.awaiting_response.is_some()
+    }
+}
+
+// Holds launcher prompts until the main window can take them. Messages are sent
+// in order; a message that continues the current chat waits until Le Chat has
+// finished responding, so it never interrupts a turn in progress. A message
+// for a new chat only waits for replies the app is following, since opening
+// the new chat would end them.
+pub struct SubmissionQueue {
+    pending: watch::Sender<VecDeque<QueuedMessage>>,
+    activity: watch::Sender<ChatActivity>,
+}
+
+impl Default for SubmissionQueue {
+    fn default() -> Self {
+        Self {
+            pending: watch::Sender::new(VecDeque::new()),
+            activity: watch::Sender::new(ChatActivity::default()),
+        }
+    }
+}
+
+impl SubmissionQueue {
+    pub fn push(&self, message: QueuedMessage) {
+        self.pending
+            .send_modify(|pending| pending.push_back(message));
+    }
+
+    // Remove a message that hasn't been taken by `next()` yet
+    pub fn cancel(&self, id: u64) -> bool {
+        self.pending.send_if_modified(|pending| {
+            let before = pending.len();
+            pending.retain(|m| m.id != id);
+            pending.len() != before
+        })
+    }
+
+    // Ids of the messages still waiting, oldest first
+    pub fn pending_ids(&self) -> Vec<u64> {
+        self.pending.borrow().iter().map(|m| m.id).collect()
+    }
+
+    // Le Chat is answering, or about to answer, a message that was sent, or
+    // the reply to one is still being followed
+    pub fn is_busy(&self) -> bool {
+        let activity = self.activity.borrow();
+        activity.is_busy() || activity.following.is_some()
+    }
+
+    pub fn set_streaming(&self, streaming: bool) {
+        self.activity.send_if_modified(|activity| {
+            let before = *activity;
+            activity.streaming = streaming;
+            if streaming {
+                activity.awaiting_response = None;
+            }
+            *activity != before
+        });
+    }
+
+    // Message `id` was sent; hold the next one until its response starts
+    pub fn expect_response(&self, id: u64) {
+        self.activity
+            .send_modify(|activity| activity.awaiting_response = Some(id));
+    }
+
+    // The main window follows the reply to `id` until `stop_following(id)`
+    pub fn follow_response(&self, id: u64) {
+        self.activity
+            .send_modify(|activity| activity.following = Some(id));
+    }
+
+    // The reply to `id` finished, or will never be reported
+    pub fn stop_following(&self, id: u64) {
+        self.activity.send_if_modified(|activity| {
+            if activity.following != Some(id) {
+                return false;
+            }
+            activity.following = None;
+            true
+        });
+    }
+
+    // Stop waiting for the response to `id` to start, e.g. after `STREAM_START_GRACE`
+    // or when the response watcher already saw it complete
+    pub fn clear_expected_response(&self, id: u64) {
+        self.activity.send_if_modified(|activity| {
+            if activity.awaiting_response != Some(id) {
+                return false;
+            }
+            activity.awaiting_response = None;
+            true
+        });
+    }
+
+    // Wait for the next message that can be sent now and take it off the queue
+    pub async fn next(&self) -> QueuedMessage {
+        let mut pending = self.pending.subscribe();
+        let mut activity = self.activity.subscribe();
+        loop {
+            let head = pending.borrow_and_update().front().cloned();
+            let (busy, following) = {
+                let activity = activity.borrow_and_update();
+                (activity.is_busy(), activity.following.is_some())
+            };
+            if let Some(head) = head {
+                let ready = if head.new_chat { !following } else { !busy };
+                if ready {
+                    let taken = self.pending.send_if_modified(|pending| {
+                        if pending.front().map(|m| m.id) != Some(head.id) {
+                            return false;
+                        }
+                        pending.pop_front();
+                        true
+                    });
+                    if taken {
+                        return head;
+                    }
+                    continue;
+                }
+            }
+            // Both senders live in `self`, so `changed()` can't fail here
+            tokio::select! {
+                _ = pending.changed() => {}
+                _ = activity.changed() => {}
+            }
+        }
+    }
+}
+
+pub fn preview(message: &str) -> String {
+    let mut chars = message.chars();
+    let mut preview: String = chars.by_ref().take(PREVIEW_CHARS).collect();
+    if chars.next().is_some() {
+        preview.push('…');
+    }
+    preview
+}
+
+// What the injection script reports through `report_inject_result`, passed on
+// as the `inject-result` event
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct InjectResult {
+    pub id: Option<u64>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// What the response watcher reports through `report_response_progress` while
+// an inline answer streams in
+#[derive(Debug, serde::Deserialize)]
+pub struct ResponseProgress {
+    pub id: u64,
+    pub html: String,
+    #[serde(default)]
+    pub url: String,
+}
+
+// What the response watcher reports through `report_response_complete`, passed
+// on as the `response-complete` event
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ResponseComplete {
+    pub id: Option<u64>,
+    // innerHTML of the last assistant message, if it could be found
+    #[serde(default)]
+    pub html: Option<String>,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_are_unique_and_increasing() {
+        let tracker = SubmissionTracker::default();
+        let first = tracker.create("one", true, false);
+        let second = tracker.create("two", false, false);
+        assert!(second.id > first.id);
+        assert_eq!(first.status, SubmissionStatus::Queued);
+        assert!(!second.new_chat);
+    }
+
+    #[test]
+    fn test_status_only_moves_forward() {
+        let tracker = SubmissionTracker::default();
+        let id = tracker.create("hello", true, false).id;
+        assert!(tracker.update(id, SubmissionStatus::Sent, None).is_some());
+        assert!(tracker
+            .update(id, SubmissionStatus::Injected, None)
+            .is_none());
+        assert!(tracker
+            .update(id, SubmissionStatus::Answered, None)
+            .is_some());
+        assert!(tracker
+            .update(id, SubmissionStatus::Failed, Some("late".into()))
+            .is_none());
+        assert_eq!(tracker.get(id).unwrap().status, SubmissionStatus::Answered);
+    }
+
+    #[test]
+    fn test_only_queued_submissions_can_be_cancelled() {
+        let tracker = SubmissionTracker::default();
+        let queued = tracker.create("queued", false, false).id;
+        let sent = tracker.create("sent", false, false).id;
+        tracker.update(sent, SubmissionStatus::Sent, None);
+        assert!(tracker
+            .update(queued, SubmissionStatus::Cancelled, None)
+            .is_some());
+        assert!(tracker
+            .update(sent, SubmissionStatus::Cancelled, None)
+            .is_none());
+        assert!(tracker
+            .update(queued, SubmissionStatus::Injected, None)
+            .is_none());
+    }
+
+    #[test]
+    fn test_failure_records_error() {
+        let tracker = SubmissionTracker::default();
+        let id = tracker.create("hello", true, false).id;
+        let failed = tracker
+            .update(id, SubmissionStatus::Failed, Some("no textarea".into()))
+            .unwrap();
+        assert_eq!(failed.error.as_deref(), Some("no textarea"));
+    }
+
+    #[test]
+    fn test_overlapping_submissions_are_tracked_separately() {
+        let tracker = SubmissionTracker::default();
+        let a = tracker.create("first", true, false).id;
+        let b = tracker.create("second", false, false).id;
+        tracker.update(b, SubmissionStatus::Failed, Some("timeout".into()));
+        tracker.update(a, SubmissionStatus::Sent, None);
+        assert_eq!(tracker.get(a).unwrap().status, SubmissionStatus::Sent);
+        assert_eq!(tracker.get(b).unwrap().status, SubmissionStatus::Failed);
+    }
+
+    #[test]
+    fn test_delivery_is_routed_by_id() {
+        let tracker = SubmissionTracker::default();
+        let mut a = tracker.expect_delivery(1, "main");
+        let mut b = tracker.expect_delivery(2, "main");
+        tracker.resolve_delivery(2, Err("failed".into()));
+        assert_eq!(b.try_recv().unwrap(), Err("failed".to_string()));
+        assert!(a.try_recv().is_err(), "other submission is still pending");
+        tracker.resolve_delivery(1, Ok(()));
+        assert_eq!(a.try_recv().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_only_count_from_the_expected_window() {
+        let tracker = SubmissionTracker::default();
+        let mut delivery = tracker.expect_delivery(4, "scheduled-4");
+        assert!(!tracker.report_delivery(4, "main", Ok(())));
+        assert!(!tracker.report_delivery(5, "scheduled-4", Ok(())));
+        assert!(delivery.try_recv().is_err(), "still waiting");
+        assert!(tracker.report_delivery(4, "scheduled-4", Ok(())));
+        assert_eq!(delivery.try_recv().unwrap(), Ok(()));
+        assert!(!tracker.report_delivery(4, "scheduled-4", Ok(())));
+
+        tracker.watch_response(4, "main");
+        assert!(tracker.is_watching(4, "main"));
+        assert!(!tracker.is_watching(4, "private"));
+        assert!(!tracker.finish_response(4, "scheduled-4"));
+        assert!(!tracker.finish_response(9, "main"));
+        assert!(tracker.finish_response(4, "main"));
+        assert!(!tracker.finish_response(4, "main"), "only once");
+
+        // Navigating away ends the watchers in that window only
+        tracker.watch_response(5, "main");
+        tracker.watch_response(6, "scheduled-6");
+        tracker.watch_response(7, "main");
+        assert_eq!(tracker.abandon_responses("main"), vec![5, 7]);
+        assert!(!tracker.is_watching(5, "main"));
+        assert!(tracker.is_watching(6, "scheduled-6"));
+        assert!(!tracker.is_watching(4, "main"));
+    }
+
+    #[test]
+    fn test_unsent_counts_submissions_not_yet_sent() {
+        let tracker = SubmissionTracker::default();
+        let queued = tracker.create("queued", false, false).id;
+        let injected = tracker.create("injected", true, false).id;
+        let sent = tracker.create("sent", true, false).id;
+        tracker.update(injected, SubmissionStatus::Injected, None);
+        tracker.update(sent, SubmissionStatus::Sent, None);
+        assert_eq!(tracker.unsent(), 2);
+        tracker.update(queued, SubmissionStatus::Cancelled, None);
+        tracker.update(injected, SubmissionStatus::Failed, Some("x".into()));
+        assert_eq!(tracker.unsent(), 0);
+    }
+
+    #[test]
+    fn test_recent_keeps_in_flight_submissions() {
+        let tracker = SubmissionTracker::default();
+        let pending = tracker.create("pending", true, false).id;
+        for i in 0..MAX_RECENT + 5 {
+            let id = tracker.create(&format!("msg {}", i), true, false).id;
+            tracker.update(id, SubmissionStatus::Answered, None);
+        }
+        let recent = tracker.recent();
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert!(recent.iter().any(|s| s.id == pending));
+    }
+
+    #[test]
+    fn test_preview_truncates_long_messages() {
+        let long = "é".repeat(PREVIEW_CHARS + 10);
+        let p = preview(&long);
+        assert_eq!(p.chars().count(), PREVIEW_CHARS + 1);
+        assert!(p.ends_with('…'));
+        assert_eq!(preview("short"), "short");
+    }
+
+    #[test]
+    fn test_event_payloads_deserialize() {
+        let result: InjectResult =
+            serde_json::from_value(serde_json::json!({ "id": 3, "success": false, "error": "x" }))
+                .unwrap();
+        assert_eq!(result.id, Some(3));
+        assert!(!result.success);
+        let complete: ResponseComplete = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(complete.id, None);
+    }
+
+    fn queued(id: u64, new_chat: bool) -> QueuedMessage {
+        QueuedMessage {
+            id,
+            message: format!("message {}", id),
+            new_chat,
+            inline: false,
+        }
+    }
+
+    async fn next_within(queue: &SubmissionQueue, ms: u64) -> Option<QueuedMessage> {
+        tokio::time::timeout(Duration::from_millis(ms), queue.next())
+            .await
+            .ok()
+    }
+
+    #[tokio::test]
+    async fn test_queue_sends_in_order() {
+        let queue = SubmissionQueue::default();
+        queue.push(queued(1, true));
+        queue.push(queued(2, true));
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 1);
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 2);
+        assert!(next_within(&queue, 10).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_continue_chat_waits_for_streaming_to_finish() {
+        let queue = std::sync::Arc::new(SubmissionQueue::default());
+        queue.set_streaming(true);
+        queue.push(queued(1, false));
+        assert!(next_within(&queue, 10).await.is_none());
+        assert_eq!(queue.pending_ids(), vec![1]);
+
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.next().await })
+        };
+        tokio::task::yield_now().await;
+        queue.set_streaming(false);
+        assert_eq!(waiter.await.unwrap().id, 1);
+        assert!(queue.pending_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_new_chat_waits_for_followed_replies_only() {
+        let queue = SubmissionQueue::default();
+        // A reply the user asked for in the page itself
+        queue.set_streaming(true);
+        queue.push(queued(1, true));
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 1);
+
+        // The reply to a submission, which opening a new chat would end
+        queue.follow_response(1);
+        queue.set_streaming(false);
+        queue.push(queued(2, true));
+        assert!(next_within(&queue, 10).await.is_none());
+        assert!(queue.is_busy());
+        queue.stop_following(7);
+        assert!(next_within(&queue, 10).await.is_none());
+        queue.stop_following(1);
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 2);
+        assert!(!queue.is_busy());
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_sent_message_response_to_start() {
+        let queue = SubmissionQueue::default();
+        queue.expect_response(1);
+        queue.push(queued(2, false));
+        assert!(next_within(&queue, 10).await.is_none());
+
+        // Streaming started and finished
+        queue.set_streaming(true);
+        assert!(next_within(&queue, 10).await.is_none());
+        queue.set_streaming(false);
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 2);
+
+        // No response ever started
+        queue.expect_response(2);
+        queue.push(queued(3, false));
+        queue.clear_expected_response(1);
+        assert!(next_within(&queue, 10).await.is_none());
+        queue.clear_expected_response(2);
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_removes_waiting_message() {
+        let queue = SubmissionQueue::default();
+        queue.set_streaming(true);
+        queue.push(queued(1, false));
+        queue.push(queued(2, true));
+        assert!(queue.cancel(1));
+        assert!(!queue.cancel(1));
+        assert_eq!(next_within(&queue, 10).await.unwrap().id, 2);
+    }
+}
@@ -0,0 +1,162 @@
+// Resolves the app's capabilities the way Tauri does at startup, from the ACL
+// manifests and capabilities that `tauri-build` writes to gen/schemas, and
+// checks what each window's pages may invoke.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use tauri::Url;
+use tauri_utils::acl::capability::Capability;
+use tauri_utils::acl::manifest::Manifest;
+use tauri_utils::acl::resolved::{Resolved, ResolvedCommand};
+use tauri_utils::acl::ExecutionContext;
+use tauri_utils::platform::Target;
+
+const CHAT: &str = "https://chat.mistral.ai/chat/abc";
+const LOCAL: &str = "tauri://localhost/index.html";
+
+fn resolved() -> Resolved {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("gen/schemas");
+    let read = |name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .unwrap_or_else(|e| panic!("{} should be written by the build script: {}", name, e))
+    };
+    let manifests: BTreeMap<String, Manifest> =
+        serde_json::from_str(&read("acl-manifests.json")).unwrap();
+    let capabilities: BTreeMap<String, Capability> =
+        serde_json::from_str(&read("capabilities.json")).unwrap();
+    Resolved::resolve(&manifests, capabilities, Target::current()).unwrap()
+}
+
+// Whether a page at `url` in `window` may invoke `command`
+fn allowed(resolved: &Resolved, command: &str, window: &str, url: &str) -> bool {
+    let url = Url::parse(url).unwrap();
+    let is_local = url.scheme() == "tauri";
+    let applies = |commands: &BTreeMap<String, Vec<ResolvedCommand>>| {
+        commands.get(command).is_some_and(|entries| {
+            entries.iter().any(|entry| {
+                let context = match &entry.context {
+                    ExecutionContext::Local => is_local,
+                    ExecutionContext::Remote { url: pattern } => pattern.test(&url),
+                };
+                context && entry.windows.iter().any(|pattern| pattern.matches(window))
+            })
+        })
+    };
+    applies(&resolved.allowed_commands) && !applies(&resolved.denied_commands)
+}
+
+#[test]
+fn app_commands_are_access_controlled() {
+    assert!(resolved().has_app_acl);
+}
+
+#[test]
+fn remote_origin_cannot_change_settings() {
+    let resolved = resolved();
+    assert!(!allowed(&resolved, "save_settings", "main", CHAT));
+    assert!(!allowed(&resolved, "save_settings", "scheduled-3", CHAT));
+    assert!(allowed(&resolved, "save_settings", "settings", LOCAL));
+
+    for command in [
+        "get_settings",
+        "save_api_key",
+        "submit_message",
+        "plugin:shell|open",
+        "plugin:store|set",
+        "plugin:global-shortcut|register",
+        "plugin:opener|reveal_item_in_dir",
+        "plugin:event|listen",
+    ] {
+        assert!(
+            !allowed(&resolved, command, "main", CHAT),
+            "the chat page must not be able to call {}",
+            command
+        );
+    }
+}
+
+#[test]
+fn remote_origin_keeps_what_the_injected_scripts_need() {
+    let resolved = resolved();
+    for window in ["main", "scheduled-1"] {
+        for command in [
+            "report_inject_result",
+            "report_response_progress",
+            "report_response_complete",
+            "report_response_failed",
+            "navigate_to_offline",
+        ] {
+            assert!(allowed(&resolved, command, window, CHAT), "{}", command);
+        }
+    }
+    assert!(allowed(&resolved, "report_heartbeat", "main", LOCAL));
+    assert!(allowed(&resolved, "report_dom_ready", "main", LOCAL));
+    // Only Le Chat itself, not other sites the window might end up on
+    assert!(!allowed(
+        &resolved,
+        "report_response_complete",
+        "main",
+        "https://example.com/"
+    ));
+    assert!(!allowed(&resolved, "navigate_to_offline", "launcher", CHAT));
+}
+
+#[test]
+fn remote_origin_cannot_send_app_events_or_open_urls() {
+    let resolved = resolved();
+    for window in ["main", "scheduled-1"] {
+        for command in [
+            "plugin:event|emit",
+            "plugin:event|emit_to",
+            "plugin:opener|open_url",
+        ] {
+            assert!(
+                !allowed(&resolved, command, window, CHAT),
+                "the chat page must not be able to call {}",
+                command
+            );
+        }
+    }
+    assert!(!allowed(&resolved, "plugin:event|emit", "main", LOCAL));
+}
+
+#[test]
+fn local_windows_get_their_own_commands() {
+    let resolved = resolved();
+    assert!(allowed(&resolved, "submit_message", "launcher", LOCAL));
+    assert!(allowed(&resolved, "plugin:event|listen", "launcher", LOCAL));
+    assert!(allowed(
+        &resolved,
+        "plugin:opener|open_url",
+        "launcher",
+        LOCAL
+    ));
+    assert!(allowed(&resolved, "switch_profile", "launcher", LOCAL));
+    assert!(allowed(&resolved, "cancel_submission", "launcher", LOCAL));
+    assert!(!allowed(&resolved, "switch_profile", "main", CHAT));
+    assert!(!allowed(&resolved, "save_api_key", "launcher", LOCAL));
+    assert!(!allowed(&resolved, "submit_message", "settings", LOCAL));
+    assert!(allowed(&resolved, "navigate_to_chat", "main", LOCAL));
+    assert!(!allowed(&resolved, "save_settings", "main", LOCAL));
+}
+
+#[test]
+fn sign_in_popups_and_the_private_window_get_no_commands() {
+    let resolved = resolved();
+    for window in ["sign-in-1", "private"] {
+        for command in [
+            "plugin:event|emit",
+            "navigate_to_offline",
+            "plugin:opener|open_url",
+            "save_settings",
+        ] {
+            assert!(
+                !allowed(&resolved, command, window, CHAT),
+                "{} must not be able to call {}",
+                window,
+                command
+            );
+        }
+    }
+}
@@ -0,0 +1,261 @@
+// Talks to the control socket the way an editor plugin or shell script would:
+// newline-delimited JSON over a plain Unix socket, no client library.
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use le_chat_lib::control::{self, ControlEvent, ControlHandler, ControlMethod};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, watch};
+
+// Records calls and answers like the app would
+#[derive(Default)]
+struct FakeApp {
+    calls: Mutex<Vec<ControlMethod>>,
+}
+
+impl ControlHandler for FakeApp {
+    async fn call(&self, method: ControlMethod) -> Result<Value, String> {
+        self.calls.lock().unwrap().push(method.clone());
+        match method {
+            ControlMethod::GetSettings => Ok(json!({ "new_chat_default": true })),
+            ControlMethod::SubmitMessage { message, .. } if message.is_empty() => {
+                Err("Message is empty".to_string())
+            }
+            ControlMethod::SubmitMessage { .. } => Ok(json!({ "id": 1, "status": "queued" })),
+            _ => Ok(Value::Null),
+        }
+    }
+}
+
+struct Server {
+    dir: PathBuf,
+    path: PathBuf,
+    app: Arc<FakeApp>,
+    events: broadcast::Sender<ControlEvent>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("le-chat-{}-{}", name, std::process::id()))
+}
+
+fn start(name: &str) -> Server {
+    let dir = temp_dir(name);
+    let path = dir.join("control").join("le-chat.sock");
+    let listener = control::bind(&path).unwrap();
+    let app = Arc::new(FakeApp::default());
+    let (events, _) = broadcast::channel(control::EVENT_BUFFER);
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    tokio::spawn(control::serve(
+        listener,
+        app.clone(),
+        events.clone(),
+        shutdown_rx,
+    ));
+    Server {
+        dir,
+        path,
+        app,
+        events,
+        shutdown,
+    }
+}
+
+struct Client {
+    stream: UnixStream,
+    reader: BufReader<UnixStream>,
+}
+
+impl Client {
+    fn connect(path: &Path) -> Self {
+        let stream = UnixStream::connect(path).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Self { stream, reader }
+    }
+
+    fn send(&mut self, line: &str) {
+        self.stream.write_all(line.as_bytes()).unwrap();
+        self.stream.write_all(b"\n").unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn call(&mut self, id: u64, method: &str, params: Value) -> Value {
+        self.send(
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string(),
+        );
+        let response = self.receive();
+        assert_eq!(response["id"], json!(id));
+        response
+    }
+}
+
+// Runs blocking client code without stalling the server
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(f).await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn socket_is_private_to_the_user() {
+    let server = start("private");
+    let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&server.path), 0o600);
+    assert_eq!(mode(server.path.parent().unwrap()), 0o700);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn methods_mirror_the_tauri_commands() {
+    let server = start("methods");
+    let path = server.path.clone();
+    blocking(move || {
+        let mut client = Client::connect(&path);
+        assert_eq!(
+            client.call(1, "get_settings", Value::Null)["result"],
+            json!({ "new_chat_default": true })
+        );
+        assert_eq!(
+            client.call(
+                2,
+                "submit_message",
+                json!({ "message": "Hello", "new_chat": true, "inline": true })
+            )["result"]["status"],
+            "queued"
+        );
+        assert_eq!(
+            client.call(3, "show_launcher", json!({}))["result"],
+            Value::Null
+        );
+        client.call(4, "toggle_launcher", Value::Null);
+        client.call(5, "navigate_to_chat", Value::Null);
+    })
+    .await;
+
+    assert_eq!(
+        *server.app.calls.lock().unwrap(),
+        vec![
+            ControlMethod::GetSettings,
+            ControlMethod::SubmitMessage {
+                message: "Hello".to_string(),
+                new_chat: true,
+                inline: Some(true),
+            },
+            ControlMethod::ShowLauncher,
+            ControlMethod::ToggleLauncher,
+            ControlMethod::NavigateToChat,
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn errors_follow_json_rpc() {
+    let server = start("errors");
+    let path = server.path.clone();
+    blocking(move || {
+        let mut client = Client::connect(&path);
+        client.send("{oops");
+        assert_eq!(client.receive()["error"]["code"], -32700);
+
+        // Only methods mirrored from the UI are reachable
+        let response = client.call(1, "save_settings", json!({ "settings": {} }));
+        assert_eq!(response["error"]["code"], -32601);
+
+        let response = client.call(2, "submit_message", json!({ "text": "Hi" }));
+        assert_eq!(response["error"]["code"], -32602);
+
+        let response = client.call(
+            3,
+            "submit_message",
+            json!({ "message": "", "new_chat": false }),
+        );
+        assert_eq!(response["error"]["code"], -32000);
+        assert_eq!(response["error"]["message"], "Message is empty");
+
+        // Notifications get no response; the next line answers the next request
+        client.send(r#"{"jsonrpc":"2.0","method":"toggle_launcher"}"#);
+        client.call(4, "get_settings", Value::Null);
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribed_events_are_streamed() {
+    let server = start("events");
+    let path = server.path.clone();
+    let mut client = blocking(move || {
+        let mut client = Client::connect(&path);
+        let response = client.call(1, "subscribe", json!({ "events": ["response-complete"] }));
+        assert_eq!(response["result"], json!(["response-complete"]));
+        client
+    })
+    .await;
+
+    for (event, id) in [("inject-result", 1), ("response-complete", 2)] {
+        server
+            .events
+            .send(ControlEvent {
+                event: event.to_string(),
+                payload: json!({ "id": id }),
+            })
+            .unwrap();
+    }
+
+    client = blocking(move || {
+        // Only the subscribed event arrives
+        assert_eq!(
+            client.receive(),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "event",
+                "params": { "event": "response-complete", "payload": { "id": 2 } },
+            })
+        );
+        let response = client.call(2, "subscribe", json!({ "events": ["page-dom-ready"] }));
+        assert_eq!(response["error"]["code"], -32602);
+        assert_eq!(
+            client.call(3, "unsubscribe", Value::Null)["result"],
+            json!([])
+        );
+        client
+    })
+    .await;
+
+    server.shutdown.send(true).unwrap();
+    blocking(move || {
+        let mut line = String::new();
+        assert_eq!(client.reader.read_line(&mut line).unwrap(), 0);
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stale_socket_is_replaced() {
+    let dir = temp_dir("stale");
+    let path = dir.join("control").join("le-chat.sock");
+    drop(control::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let listener = control::bind(&path).unwrap();
+    // A live socket isn't taken over
+    assert!(control::bind(&path).is_err());
+    drop(listener);
+    let _ = std::fs::remove_dir_all(&dir);
+}
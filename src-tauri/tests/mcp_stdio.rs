@@ -0,0 +1,188 @@
+// Drives `le-chat mcp` the way an agent host does: a scripted client writing
+// newline-delimited JSON-RPC into the server's stdin and reading its stdout.
+
+use std::io::{BufRead, BufReader, PipeReader, PipeWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use le_chat_lib::mcp::{self, Backend, Snapshot, CONVERSATION_URI};
+use serde_json::{json, Value};
+
+// Answers like the app would and records the prompts it was sent
+#[derive(Default)]
+struct FakeApp {
+    prompts: Arc<Mutex<Vec<(String, bool)>>>,
+}
+
+impl Backend for FakeApp {
+    fn ask(&mut self, prompt: &str, new_chat: bool) -> Result<String, String> {
+        self.prompts
+            .lock()
+            .unwrap()
+            .push((prompt.to_string(), new_chat));
+        if prompt == "fail" {
+            return Err("Le Chat is offline".to_string());
+        }
+        Ok(format!("You said: **{}**", prompt))
+    }
+
+    fn last_response(&mut self) -> Result<Snapshot, String> {
+        Ok(Snapshot {
+            markdown: "The last answer".to_string(),
+            url: "https://chat.mistral.ai/chat/abc".to_string(),
+            title: "Test".to_string(),
+        })
+    }
+
+    fn conversation(&mut self) -> Result<Snapshot, String> {
+        Ok(Snapshot {
+            markdown: "## You\n\nHi\n\n## Le Chat\n\nHello".to_string(),
+            url: String::new(),
+            title: "Test".to_string(),
+        })
+    }
+}
+
+struct Client {
+    input: Option<PipeWriter>,
+    output: BufReader<PipeReader>,
+    server: JoinHandle<std::io::Result<()>>,
+    next_id: u64,
+}
+
+impl Client {
+    fn start(mut app: FakeApp) -> Self {
+        let (server_in, input) = std::io::pipe().unwrap();
+        let (output, server_out) = std::io::pipe().unwrap();
+        let server =
+            std::thread::spawn(move || mcp::serve(BufReader::new(server_in), server_out, &mut app));
+        Self {
+            input: Some(input),
+            output: BufReader::new(output),
+            server,
+            next_id: 0,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        let input = self.input.as_mut().unwrap();
+        writeln!(input, "{}", message).unwrap();
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let mut line = String::new();
+        self.output.read_line(&mut line).unwrap();
+        let response: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(response["id"], json!(id));
+        response
+    }
+
+    // Closing stdin ends the server
+    fn close(mut self) {
+        drop(self.input.take());
+        self.server.join().unwrap().unwrap();
+        let mut rest = String::new();
+        self.output.read_line(&mut rest).unwrap();
+        assert_eq!(rest, "");
+    }
+}
+
+fn initialize(client: &mut Client) -> Value {
+    let response = client.request(
+        "initialize",
+        json!({
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": { "name": "scripted", "version": "0" }
+        }),
+    );
+    client.send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }));
+    response["result"].clone()
+}
+
+#[test]
+fn handshake_lists_tools_and_resources() {
+    let mut client = Client::start(FakeApp::default());
+    let result = initialize(&mut client);
+    assert_eq!(result["protocolVersion"], "2025-06-18");
+    assert!(result["capabilities"]["tools"].is_object());
+    assert!(result["capabilities"]["resources"].is_object());
+
+    assert_eq!(client.request("ping", Value::Null)["result"], json!({}));
+
+    let tools = client.request("tools/list", json!({}))["result"]["tools"].clone();
+    let names: Vec<_> = tools
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["ask_le_chat", "get_last_response"]);
+    assert_eq!(tools[0]["inputSchema"]["required"], json!(["prompt"]));
+
+    let resources = client.request("resources/list", json!({}))["result"]["resources"].clone();
+    assert_eq!(resources[0]["uri"], CONVERSATION_URI);
+    assert_eq!(resources[0]["mimeType"], "text/markdown");
+    client.close();
+}
+
+#[test]
+fn tools_forward_to_the_app() {
+    let app = FakeApp::default();
+    let prompts = app.prompts.clone();
+    let mut client = Client::start(app);
+    initialize(&mut client);
+
+    let result = client.request(
+        "tools/call",
+        json!({ "name": "ask_le_chat", "arguments": { "prompt": "hi" } }),
+    )["result"]
+        .clone();
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["text"], "You said: **hi**");
+
+    let result = client.request(
+        "tools/call",
+        json!({ "name": "ask_le_chat", "arguments": { "prompt": "fail", "new_chat": false } }),
+    )["result"]
+        .clone();
+    assert_eq!(result["isError"], true);
+    assert_eq!(result["content"][0]["text"], "Le Chat is offline");
+
+    let result =
+        client.request("tools/call", json!({ "name": "get_last_response" }))["result"].clone();
+    assert_eq!(
+        result["content"][0]["text"],
+        "The last answer\n\n<https://chat.mistral.ai/chat/abc>"
+    );
+
+    assert_eq!(
+        *prompts.lock().unwrap(),
+        [("hi".to_string(), true), ("fail".to_string(), false)]
+    );
+    client.close();
+}
+
+#[test]
+fn conversation_is_a_resource() {
+    let mut client = Client::start(FakeApp::default());
+    initialize(&mut client);
+    let response = client.request("resources/read", json!({ "uri": CONVERSATION_URI }));
+    assert_eq!(
+        response["result"]["contents"][0],
+        json!({
+            "uri": CONVERSATION_URI,
+            "mimeType": "text/markdown",
+            "text": "## You\n\nHi\n\n## Le Chat\n\nHello"
+        })
+    );
+
+    let response = client.request("resources/read", json!({ "uri": "lechat://other" }));
+    assert_eq!(response["error"]["code"], -32602);
+    let response = client.request("prompts/list", json!({}));
+    assert_eq!(response["error"]["code"], -32601);
+    client.close();
+}
@@ -2,6 +2,73 @@
 const { invoke } = window.__TAURI__.core;
 const { listen } = window.__TAURI__.event;
 
+// Markup kept in inline answers, the same allowlist as `sanitize` in html.rs.
+// Answers are sanitized in Rust too, but events can come from any window that
+// is allowed to emit them, so the launcher checks again before rendering.
+const ANSWER_ELEMENTS = new Set([
+  'a', 'b', 'blockquote', 'br', 'code', 'del', 'em', 'h1', 'h2', 'h3', 'h4', 'h5', 'h6',
+  'hr', 'i', 'img', 'li', 'ol', 'p', 'pre', 's', 'strong', 'sub', 'sup', 'table', 'tbody',
+  'td', 'tfoot', 'th', 'thead', 'tr', 'ul',
+]);
+// Dropped together with their content; anything else unknown is unwrapped
+const DROPPED_ANSWER_ELEMENTS = new Set([
+  'button', 'form', 'iframe', 'input', 'noscript', 'object', 'script', 'select', 'style',
+  'svg', 'template', 'textarea',
+]);
+
+function isSafeUrl(url, allowDataImages) {
+  const value = url.trim().toLowerCase();
+  return value.startsWith('https://') || value.startsWith('http://') || value.startsWith('mailto:')
+    || (allowDataImages && value.startsWith('data:image/'));
+}
+
+function isAllowedAttribute(tag, name, value) {
+  switch (`${tag} ${name}`) {
+    case 'a href': return isSafeUrl(value, false);
+    case 'img src': return isSafeUrl(value, true);
+    case 'img alt':
+    case 'th align':
+    case 'td align': return true;
+    case 'code class': return value.split(/\s+/).some(c => c.startsWith('language-'));
+    case 'ol start': return /^\d+$/.test(value);
+    default: return false;
+  }
+}
+
+// Copy the allowed parts of `source` into new nodes of this document
+function sanitizeAnswerNodes(source, target) {
+  for (const node of source.childNodes) {
+    if (node.nodeType === Node.TEXT_NODE) {
+      target.appendChild(document.createTextNode(node.textContent));
+      continue;
+    }
+    if (node.nodeType !== Node.ELEMENT_NODE) continue;
+    const tag = node.localName;
+    if (DROPPED_ANSWER_ELEMENTS.has(tag)) continue;
+    if (!ANSWER_ELEMENTS.has(tag) || node.namespaceURI !== 'http://www.w3.org/1999/xhtml') {
+      sanitizeAnswerNodes(node, target);
+      continue;
+    }
+    const element = document.createElement(tag);
+    for (const { name, value } of node.attributes) {
+      if (isAllowedAttribute(tag, name, value)) {
+        element.setAttribute(name, value);
+      }
+    }
+    sanitizeAnswerNodes(node, element);
+    target.appendChild(element);
+  }
+}
+
+// Answer markup as a fragment that can't run scripts or restyle the launcher.
+// DOMParser builds an inert document, so nothing in `html` runs or loads.
+function renderAnswer(html) {
+  const parsed = new DOMParser().parseFromString(html || '', 'text/html');
+  const fragment = document.createDocumentFragment();
+  sanitizeAnswerNodes(parsed.body, fragment);
+  return fragment;
+}
+
 // Launcher App Class - Encapsulates all launcher functionality
 class LauncherApp {
   constructor() {
@@ -9,11 +76,30 @@ class LauncherApp {
     this.input = document.getElementById('launcher-input');
     this.submitBtn = document.getElementById('submit-btn');
     this.newChatToggle = document.getElementById('new-chat-toggle');
+    this.statusEl = document.getElementById('launcher-status');
+    this.queueBtn = document.getElementById('launcher-queue');
+    this.queueItems = document.getElementById('launcher-queue-items');
+    this.inlineToggle = document.getElementById('inline-toggle');
+    this.backendBadge = document.getElementById('launcher-backend');
+    this.profileSelect = document.getElementById('launcher-profile');
+    this.container = document.querySelector('.launcher-container');
+    this.answerPanel = document.getElementById('launcher-answer');
+    this.answerBody = document.getElementById('launcher-answer-body');
+    this.copyBtn = document.getElementById('answer-copy');
+    this.followUpBtn = document.getElementById('answer-follow-up');
+    this.openBtn = document.getElementById('answer-open');
     
     // State
     this.focusTimeout = null;
     this.isSubmitting = false;
     this.newChatMode = true; // Default: start new conversations
+    this.latestSubmissionId = null;
+    this.sentMessages = new Map(); // submission id -> message, restored if sending fails
+    this.inlineMode = false; // Default: replies open in the main window
+    this.activeProfile = 'default'; // Profile open in the main window
+    this.apiMode = false; // Prompts go to the Mistral API and are always answered inline
+    this.answerSubmissionId = null; // Submission whose reply is shown inline
+    this.answerMarkdown = '';
     
     // Constants
     this.MAX_MESSAGE_LENGTH = 5000;
@@ -42,10 +128,13 @@ class LauncherApp {
       el.textContent = modLabel;
     });
     
-    // Update the new-chat toggle tooltip
+    // Update the toggle tooltips
     if (this.newChatToggle) {
       this.newChatToggle.title = `Start a new conversation (${modLabel}+N)`;
     }
+    if (this.inlineToggle) {
+      this.inlineToggle.title = `Show the answer here (${modLabel}+I)`;
+    }
   }
   
   initEventListeners() {
@@ -58,11 +147,38 @@ class LauncherApp {
     // Submit button
     this.submitBtn.addEventListener('click', () => this.submitMessage());
     
+    // Cancel queued messages
+    if (this.queueBtn) {
+      this.queueBtn.addEventListener('click', () => this.clearQueue());
+    }
+    
     // New chat toggle
     if (this.newChatToggle) {
       this.newChatToggle.addEventListener('click', () => this.toggleNewChat());
     }
     
+    // Profile picker: switches the main window to that profile's account
+    if (this.profileSelect) {
+      this.profileSelect.addEventListener('change', () => this.switchProfile());
+    }
+    
+    // Inline answer toggle and actions
+    if (this.inlineToggle) {
+      this.inlineToggle.addEventListener('click', () => this.setInlineMode(!this.inlineMode));
+    }
+    if (this.copyBtn) {
+      this.copyBtn.addEventListener('click', () => this.copyAnswer());
+    }
+    if (this.followUpBtn) {
+      this.followUpBtn.addEventListener('click', () => this.followUp());
+    }
+    if (this.openBtn) {
+      this.openBtn.addEventListener('click', () => this.openInMainWindow());
+    }
+    if (this.answerBody) {
+      this.answerBody.addEventListener('click', (e) => this.handleAnswerClick(e));
+    }
+    
     // Window focus
     window.addEventListener('focus', () => this.handleWindowFocus());
     
@@ -71,11 +187,13 @@ class LauncherApp {
   }
   
   initTauriListeners() {
-    // Load new-chat default from settings
+    // Load new-chat and inline defaults from settings
     this.loadNewChatDefault();
     
     // Listen for launcher-shown event from Rust to clear and focus input
     listen('launcher-shown', () => {
+      this.collapseAnswer();
+      this.clearLinkPrompt();
       if (this.input) {
         this.input.value = '';
         this.input.focus();
@@ -86,6 +204,12 @@ class LauncherApp {
       console.error('Failed to listen for launcher-shown event:', error);
     });
     
+    // A lechat://ask link filled in a prompt; it's only sent once the user confirms
+    listen('link-prompt', () => this.loadLinkPrompt()).catch(error => {
+      console.error('Failed to listen for link-prompt event:', error);
+    });
+    this.loadLinkPrompt();
+    
     // Listen for settings-changed event
     listen('settings-changed', (event) => {
       const settings = event.payload;
@@ -100,23 +224,194 @@ class LauncherApp {
             : 'Continue current chat...';
         }
       }
+      if (settings && typeof settings.inline_answers === 'boolean') {
+        this.setInlineMode(settings.inline_answers);
+      }
+      if (settings) {
+        this.setApiMode(settings.launcher_backend === 'api', settings.api?.model);
+        this.showProfiles(settings.profiles, settings.active_profile);
+      }
     }).catch(error => {
       console.error('Failed to listen for settings-changed event:', error);
     });
     
-    // Listen for inject-result from the main window's injected JS
-    listen('inject-result', (event) => {
-      const { success, error } = event.payload || {};
-      if (!success && error) {
-        console.error('[Launcher] Message injection failed:', error);
-        this.showError(error);
+    // Replies to inline submissions, streamed from the hidden main window
+    listen('inline-answer', (event) => {
+      this.showAnswer(event.payload);
+    }).catch(error => {
+      console.error('Failed to listen for inline-answer event:', error);
+    });
+    
+    // Progress of submissions, keyed by the id returned from submit_message
+    listen('submission-status', (event) => {
+      const submission = event.payload;
+      if (!submission) return;
+      // Only the most recent submission is shown
+      if (this.latestSubmissionId === null || submission.id >= this.latestSubmissionId) {
+        this.latestSubmissionId = submission.id;
+        this.showSubmissionStatus(submission);
       }
+      this.handleSubmissionOutcome(submission);
     }).catch(error => {
-      console.error('Failed to listen for inject-result event:', error);
+      console.error('Failed to listen for submission-status event:', error);
     });
+    
+    // Messages waiting for the current response to finish
+    listen('submission-queue', (event) => {
+      this.showQueue(event.payload || []);
+    }).catch(error => {
+      console.error('Failed to listen for submission-queue event:', error);
+    });
+    invoke('get_submission_queue')
+      .then(queue => this.showQueue(queue))
+      .catch(error => console.error('Failed to load submission queue:', error));
+    
+    // Whether Le Chat can take messages right now
+    listen('main-state-changed', (event) => {
+      this.showMainState(event.payload);
+    }).catch(error => {
+      console.error('Failed to listen for main-state-changed event:', error);
+    });
+    invoke('get_main_state')
+      .then(info => this.showMainState(info))
+      .catch(error => console.error('Failed to load main window state:', error));
+  }
+  
+  async loadLinkPrompt() {
+    let linkPrompt = null;
+    try {
+      linkPrompt = await invoke('take_link_prompt');
+    } catch (error) {
+      console.error('Failed to get link prompt:', error);
+    }
+    if (!linkPrompt || !this.input) return;
+    this.collapseAnswer();
+    if (typeof linkPrompt.new_chat === 'boolean' && linkPrompt.new_chat !== this.newChatMode) {
+      this.toggleNewChat();
+    }
+    this.input.value = linkPrompt.prompt;
+    this.input.classList.add('from-link');
+    this.input.focus();
+    this.input.setSelectionRange(this.input.value.length, this.input.value.length);
+    if (this.statusEl) {
+      const from = linkPrompt.source === 'browser' ? 'From your browser' : 'From a link';
+      this.statusEl.textContent = `${from} — check the prompt, then press ⏎ to send`;
+      this.statusEl.title = linkPrompt.prompt;
+      this.statusEl.dataset.source = 'link';
+      this.statusEl.classList.remove('failed');
+    }
+  }
+  
+  clearLinkPrompt() {
+    this.input?.classList.remove('from-link');
+    if (this.statusEl?.dataset.source === 'link') {
+      this.statusEl.textContent = '';
+      this.statusEl.title = '';
+      delete this.statusEl.dataset.source;
+    }
+  }
+  
+  showMainState(info) {
+    if (!this.statusEl || !info) return;
+    const notices = {
+      offline: 'Le Chat is offline — messages are saved until it\'s back',
+      signed_out: 'Signed out of Le Chat',
+    };
+    const notice = notices[info.state];
+    if (notice) {
+      this.statusEl.textContent = notice;
+      this.statusEl.dataset.source = 'main-state';
+      this.statusEl.classList.remove('failed');
+    } else if (this.statusEl.dataset.source === 'main-state') {
+      this.statusEl.textContent = '';
+      delete this.statusEl.dataset.source;
+    }
+  }
+  
+  // Re-show the launcher with the message if it couldn't be sent
+  handleSubmissionOutcome(submission) {
+    const message = this.sentMessages.get(submission.id);
+    if (message === undefined) return;
+    
+    if (submission.status === 'failed' || submission.status === 'outboxed') {
+      // The reply will never show up here
+      if (submission.id === this.answerSubmissionId) {
+        this.collapseAnswer();
+      }
+    }
+    
+    if (submission.status === 'failed') {
+      this.sentMessages.delete(submission.id);
+      this.showError(submission.error || 'Message could not be sent').then(() => {
+        if (this.input && !this.input.value) {
+          this.input.value = message;
+        }
+      });
+    } else if (submission.status !== 'queued' && submission.status !== 'injected') {
+      this.sentMessages.delete(submission.id);
+    }
+  }
+  
+  showQueue(queue) {
+    if (!this.queueBtn) return;
+    // One button per waiting message, and one for all of them
+    if (this.queueItems) {
+      this.queueItems.replaceChildren(...queue.map(submission => {
+        const item = document.createElement('button');
+        item.type = 'button';
+        item.className = 'launcher-queue launcher-queue-item';
+        item.textContent = `${submission.preview} ✕`;
+        item.title = `Cancel: ${submission.preview}`;
+        item.addEventListener('click', () => this.cancelQueued(submission.id));
+        return item;
+      }));
+    }
+    this.queueBtn.hidden = queue.length < 2;
+    this.queueBtn.textContent = `${queue.length} queued ✕`;
+    this.queueBtn.title = ['Cancel queued messages:', ...queue.map(s => s.preview)].join('\n');
+  }
+  
+  async cancelQueued(id) {
+    try {
+      await invoke('cancel_submission', { id });
+    } catch (error) {
+      // Already on its way; the queue event will drop it from the list
+      console.error('Failed to cancel queued message:', error);
+    }
+    this.focusInput();
+  }
+  
+  async clearQueue() {
+    try {
+      await invoke('clear_submission_queue');
+    } catch (error) {
+      console.error('Failed to cancel queued messages:', error);
+    }
+    this.focusInput();
+  }
+  
+  showSubmissionStatus(submission) {
+    if (!this.statusEl) return;
+    const labels = {
+      queued: 'Queued',
+      injected: 'Sending…',
+      sent: 'Sent',
+      answered: 'Answered',
+      failed: 'Failed',
+      cancelled: 'Cancelled',
+      outboxed: 'Offline — saved to outbox',
+    };
+    const label = labels[submission.status] || submission.status;
+    this.statusEl.textContent = submission.error && submission.status === 'failed'
+      ? `${label}: ${submission.error}`
+      : label;
+    this.statusEl.title = submission.preview;
+    this.statusEl.dataset.source = 'submission';
+    this.statusEl.classList.toggle('failed', submission.status === 'failed');
   }
   
   async showError(errorMessage) {
+    console.error('[Launcher] Message submission failed:', errorMessage);
     // Briefly re-show the launcher with an error state
     try {
       await invoke('show_launcher');
@@ -152,6 +447,9 @@ class LauncherApp {
     try {
       const settings = await invoke('get_settings');
       this.newChatMode = settings.new_chat_default ?? true;
+      this.setInlineMode(settings.inline_answers ?? false);
+      this.setApiMode(settings.launcher_backend === 'api', settings.api?.model);
+      this.showProfiles(settings.profiles, settings.active_profile);
       if (this.newChatToggle) {
         this.newChatToggle.classList.toggle('active', this.newChatMode);
       }
@@ -166,6 +464,35 @@ class LauncherApp {
     }
   }
   
+  // Only shown once there is more than the default profile
+  showProfiles(profiles, active) {
+    if (!this.profileSelect) return;
+    const list = profiles || [];
+    this.profileSelect.hidden = list.length === 0;
+    const options = [{ id: 'default', name: 'Default' }, ...list].map(profile => {
+      const option = document.createElement('option');
+      option.value = profile.id;
+      option.textContent = profile.name;
+      return option;
+    });
+    this.profileSelect.replaceChildren(...options);
+    this.profileSelect.value = active ?? 'default';
+    this.activeProfile = this.profileSelect.value;
+  }
+  
+  async switchProfile() {
+    const profile = this.profileSelect.value;
+    try {
+      await invoke('switch_profile', { profile });
+      this.activeProfile = profile;
+    } catch (error) {
+      console.error('Failed to switch profile:', error);
+      this.profileSelect.value = this.activeProfile;
+      await this.showError(typeof error === 'string' ? error : error.message);
+    }
+    this.focusInput();
+  }
+  
   focusInput() {
     if (this.input) {
       this.input.focus();
@@ -190,6 +517,13 @@ class LauncherApp {
         return;
       }
       
+      // Cmd/Ctrl+I to toggle inline answers
+      if (e.key === 'i' && (e.metaKey || e.ctrlKey)) {
+        e.preventDefault();
+        this.setInlineMode(!this.inlineMode);
+        return;
+      }
+      
       // Enter to submit
       if (e.key === 'Enter' && !e.shiftKey && this.input) {
         e.preventDefault();
@@ -237,6 +571,121 @@ class LauncherApp {
     }
   }
   
+  setInlineMode(enabled) {
+    this.inlineMode = enabled;
+    if (this.inlineToggle) {
+      this.inlineToggle.classList.toggle('active', this.inlineMode);
+    }
+  }
+  
+  setApiMode(enabled, model) {
+    this.apiMode = enabled;
+    if (this.inlineToggle) {
+      this.inlineToggle.hidden = enabled;
+    }
+    if (this.backendBadge) {
+      this.backendBadge.hidden = !enabled;
+      this.backendBadge.textContent = model ? `API · ${model}` : 'API';
+    }
+  }
+  
+  // Grow the window into an answer panel for the given submission
+  async expandAnswer(submissionId) {
+    this.answerSubmissionId = submissionId;
+    this.answerMarkdown = '';
+    if (this.answerBody) {
+      this.answerBody.textContent = 'Waiting for Le Chat…';
+      this.answerBody.classList.add('pending');
+    }
+    if (this.copyBtn) {
+      this.copyBtn.disabled = true;
+    }
+    if (this.openBtn) {
+      // API conversations don't exist in Le Chat
+      this.openBtn.hidden = this.apiMode;
+    }
+    try {
+      await invoke('set_launcher_expanded', { expanded: true });
+    } catch (error) {
+      console.error('Failed to expand launcher:', error);
+    }
+    this.container?.classList.add('expanded');
+    if (this.answerPanel) {
+      this.answerPanel.hidden = false;
+    }
+  }
+  
+  async collapseAnswer() {
+    this.answerSubmissionId = null;
+    this.answerMarkdown = '';
+    if (!this.container?.classList.contains('expanded')) return;
+    this.container.classList.remove('expanded');
+    if (this.answerPanel) {
+      this.answerPanel.hidden = true;
+    }
+    try {
+      await invoke('set_launcher_expanded', { expanded: false });
+    } catch (error) {
+      console.error('Failed to collapse launcher:', error);
+    }
+  }
+  
+  showAnswer(answer) {
+    if (!answer || answer.submission_id !== this.answerSubmissionId || !this.answerBody) return;
+    // Stay at the bottom while the reply grows, unless the user scrolled up
+    const body = this.answerBody;
+    const atBottom = body.scrollHeight - body.scrollTop - body.clientHeight < 24;
+    body.replaceChildren(renderAnswer(answer.html));
+    body.classList.remove('pending');
+    if (atBottom) {
+      body.scrollTop = body.scrollHeight;
+    }
+    this.answerMarkdown = typeof answer.markdown === 'string' ? answer.markdown : '';
+    if (this.copyBtn) {
+      this.copyBtn.disabled = !answer.markdown;
+    }
+  }
+  
+  async copyAnswer() {
+    if (!this.answerMarkdown || !this.copyBtn) return;
+    try {
+      await navigator.clipboard.writeText(this.answerMarkdown);
+      this.copyBtn.textContent = 'Copied';
+      setTimeout(() => {
+        this.copyBtn.textContent = 'Copy';
+      }, 1500);
+    } catch (error) {
+      console.error('Failed to copy answer:', error);
+    }
+  }
+  
+  // Ask another question in the same conversation
+  followUp() {
+    if (this.newChatMode) {
+      this.toggleNewChat();
+    }
+    this.focusInput();
+  }
+  
+  async openInMainWindow() {
+    try {
+      await invoke('show_main_window');
+      await invoke('hide_launcher');
+    } catch (error) {
+      console.error('Failed to open main window:', error);
+    }
+  }
+  
+  // Links in the answer open in the browser rather than in the launcher
+  handleAnswerClick(e) {
+    const link = e.target.closest('a[href]');
+    if (!link) return;
+    e.preventDefault();
+    invoke('plugin:opener|open_url', { url: link.href }).catch(error => {
+      console.error('Failed to open link:', error);
+    });
+  }
+  
   async submitMessage() {
     // Prevent multiple submissions
     if (this.isSubmitting) {
@@ -277,29 +726,33 @@ class LauncherApp {
       
       // Clear input only after successful validation
       this.input.value = '';
+      this.clearLinkPrompt();
       
-      // Send message to Rust backend with timeout
+      // Queue the message in the Rust backend. Delivery is reported through
+      // 'submission-status' events for the returned submission id.
       const newChat = this.newChatMode;
-      const submitPromise = invoke('submit_message', { message, newChat });
-      
-      // Add timeout to prevent hanging
-      const timeoutPromise = new Promise((_, reject) => {
-        setTimeout(() => reject(new Error('Submit message timeout')), 10000);
-      });
-      
-      await Promise.race([submitPromise, timeoutPromise]);
+      const inline = this.inlineMode;
+      const profile = this.profileSelect && !this.profileSelect.hidden
+        ? this.profileSelect.value
+        : null;
+      const submission = await invoke('submit_message', { message, newChat, inline, profile });
+      this.sentMessages.set(submission.id, message);
+      if (submission.inline) {
+        await this.expandAnswer(submission.id);
+      }
       
     } catch (error) {
       console.error('Failed to submit message:', error);
       
+      const reason = typeof error === 'string' ? error : error.message;
+      if (originalMessage) {
+        await this.showError(reason);
+      }
+      
       // Restore message on error
       if (this.input && originalMessage) {
         this.input.value = originalMessage;
       }
-      
-      if (error.message === 'Submit message timeout') {
-        console.error('Message submission timed out');
-      }
     } finally {
       this.isSubmitting = false;
       this.submitBtn.classList.remove('launcher-submitting');
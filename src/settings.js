@@ -2,12 +2,52 @@
 const { invoke } = window.__TAURI__.core;
 const { listen } = window.__TAURI__.event;
 
+// Keys that only act as modifiers while recording a shortcut
+const MODIFIER_CODES = new Set([
+  'ShiftLeft', 'ShiftRight', 'ControlLeft', 'ControlRight',
+  'AltLeft', 'AltRight', 'MetaLeft', 'MetaRight',
+]);
+
 class SettingsApp {
   constructor() {
     this.newChatDefault = document.getElementById('new-chat-default');
+    this.inlineAnswers = document.getElementById('inline-answers');
     this.notificationsEnabled = document.getElementById('notifications-enabled');
+    this.openExternal = document.getElementById('open-external');
+    this.allowedDomains = document.getElementById('allowed-domains');
+    this.linksError = document.getElementById('links-error');
+    this.controlSocket = document.getElementById('control-socket');
+    this.controlSocketPath = document.getElementById('control-socket-path');
+    this.launcherBackend = document.getElementById('launcher-backend');
+    this.apiKey = document.getElementById('api-key');
+    this.apiKeySave = document.getElementById('api-key-save');
+    this.apiKeyDelete = document.getElementById('api-key-delete');
+    this.apiKeyStatus = document.getElementById('api-key-status');
+    this.apiKeyError = document.getElementById('api-key-error');
+    this.apiBaseUrl = document.getElementById('api-base-url');
+    this.apiModel = document.getElementById('api-model');
+    this.apiModelsRefresh = document.getElementById('api-models-refresh');
+    this.apiModelError = document.getElementById('api-model-error');
+    this.shortcutInputs = document.querySelectorAll('.shortcut-input');
+    this.pageScriptsList = document.getElementById('page-scripts-list');
+    this.pageScriptToggles = [];
+    this.hooksList = document.getElementById('hooks-list');
+    this.hooksError = document.getElementById('hooks-error');
+    this.hookAdd = document.getElementById('hook-add');
+    this.hooks = [];
+    this.scheduledList = document.getElementById('scheduled-list');
+    this.scheduledError = document.getElementById('scheduled-error');
+    this.scheduledAdd = document.getElementById('scheduled-add');
+    this.missedRuns = document.getElementById('missed-runs');
+    this.scheduledPrompts = [];
+    this.profilesList = document.getElementById('profiles-list');
+    this.profilesError = document.getElementById('profiles-error');
+    this.profileAdd = document.getElementById('profile-add');
+    this.profiles = [];
+    this.activeProfile = 'default';
 
     this.initEventListeners();
+    this.initTauriListeners();
     this.loadSettings();
   }
 
@@ -15,9 +55,58 @@ class SettingsApp {
     if (this.newChatDefault) {
       this.newChatDefault.addEventListener('change', () => this.saveSettings());
     }
+    if (this.inlineAnswers) {
+      this.inlineAnswers.addEventListener('change', () => this.saveSettings());
+    }
     if (this.notificationsEnabled) {
       this.notificationsEnabled.addEventListener('change', () => this.saveSettings());
     }
+    if (this.controlSocket) {
+      this.controlSocket.addEventListener('change', () => this.saveSettings());
+    }
+    [this.launcherBackend, this.apiBaseUrl, this.apiModel, this.missedRuns, this.openExternal, this.allowedDomains].forEach(el => {
+      el?.addEventListener('change', () => this.saveSettings());
+    });
+    this.apiKeySave?.addEventListener('click', () => this.saveApiKey());
+    this.apiKey?.addEventListener('keydown', (e) => {
+      if (e.key === 'Enter') this.saveApiKey();
+    });
+    this.apiKeyDelete?.addEventListener('click', () => this.deleteApiKey());
+    this.apiModelsRefresh?.addEventListener('click', () => this.loadApiModels());
+    this.hookAdd?.addEventListener('click', () => {
+      this.hooks.push({ event: 'response-complete', kind: 'command', command: '', timeout_secs: 10, enabled: true });
+      this.renderHooks();
+      this.hooksList?.querySelector('.hook-row:last-child .setting-input')?.focus();
+    });
+    this.scheduledAdd?.addEventListener('click', () => {
+      this.scheduledPrompts.push({ name: '', schedule: '', prompt: '', enabled: true });
+      this.renderScheduledPrompts();
+      this.scheduledList?.querySelector('.scheduled-row:last-child .setting-input')?.focus();
+    });
+    this.profileAdd?.addEventListener('click', () => {
+      this.profiles.push({ id: '', name: '' });
+      this.renderProfiles();
+      this.profilesList?.querySelector('.profile-row:last-child .setting-input')?.focus();
+    });
+    this.shortcutInputs.forEach(input => {
+      input.addEventListener('keydown', (e) => this.recordShortcut(e, input));
+      input.addEventListener('focus', () => input.classList.add('recording'));
+      input.addEventListener('blur', () => input.classList.remove('recording'));
+    });
+  }
+
+  initTauriListeners() {
+    // Shortcut registration results, e.g. after the app re-registers on startup
+    listen('shortcut-status', (event) => {
+      this.showShortcutFailures(event.payload || []);
+    }).catch(error => {
+      console.error('Failed to listen for shortcut-status event:', error);
+    });
+
+    // Per-profile settings change with the open profile
+    listen('profile-changed', () => this.loadSettings()).catch(error => {
+      console.error('Failed to listen for profile-changed event:', error);
+    });
   }
 
   async loadSettings() {
@@ -26,24 +115,536 @@ class SettingsApp {
       if (this.newChatDefault) {
         this.newChatDefault.checked = settings.new_chat_default ?? true;
       }
+      if (this.inlineAnswers) {
+        this.inlineAnswers.checked = settings.inline_answers ?? false;
+      }
       if (this.notificationsEnabled) {
         this.notificationsEnabled.checked = settings.notifications_enabled ?? true;
       }
+      const navigation = settings.navigation || {};
+      if (this.openExternal) {
+        this.openExternal.checked = navigation.open_external ?? true;
+      }
+      if (this.allowedDomains) {
+        this.allowedDomains.value = (navigation.allowed_domains || []).join(', ');
+      }
+      if (this.controlSocket) {
+        this.controlSocket.checked = settings.control_socket ?? false;
+      }
+      if (this.launcherBackend) {
+        this.launcherBackend.value = settings.launcher_backend ?? 'webview';
+      }
+      const api = settings.api || {};
+      if (this.apiBaseUrl) {
+        this.apiBaseUrl.value = api.base_url ?? '';
+      }
+      this.setModelOptions([], api.model);
+      this.hooks = settings.hooks || [];
+      this.renderHooks();
+      this.scheduledPrompts = settings.scheduled_prompts || [];
+      this.renderScheduledPrompts();
+      this.profiles = settings.profiles || [];
+      this.activeProfile = settings.active_profile ?? 'default';
+      this.renderProfiles();
+      if (this.missedRuns) {
+        this.missedRuns.value = settings.missed_runs ?? 'catch_up';
+      }
+      const shortcuts = settings.shortcuts || {};
+      this.shortcutInputs.forEach(input => {
+        input.value = shortcuts[input.dataset.action] ?? '';
+      });
     } catch (error) {
       console.error('Failed to load settings:', error);
     }
+
+    await this.loadPageScripts();
+    await this.loadApiKeyStatus();
+    await this.loadControlSocketPath();
+
+    try {
+      const failures = await invoke('get_shortcut_failures');
+      this.showShortcutFailures(failures);
+    } catch (error) {
+      console.error('Failed to load shortcut status:', error);
+    }
+  }
+
+  // Render one toggle per page script available on this platform
+  async loadPageScripts() {
+    if (!this.pageScriptsList) return;
+    try {
+      const scripts = await invoke('get_page_scripts');
+      this.pageScriptsList.replaceChildren();
+      this.pageScriptToggles = scripts.map(script => {
+        const id = `page-script-${script.name}`;
+        const row = document.createElement('div');
+        row.className = 'setting-row';
+
+        const info = document.createElement('div');
+        info.className = 'setting-info';
+        const label = document.createElement('label');
+        label.htmlFor = id;
+        label.textContent = script.name;
+        const description = document.createElement('p');
+        description.className = 'setting-description';
+        description.textContent = script.description;
+        info.append(label, description);
+
+        const toggle = document.createElement('label');
+        toggle.className = 'toggle-switch';
+        const checkbox = document.createElement('input');
+        checkbox.type = 'checkbox';
+        checkbox.id = id;
+        checkbox.dataset.script = script.name;
+        checkbox.checked = script.enabled;
+        checkbox.addEventListener('change', () => this.saveSettings());
+        const slider = document.createElement('span');
+        slider.className = 'toggle-slider';
+        toggle.append(checkbox, slider);
+
+        row.append(info, toggle);
+        this.pageScriptsList.append(row);
+        return checkbox;
+      });
+    } catch (error) {
+      console.error('Failed to load page scripts:', error);
+    }
+  }
+
+  // One row per hook: event, what to run, target, timeout and a remove button
+  renderHooks() {
+    if (!this.hooksList) return;
+    const option = (value, text) => {
+      const el = document.createElement('option');
+      el.value = value;
+      el.textContent = text;
+      return el;
+    };
+    this.hooksList.replaceChildren(...this.hooks.map((hook, index) => {
+      const row = document.createElement('div');
+      row.className = 'setting-row hook-row';
+
+      const event = document.createElement('select');
+      event.className = 'setting-select';
+      event.setAttribute('aria-label', 'Event');
+      event.append(
+        option('response-complete', 'Response complete'),
+        option('submission', 'Prompt submitted'),
+        option('inject-failed', 'Prompt not delivered'),
+        option('offline', 'Went offline'),
+        option('online', 'Back online'),
+      );
+      event.value = hook.event;
+
+      const kind = document.createElement('select');
+      kind.className = 'setting-select';
+      kind.setAttribute('aria-label', 'Action');
+      kind.append(option('command', 'Run command'), option('post', 'POST to URL'));
+      kind.value = hook.kind;
+
+      const target = document.createElement('input');
+      target.type = 'text';
+      target.className = 'setting-input';
+      target.spellcheck = false;
+      const updateTarget = () => {
+        target.placeholder = kind.value === 'post' ? 'http://localhost:8080/hook' : 'notes-append --tag le-chat';
+        target.value = (kind.value === 'post' ? hook.url : hook.command) ?? '';
+      };
+      updateTarget();
+
+      const timeout = document.createElement('input');
+      timeout.type = 'number';
+      timeout.min = '1';
+      timeout.max = '300';
+      timeout.className = 'setting-input hook-timeout';
+      timeout.title = 'Timeout in seconds';
+      timeout.value = hook.timeout_secs ?? 10;
+
+      const remove = document.createElement('button');
+      remove.type = 'button';
+      remove.className = 'setting-button';
+      remove.textContent = 'Remove';
+      remove.addEventListener('click', () => {
+        this.hooks.splice(index, 1);
+        this.renderHooks();
+        this.saveSettings();
+      });
+
+      const update = () => {
+        const next = { event: event.value, kind: kind.value, timeout_secs: Number(timeout.value) || 10, enabled: hook.enabled ?? true };
+        next[kind.value === 'post' ? 'url' : 'command'] = target.value.trim();
+        this.hooks[index] = next;
+        this.saveSettings();
+      };
+      event.addEventListener('change', update);
+      kind.addEventListener('change', () => {
+        hook = { ...hook, kind: kind.value };
+        updateTarget();
+        update();
+      });
+      target.addEventListener('change', update);
+      timeout.addEventListener('change', update);
+
+      row.append(event, kind, target, timeout, remove);
+      return row;
+    }));
+  }
+
+  // Hooks still being filled in aren't saved yet
+  collectHooks() {
+    return this.hooks.filter(hook => (hook.kind === 'post' ? hook.url : hook.command)?.trim());
+  }
+
+  // One row per scheduled prompt: name, schedule, on/off and remove, with the prompt below
+  renderScheduledPrompts() {
+    if (!this.scheduledList) return;
+    this.scheduledList.replaceChildren(...this.scheduledPrompts.map((scheduled, index) => {
+      const row = document.createElement('div');
+      row.className = 'setting-row scheduled-row';
+
+      const input = (label, placeholder, value) => {
+        const el = document.createElement('input');
+        el.type = 'text';
+        el.className = 'setting-input';
+        el.spellcheck = false;
+        el.placeholder = placeholder;
+        el.setAttribute('aria-label', label);
+        el.value = value ?? '';
+        return el;
+      };
+      const name = input('Name', 'Standup', scheduled.name);
+      name.classList.add('scheduled-name');
+      const schedule = input('Schedule', 'every weekday 9:00', scheduled.schedule);
+      const prompt = input('Prompt', 'Summarise these notes: {file:~/standup.md}', scheduled.prompt);
+      prompt.classList.add('scheduled-prompt');
+
+      const enabled = document.createElement('input');
+      enabled.type = 'checkbox';
+      enabled.title = 'Enabled';
+      enabled.checked = scheduled.enabled ?? true;
+
+      const remove = document.createElement('button');
+      remove.type = 'button';
+      remove.className = 'setting-button';
+      remove.textContent = 'Remove';
+      remove.addEventListener('click', () => {
+        this.scheduledPrompts.splice(index, 1);
+        this.renderScheduledPrompts();
+        this.saveSettings();
+      });
+
+      const update = () => {
+        this.scheduledPrompts[index] = {
+          name: name.value.trim(),
+          schedule: schedule.value.trim(),
+          prompt: prompt.value,
+          enabled: enabled.checked,
+        };
+        this.saveSettings();
+      };
+      [name, schedule, prompt, enabled].forEach(el => el.addEventListener('change', update));
+
+      row.append(name, schedule, enabled, remove, prompt);
+      return row;
+    }));
+  }
+
+  // Scheduled prompts still being filled in aren't saved yet
+  collectScheduledPrompts() {
+    return this.scheduledPrompts.filter(scheduled =>
+      scheduled.name?.trim() && scheduled.schedule?.trim() && scheduled.prompt?.trim());
+  }
+
+  // One row per profile: name and remove. The open profile can't be removed.
+  renderProfiles() {
+    if (!this.profilesList) return;
+    this.profilesList.replaceChildren(...this.profiles.map((profile, index) => {
+      const row = document.createElement('div');
+      row.className = 'setting-row profile-row';
+
+      const name = document.createElement('input');
+      name.type = 'text';
+      name.className = 'setting-input';
+      name.spellcheck = false;
+      name.placeholder = 'Work';
+      name.setAttribute('aria-label', 'Profile name');
+      name.value = profile.name ?? '';
+      name.addEventListener('change', () => {
+        this.profiles[index] = { ...this.profiles[index], name: name.value.trim() };
+        this.saveSettings();
+      });
+
+      const open = document.createElement('span');
+      open.className = 'profile-open';
+      open.textContent = 'Open';
+      open.hidden = profile.id !== this.activeProfile;
+
+      const remove = document.createElement('button');
+      remove.type = 'button';
+      remove.className = 'setting-button';
+      remove.textContent = 'Remove';
+      remove.disabled = profile.id === this.activeProfile;
+      remove.title = remove.disabled ? 'Switch to another profile first' : '';
+      remove.addEventListener('click', () => {
+        this.profiles.splice(index, 1);
+        this.renderProfiles();
+        this.saveSettings();
+      });
+
+      row.append(name, open, remove);
+      return row;
+    }));
+  }
+
+  // Named profiles; new ones get an id made from their name
+  collectProfiles() {
+    const named = this.profiles.filter(profile => profile.name?.trim());
+    const taken = new Set(named.map(profile => profile.id).filter(id => id));
+    return named.map(profile => {
+      if (profile.id) return profile;
+      const base = profile.name.toLowerCase().normalize('NFKD')
+        .replace(/[^a-z0-9]+/g, '-').replace(/^-+|-+$/g, '').slice(0, 48) || 'profile';
+      let id = base === 'default' ? 'default-1' : base;
+      for (let n = 2; taken.has(id); n++) id = `${base}-${n}`;
+      taken.add(id);
+      profile.id = id;
+      return profile;
+    });
+  }
+
+  collectNavigation() {
+    return {
+      allowed_domains: (this.allowedDomains?.value ?? '')
+        .split(',')
+        .map(domain => domain.trim())
+        .filter(domain => domain),
+      open_external: this.openExternal?.checked ?? true,
+    };
+  }
+
+  async loadControlSocketPath() {
+    if (!this.controlSocketPath) return;
+    try {
+      this.controlSocketPath.textContent = await invoke('get_control_socket_path');
+    } catch (error) {
+      console.error('Failed to get control socket path:', error);
+    }
+  }
+
+  async loadApiKeyStatus() {
+    try {
+      const hasKey = await invoke('has_api_key');
+      if (this.apiKeyStatus) {
+        this.apiKeyStatus.textContent = hasKey
+          ? 'Stored in the system keyring'
+          : 'Not set — needed for the Mistral API backend';
+      }
+      if (this.apiKey) {
+        this.apiKey.placeholder = hasKey ? 'Replace the stored key' : 'Paste a La Plateforme key';
+      }
+      if (this.apiKeyDelete) {
+        this.apiKeyDelete.hidden = !hasKey;
+      }
+      this.showApiKeyError('');
+    } catch (error) {
+      this.showApiKeyError(`Keyring unavailable: ${error}`);
+    }
+  }
+
+  showApiKeyError(message) {
+    if (this.apiKeyError) {
+      this.apiKeyError.textContent = message;
+    }
+  }
+
+  async saveApiKey() {
+    const key = this.apiKey?.value.trim();
+    if (!key) return;
+    try {
+      await invoke('save_api_key', { key });
+      this.apiKey.value = '';
+      await this.loadApiKeyStatus();
+      await this.loadApiModels();
+    } catch (error) {
+      this.showApiKeyError(`Failed to save the key: ${error}`);
+    }
+  }
+
+  async deleteApiKey() {
+    try {
+      await invoke('delete_api_key');
+      await this.loadApiKeyStatus();
+    } catch (error) {
+      this.showApiKeyError(`Failed to remove the key: ${error}`);
+    }
+  }
+
+  // Fill the model picker, keeping the selected model even if the server doesn't list it
+  setModelOptions(models, selected) {
+    if (!this.apiModel) return;
+    const current = selected ?? this.apiModel.value;
+    const names = [...models];
+    if (current && !names.includes(current)) {
+      names.unshift(current);
+    }
+    this.apiModel.replaceChildren(...names.map(name => {
+      const option = document.createElement('option');
+      option.value = name;
+      option.textContent = name;
+      return option;
+    }));
+    if (current) {
+      this.apiModel.value = current;
+    }
+  }
+
+  async loadApiModels() {
+    if (this.apiModelError) {
+      this.apiModelError.textContent = '';
+    }
+    try {
+      const models = await invoke('list_api_models', { api: this.collectApiSettings() });
+      this.setModelOptions(models);
+    } catch (error) {
+      if (this.apiModelError) {
+        this.apiModelError.textContent = `Couldn't load models: ${error}`;
+      }
+    }
+  }
+
+  collectApiSettings() {
+    return {
+      base_url: this.apiBaseUrl?.value.trim() || 'https://api.mistral.ai/v1',
+      model: this.apiModel?.value || 'mistral-small-latest',
+    };
+  }
+
+  // Build a shortcut string like "Ctrl+Shift+K" from a keydown event
+  recordShortcut(e, input) {
+    if (e.key === 'Tab') {
+      return;
+    }
+    e.preventDefault();
+
+    if (e.key === 'Escape') {
+      input.blur();
+      return;
+    }
+
+    const hasModifier = e.ctrlKey || e.altKey || e.shiftKey || e.metaKey;
+    if (!hasModifier && (e.key === 'Backspace' || e.key === 'Delete')) {
+      input.value = '';
+      this.saveSettings();
+      return;
+    }
+
+    if (MODIFIER_CODES.has(e.code)) {
+      return;
+    }
+
+    const parts = [];
+    if (e.ctrlKey) parts.push('Ctrl');
+    if (e.altKey) parts.push('Alt');
+    if (e.shiftKey) parts.push('Shift');
+    if (e.metaKey) parts.push('Super');
+    parts.push(this.keyName(e.code));
+
+    input.value = parts.join('+');
+    this.saveSettings();
+  }
+
+  keyName(code) {
+    if (code.startsWith('Key')) return code.slice(3);
+    if (code.startsWith('Digit')) return code.slice(5);
+    return code;
+  }
+
+  showShortcutFailures(failures) {
+    document.querySelectorAll('.shortcut-error').forEach(el => {
+      el.textContent = '';
+      el.classList.remove('visible');
+    });
+    this.shortcutInputs.forEach(input => input.classList.remove('invalid'));
+
+    for (const failure of failures) {
+      const errorEl = document.querySelector(`.shortcut-error[data-action="${failure.action}"]`);
+      const input = document.querySelector(`.shortcut-input[data-action="${failure.action}"]`);
+      if (!errorEl) continue;
+
+      input?.classList.add('invalid');
+      errorEl.textContent = failure.fallback
+        ? `${failure.shortcut} is unavailable, using ${failure.fallback} instead.`
+        : `${failure.shortcut}: ${failure.error}.`;
+
+      if (failure.suggestions?.length) {
+        errorEl.append(' Try:');
+        for (const suggestion of failure.suggestions) {
+          const btn = document.createElement('button');
+          btn.type = 'button';
+          btn.className = 'shortcut-suggestion';
+          btn.textContent = suggestion;
+          btn.addEventListener('click', () => {
+            if (input) {
+              input.value = suggestion;
+              this.saveSettings();
+            }
+          });
+          errorEl.append(btn);
+        }
+      }
+      errorEl.classList.add('visible');
+    }
+  }
+
+  collectShortcuts() {
+    const shortcuts = {};
+    this.shortcutInputs.forEach(input => {
+      shortcuts[input.dataset.action] = input.value;
+    });
+    return shortcuts;
+  }
+
+  collectPageScripts() {
+    const pageScripts = {};
+    this.pageScriptToggles.forEach(checkbox => {
+      pageScripts[checkbox.dataset.script] = checkbox.checked;
+    });
+    return pageScripts;
   }
 
   async saveSettings() {
     const settings = {
       new_chat_default: this.newChatDefault?.checked ?? true,
+      inline_answers: this.inlineAnswers?.checked ?? false,
+      launcher_backend: this.launcherBackend?.value ?? 'webview',
+      api: this.collectApiSettings(),
       notifications_enabled: this.notificationsEnabled?.checked ?? true,
+      navigation: this.collectNavigation(),
+      control_socket: this.controlSocket?.checked ?? false,
+      hooks: this.collectHooks(),
+      scheduled_prompts: this.collectScheduledPrompts(),
+      missed_runs: this.missedRuns?.value ?? 'catch_up',
+      shortcuts: this.collectShortcuts(),
+      page_scripts: this.collectPageScripts(),
+      profiles: this.collectProfiles(),
+      active_profile: this.activeProfile,
     };
 
     try {
-      await invoke('save_settings', { settings });
+      const failures = await invoke('save_settings', { settings });
+      this.showShortcutFailures(failures || []);
+      [this.hooksError, this.scheduledError, this.linksError, this.profilesError].forEach(el => {
+        if (el) el.textContent = '';
+      });
     } catch (error) {
       console.error('Failed to save settings:', error);
+      // Invalid hooks, scheduled prompts, domains and profiles are the only settings the backend refuses
+      const message = String(error);
+      const target = message.startsWith('Scheduled prompt') ? this.scheduledError
+        : message.startsWith('Allowed domain') ? this.linksError
+        : message.startsWith('Profile') ? this.profilesError
+        : this.hooksError;
+      if (target) target.textContent = `Not saved: ${error}`;
     }
   }
 }